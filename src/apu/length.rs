@@ -0,0 +1,69 @@
+/**
+ * Length counter shared by all channels, turns the channel off once it reaches zero
+ */
+pub struct LengthCounter {
+    max: u16,
+    counter: u16,
+    enabled: bool,
+}
+
+impl LengthCounter {
+    pub fn new(max: u16) -> Self {
+        LengthCounter {
+            max,
+            counter: 0,
+            enabled: false,
+        }
+    }
+
+    pub fn load(&mut self, value: u8) {
+        self.counter = self.max - value as u16;
+    }
+
+    pub fn get_counter(&self) -> u16 {
+        self.counter
+    }
+
+    pub fn set_counter(&mut self, counter: u16) {
+        self.counter = counter;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /**
+     * Returns true when the counter expires and the channel has to be disabled
+     */
+    pub fn clock(&mut self) -> bool {
+        if self.enabled && self.counter > 0 {
+            self.counter -= 1;
+            return self.counter == 0;
+        }
+        false
+    }
+
+    /**
+     * Enabling the counter while the next frame sequencer step doesn't clock it gives it an extra clock
+     */
+    pub fn set_enabled(&mut self, enabled: bool, next_step_clocks_length: bool) -> bool {
+        let was_enabled = self.enabled;
+        self.enabled = enabled;
+        if !was_enabled && enabled && !next_step_clocks_length {
+            return self.clock();
+        }
+        false
+    }
+
+    /**
+     * Triggering with an expired counter reloads it, losing one step to the same quirk as set_enabled
+     */
+    pub fn trigger(&mut self, next_step_clocks_length: bool) {
+        if self.counter == 0 {
+            self.counter = self.max;
+            if self.enabled && !next_step_clocks_length {
+                self.counter -= 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,264 @@
+mod envelope;
+mod length;
+mod noise;
+mod square;
+mod tests;
+mod wave;
+
+pub use noise::Noise;
+pub use square::Square;
+pub use wave::Wave;
+
+use crate::audio::{AudioOutput, AudioSink};
+
+/**
+ * Audio processing unit, mapped at 0xFF10-0xFF3F
+ */
+pub struct Apu {
+    is_cgb: bool,
+    powered: bool,
+    square1: Square,
+    square2: Square,
+    wave: Wave,
+    noise: Noise,
+    nr50: u8,
+    nr51: u8,
+    frame_step: u8,
+    sample_timer: u8,
+    output: Option<AudioOutput>,
+}
+
+impl Apu {
+    pub fn new(is_cgb: bool) -> Self {
+        Apu {
+            is_cgb,
+            powered: false,
+            square1: Square::new(true),
+            square2: Square::new(false),
+            wave: Wave::new(),
+            noise: Noise::new(),
+            nr50: 0,
+            nr51: 0,
+            frame_step: 0,
+            sample_timer: 0,
+            output: None,
+        }
+    }
+
+    /**
+     * Samples are sent to the sink once per M-cycle, resampled to the rate it asks for
+     */
+    pub fn set_audio_sink(&mut self, sink: Box<dyn AudioSink>) {
+        self.output = Some(AudioOutput::new(sink));
+    }
+
+    /**
+     * Drops the sink after handing it the samples still buffered
+     */
+    pub fn remove_audio_sink(&mut self) {
+        self.output = None;
+    }
+
+    pub fn flush_audio(&mut self) {
+        if let Some(output) = self.output.as_mut() {
+            output.flush();
+        }
+    }
+
+    /**
+     * Advances the channel timers by the given amount of cycles
+     */
+    pub fn tick(&mut self, cycles: u64) {
+        for _ in 0..cycles {
+            if self.powered {
+                self.square1.tick();
+                self.square2.tick();
+                self.wave.tick();
+                self.noise.tick();
+            }
+
+            self.sample_timer = (self.sample_timer + 1) & 0x03;
+            if self.sample_timer == 0 && self.output.is_some() {
+                let (left, right) = self.get_output();
+                if let Some(output) = self.output.as_mut() {
+                    output.push(left, right);
+                }
+            }
+        }
+    }
+
+    /**
+     * Called at 512 Hz on the falling edges of a DIV bit. Length counters are clocked on even steps,
+     * the sweep on steps 2 and 6 and the envelopes on step 7
+     */
+    pub fn clock_frame_sequencer(&mut self) {
+        if !self.powered {
+            return;
+        }
+
+        if self.frame_step & 1 == 0 {
+            self.square1.clock_length();
+            self.square2.clock_length();
+            self.wave.clock_length();
+            self.noise.clock_length();
+        }
+        if self.frame_step == 2 || self.frame_step == 6 {
+            self.square1.clock_sweep();
+        }
+        if self.frame_step == 7 {
+            self.square1.clock_envelope();
+            self.square2.clock_envelope();
+            self.noise.clock_envelope();
+        }
+
+        self.frame_step = (self.frame_step + 1) & 0x07;
+    }
+
+    /**
+     * Current stereo sample, each side in the range -1.0 to 1.0
+     */
+    pub fn get_output(&self) -> (f32, f32) {
+        if !self.powered {
+            return (0.0, 0.0);
+        }
+
+        let channels = [
+            to_analog(self.square1.is_dac_enabled(), self.square1.get_output()),
+            to_analog(self.square2.is_dac_enabled(), self.square2.get_output()),
+            to_analog(self.wave.is_dac_enabled(), self.wave.get_output()),
+            to_analog(self.noise.is_dac_enabled(), self.noise.get_output()),
+        ];
+
+        let mut left = 0.0;
+        let mut right = 0.0;
+        for (index, sample) in channels.iter().enumerate() {
+            if self.nr51 & (0x10 << index) != 0 {
+                left += sample;
+            }
+            if self.nr51 & (0x01 << index) != 0 {
+                right += sample;
+            }
+        }
+
+        let left_volume = ((self.nr50 >> 4) & 0x07) as f32 + 1.0;
+        let right_volume = (self.nr50 & 0x07) as f32 + 1.0;
+        (left / 4.0 * left_volume / 8.0, right / 4.0 * right_volume / 8.0)
+    }
+
+    /**
+     * Whether the next frame sequencer step clocks the length counters, needed for the length quirks
+     */
+    fn next_step_clocks_length(&self) -> bool {
+        self.frame_step & 1 == 0
+    }
+
+    fn get_channel_status(&self) -> u8 {
+        self.square1.is_enabled() as u8
+            | (self.square2.is_enabled() as u8) << 1
+            | (self.wave.is_enabled() as u8) << 2
+            | (self.noise.is_enabled() as u8) << 3
+    }
+
+    /**
+     * Turning the APU off clears every register but wave RAM, the DMG also keeps the length counters
+     */
+    fn set_power(&mut self, powered: bool) {
+        if self.powered && !powered {
+            let wave_ram: Vec<u8> = (0..16).map(|address| self.wave.read_ram(address)).collect();
+            let lengths = [
+                self.square1.get_length_counter(),
+                self.square2.get_length_counter(),
+                self.wave.get_length_counter(),
+                self.noise.get_length_counter(),
+            ];
+
+            self.square1 = Square::new(true);
+            self.square2 = Square::new(false);
+            self.wave = Wave::new();
+            self.noise = Noise::new();
+            self.nr50 = 0;
+            self.nr51 = 0;
+
+            for (address, value) in wave_ram.iter().enumerate() {
+                self.wave.write_ram(address as u16, *value);
+            }
+            if !self.is_cgb {
+                self.square1.set_length_counter(lengths[0]);
+                self.square2.set_length_counter(lengths[1]);
+                self.wave.set_length_counter(lengths[2]);
+                self.noise.set_length_counter(lengths[3]);
+            }
+        } else if !self.powered && powered {
+            self.frame_step = 0;
+        }
+        self.powered = powered;
+    }
+
+    pub fn read_register(&self, address: u16) -> Option<u8> {
+        let value = match address {
+            0xFF10..=0xFF14 => self.square1.read_register(address - 0xFF10),
+            0xFF15..=0xFF19 => self.square2.read_register(address - 0xFF15),
+            0xFF1A..=0xFF1E => self.wave.read_register(address - 0xFF1A),
+            0xFF1F..=0xFF23 => self.noise.read_register(address - 0xFF1F),
+            0xFF24 => self.nr50,
+            0xFF25 => self.nr51,
+            0xFF26 => (self.powered as u8) << 7 | 0x70 | self.get_channel_status(),
+            0xFF30..=0xFF3F => self.wave.read_ram(address - 0xFF30),
+            0xFF27..=0xFF2F => 0xFF,
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    pub fn write_register(&mut self, address: u16, value: u8) {
+        match address {
+            0xFF26 => return self.set_power(value & 0x80 != 0),
+            0xFF30..=0xFF3F => return self.wave.write_ram(address - 0xFF30, value),
+            _ => {}
+        }
+
+        if !self.powered {
+            //The DMG keeps the length counters writable while the APU is off
+            if !self.is_cgb {
+                match address {
+                    0xFF11 => self.square1.write_length(value),
+                    0xFF16 => self.square2.write_length(value),
+                    0xFF1B => self.wave.write_length(value),
+                    0xFF20 => self.noise.write_length(value),
+                    _ => {}
+                }
+            }
+            return;
+        }
+
+        let next_step_clocks_length = self.next_step_clocks_length();
+        match address {
+            0xFF10..=0xFF14 => {
+                self.square1.write_register(address - 0xFF10, value, next_step_clocks_length)
+            }
+            0xFF15..=0xFF19 => {
+                self.square2.write_register(address - 0xFF15, value, next_step_clocks_length)
+            }
+            0xFF1A..=0xFF1E => {
+                self.wave.write_register(address - 0xFF1A, value, next_step_clocks_length)
+            }
+            0xFF1F..=0xFF23 => {
+                self.noise.write_register(address - 0xFF1F, value, next_step_clocks_length)
+            }
+            0xFF24 => self.nr50 = value,
+            0xFF25 => self.nr51 = value,
+            _ => {}
+        }
+    }
+}
+
+/**
+ * Each channel DAC maps the digital 0-15 output linearly to -1.0..1.0, a disabled DAC outputs silence
+ */
+fn to_analog(dac_enabled: bool, sample: u8) -> f32 {
+    if dac_enabled {
+        1.0 - sample as f32 / 7.5
+    } else {
+        0.0
+    }
+}
@@ -0,0 +1,149 @@
+use super::{envelope::Envelope, length::LengthCounter};
+
+const DIVISORS: [u16; 8] = [8, 16, 32, 48, 64, 80, 96, 112];
+
+/**
+ * Noise channel, a linear feedback shift register clocked at a configurable rate
+ */
+pub struct Noise {
+    enabled: bool,
+    clock_shift: u8,
+    short_mode: bool,
+    divisor_code: u8,
+    lfsr: u16,
+    timer: u32,
+    length: LengthCounter,
+    envelope: Envelope,
+}
+
+impl Noise {
+    pub fn new() -> Self {
+        Noise {
+            enabled: false,
+            clock_shift: 0,
+            short_mode: false,
+            divisor_code: 0,
+            lfsr: 0x7FFF,
+            timer: 0,
+            length: LengthCounter::new(64),
+            envelope: Envelope::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn is_dac_enabled(&self) -> bool {
+        self.envelope.is_dac_enabled()
+    }
+
+    /**
+     * Digital output of the channel, 0-15. The output is high while bit 0 of the LFSR is clear
+     */
+    pub fn get_output(&self) -> u8 {
+        if !self.enabled || self.lfsr & 1 != 0 {
+            return 0;
+        }
+        self.envelope.get_volume()
+    }
+
+    fn get_period(&self) -> u32 {
+        (DIVISORS[self.divisor_code as usize] as u32) << self.clock_shift
+    }
+
+    pub fn tick(&mut self) {
+        if self.timer > 0 {
+            self.timer -= 1;
+        }
+        if self.timer == 0 {
+            self.timer = self.get_period();
+            //Shifts of 14 and 15 stop the LFSR
+            if self.clock_shift < 14 {
+                self.step_lfsr();
+            }
+        }
+    }
+
+    /**
+     * The XOR of the two lowest bits goes into bit 14, and also into bit 6 in 7-bit mode
+     */
+    fn step_lfsr(&mut self) {
+        let bit = (self.lfsr ^ (self.lfsr >> 1)) & 1;
+        self.lfsr = (self.lfsr >> 1) | (bit << 14);
+        if self.short_mode {
+            self.lfsr = (self.lfsr & !(1 << 6)) | (bit << 6);
+        }
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock();
+    }
+
+    fn trigger(&mut self, next_step_clocks_length: bool) {
+        self.enabled = self.is_dac_enabled();
+        self.length.trigger(next_step_clocks_length);
+        self.timer = self.get_period();
+        self.lfsr = 0x7FFF;
+        self.envelope.trigger();
+    }
+
+    pub fn get_length_counter(&self) -> u16 {
+        self.length.get_counter()
+    }
+
+    pub fn set_length_counter(&mut self, counter: u16) {
+        self.length.set_counter(counter);
+    }
+
+    pub fn write_length(&mut self, value: u8) {
+        self.length.load(value & 0x3F);
+    }
+
+    pub fn read_register(&self, register: u16) -> u8 {
+        match register {
+            2 => self.envelope.read(),
+            3 => self.clock_shift << 4 | (self.short_mode as u8) << 3 | self.divisor_code,
+            4 => 0xBF | (self.length.is_enabled() as u8) << 6,
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write_register(&mut self, register: u16, value: u8, next_step_clocks_length: bool) {
+        match register {
+            1 => self.write_length(value),
+            2 => {
+                self.envelope.write(value);
+                if !self.is_dac_enabled() {
+                    self.enabled = false;
+                }
+            }
+            3 => {
+                self.clock_shift = value >> 4;
+                self.short_mode = value & 0x08 != 0;
+                self.divisor_code = value & 0x07;
+            }
+            4 => {
+                if self.length.set_enabled(value & 0x40 != 0, next_step_clocks_length) {
+                    self.enabled = false;
+                }
+                if value & 0x80 != 0 {
+                    self.trigger(next_step_clocks_length);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        Self::new()
+    }
+}
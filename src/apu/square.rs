@@ -0,0 +1,202 @@
+use super::{envelope::Envelope, length::LengthCounter};
+
+const DUTY_PATTERNS: [u8; 4] = [0b0000_0001, 0b1000_0001, 0b1000_0111, 0b0111_1110];
+
+/**
+ * Square wave channel. Channel 1 adds the frequency sweep unit (NR10), channel 2 doesn't have it
+ */
+pub struct Square {
+    has_sweep: bool,
+    enabled: bool,
+    duty: u8,
+    duty_position: u8,
+    frequency: u16,
+    timer: u16,
+    length: LengthCounter,
+    envelope: Envelope,
+    sweep_period: u8,
+    sweep_negate: bool,
+    sweep_shift: u8,
+    sweep_timer: u8,
+    sweep_enabled: bool,
+    shadow_frequency: u16,
+    negate_used: bool,
+}
+
+impl Square {
+    pub fn new(has_sweep: bool) -> Self {
+        Square {
+            has_sweep,
+            enabled: false,
+            duty: 0,
+            duty_position: 0,
+            frequency: 0,
+            timer: 0,
+            length: LengthCounter::new(64),
+            envelope: Envelope::new(),
+            sweep_period: 0,
+            sweep_negate: false,
+            sweep_shift: 0,
+            sweep_timer: 0,
+            sweep_enabled: false,
+            shadow_frequency: 0,
+            negate_used: false,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /**
+     * Digital output of the channel, 0-15
+     */
+    pub fn get_output(&self) -> u8 {
+        if !self.enabled {
+            return 0;
+        }
+        let high = (DUTY_PATTERNS[self.duty as usize] >> (7 - self.duty_position)) & 1;
+        high * self.envelope.get_volume()
+    }
+
+    pub fn is_dac_enabled(&self) -> bool {
+        self.envelope.is_dac_enabled()
+    }
+
+    pub fn tick(&mut self) {
+        if self.timer > 0 {
+            self.timer -= 1;
+        }
+        if self.timer == 0 {
+            self.timer = (2048 - self.frequency) * 4;
+            self.duty_position = (self.duty_position + 1) & 0x07;
+        }
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock();
+    }
+
+    pub fn clock_sweep(&mut self) {
+        self.sweep_timer = self.sweep_timer.saturating_sub(1);
+        if self.sweep_timer > 0 {
+            return;
+        }
+        self.reload_sweep_timer();
+
+        if self.sweep_enabled && self.sweep_period != 0 {
+            let frequency = self.calculate_sweep();
+            if frequency <= 2047 && self.sweep_shift != 0 {
+                self.frequency = frequency;
+                self.shadow_frequency = frequency;
+                self.calculate_sweep();
+            }
+        }
+    }
+
+    //A period of 0 is treated as 8
+    fn reload_sweep_timer(&mut self) {
+        self.sweep_timer = if self.sweep_period == 0 { 8 } else { self.sweep_period };
+    }
+
+    /**
+     * Computes the next frequency, disabling the channel when it overflows
+     */
+    fn calculate_sweep(&mut self) -> u16 {
+        let delta = self.shadow_frequency >> self.sweep_shift;
+        let frequency = if self.sweep_negate {
+            self.negate_used = true;
+            self.shadow_frequency - delta
+        } else {
+            self.shadow_frequency + delta
+        };
+
+        if frequency > 2047 {
+            self.enabled = false;
+        }
+        frequency
+    }
+
+    fn trigger(&mut self, next_step_clocks_length: bool) {
+        self.enabled = self.is_dac_enabled();
+        self.length.trigger(next_step_clocks_length);
+        self.timer = (2048 - self.frequency) * 4;
+        self.envelope.trigger();
+
+        if self.has_sweep {
+            self.shadow_frequency = self.frequency;
+            self.negate_used = false;
+            self.reload_sweep_timer();
+            self.sweep_enabled = self.sweep_period != 0 || self.sweep_shift != 0;
+            if self.sweep_shift != 0 {
+                self.calculate_sweep();
+            }
+        }
+    }
+
+    pub fn get_length_counter(&self) -> u16 {
+        self.length.get_counter()
+    }
+
+    pub fn set_length_counter(&mut self, counter: u16) {
+        self.length.set_counter(counter);
+    }
+
+    pub fn write_length(&mut self, value: u8) {
+        self.length.load(value & 0x3F);
+    }
+
+    pub fn read_register(&self, register: u16) -> u8 {
+        match register {
+            0 if self.has_sweep => {
+                0x80 | self.sweep_period << 4 | (self.sweep_negate as u8) << 3 | self.sweep_shift
+            }
+            1 => self.duty << 6 | 0x3F,
+            2 => self.envelope.read(),
+            4 => 0xBF | (self.length.is_enabled() as u8) << 6,
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write_register(&mut self, register: u16, value: u8, next_step_clocks_length: bool) {
+        match register {
+            0 if self.has_sweep => {
+                self.sweep_period = (value >> 4) & 0x07;
+                let negate = value & 0x08 != 0;
+                //Leaving negate mode after it was used in a calculation disables the channel
+                if self.sweep_negate && !negate && self.negate_used {
+                    self.enabled = false;
+                }
+                self.sweep_negate = negate;
+                self.sweep_shift = value & 0x07;
+            }
+            1 => {
+                self.duty = value >> 6;
+                self.write_length(value);
+            }
+            2 => {
+                self.envelope.write(value);
+                if !self.is_dac_enabled() {
+                    self.enabled = false;
+                }
+            }
+            3 => self.frequency = (self.frequency & 0x700) | value as u16,
+            4 => {
+                self.frequency = (self.frequency & 0xFF) | ((value as u16 & 0x07) << 8);
+                if self.length.set_enabled(value & 0x40 != 0, next_step_clocks_length) {
+                    self.enabled = false;
+                }
+                if value & 0x80 != 0 {
+                    self.trigger(next_step_clocks_length);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,198 @@
+#![cfg(test)]
+#![allow(clippy::bool_assert_comparison)]
+
+use super::Apu;
+
+fn powered_apu() -> Apu {
+    let mut apu = Apu::new(false);
+    apu.write_register(0xFF26, 0x80);
+    apu.write_register(0xFF24, 0x77);
+    apu.write_register(0xFF25, 0xFF);
+    apu
+}
+
+fn clock_frame_sequencer(apu: &mut Apu, steps: usize) {
+    for _ in 0..steps {
+        apu.clock_frame_sequencer();
+    }
+}
+
+#[test]
+fn test_register_read_masks() {
+    let mut apu = Apu::new(false);
+
+    assert_eq!(apu.read_register(0xFF26), Some(0x70));
+    //Writes are ignored while the APU is off
+    apu.write_register(0xFF12, 0xF3);
+    assert_eq!(apu.read_register(0xFF12), Some(0x00));
+
+    apu.write_register(0xFF26, 0x80);
+    apu.write_register(0xFF10, 0x00);
+    apu.write_register(0xFF11, 0x80);
+    apu.write_register(0xFF13, 0x12);
+    apu.write_register(0xFF1C, 0x40);
+    assert_eq!(apu.read_register(0xFF10), Some(0x80));
+    assert_eq!(apu.read_register(0xFF11), Some(0xBF));
+    assert_eq!(apu.read_register(0xFF13), Some(0xFF));
+    assert_eq!(apu.read_register(0xFF15), Some(0xFF));
+    assert_eq!(apu.read_register(0xFF1C), Some(0xDF));
+    assert_eq!(apu.read_register(0xFF27), Some(0xFF));
+    assert_eq!(apu.read_register(0xFF26), Some(0xF0));
+
+    //Powering off clears the registers but not wave RAM
+    apu.write_register(0xFF30, 0xAB);
+    apu.write_register(0xFF26, 0x00);
+    assert_eq!(apu.read_register(0xFF11), Some(0x3F));
+    assert_eq!(apu.read_register(0xFF30), Some(0xAB));
+}
+
+#[test]
+fn test_square_trigger_and_length() {
+    let mut apu = powered_apu();
+
+    apu.write_register(0xFF17, 0xF0);
+    apu.write_register(0xFF16, 0x3E);
+    apu.write_register(0xFF19, 0xC0);
+    assert_eq!(apu.read_register(0xFF26), Some(0xF2));
+
+    //Two length clocks are left
+    clock_frame_sequencer(&mut apu, 2);
+    assert_eq!(apu.read_register(0xFF26), Some(0xF2));
+    clock_frame_sequencer(&mut apu, 1);
+    assert_eq!(apu.read_register(0xFF26), Some(0xF0));
+}
+
+#[test]
+fn test_power_off_keeps_dmg_length() {
+    for (is_cgb, expired) in [(false, true), (true, false)] {
+        let mut apu = Apu::new(is_cgb);
+        apu.write_register(0xFF26, 0x80);
+        apu.write_register(0xFF16, 0x3E);
+        apu.write_register(0xFF26, 0x00);
+        apu.write_register(0xFF26, 0x80);
+
+        //The CGB clears the counter, so the trigger reloads it with the full length
+        apu.write_register(0xFF17, 0xF0);
+        apu.write_register(0xFF19, 0xC0);
+        clock_frame_sequencer(&mut apu, 3);
+        assert_eq!(apu.read_register(0xFF26), Some(if expired { 0xF0 } else { 0xF2 }));
+    }
+}
+
+#[test]
+fn test_dac_off_disables_channel() {
+    let mut apu = powered_apu();
+
+    apu.write_register(0xFF17, 0xF0);
+    apu.write_register(0xFF19, 0x80);
+    assert_eq!(apu.read_register(0xFF26), Some(0xF2));
+
+    apu.write_register(0xFF17, 0x07);
+    assert_eq!(apu.read_register(0xFF26), Some(0xF0));
+
+    //Triggering with the DAC off doesn't enable the channel
+    apu.write_register(0xFF19, 0x80);
+    assert_eq!(apu.read_register(0xFF26), Some(0xF0));
+}
+
+#[test]
+fn test_length_enable_extra_clock() {
+    let mut apu = powered_apu();
+    apu.write_register(0xFF17, 0xF0);
+    apu.write_register(0xFF16, 0x3F);
+    apu.write_register(0xFF19, 0x80);
+
+    //The next step doesn't clock length, so enabling it clocks the last step right away
+    clock_frame_sequencer(&mut apu, 1);
+    apu.write_register(0xFF19, 0x40);
+    assert_eq!(apu.read_register(0xFF26), Some(0xF0));
+}
+
+#[test]
+fn test_sweep_overflow() {
+    let mut apu = powered_apu();
+    apu.write_register(0xFF12, 0xF0);
+    apu.write_register(0xFF13, 0xFF);
+
+    //The overflow check on trigger already disables the channel
+    apu.write_register(0xFF10, 0x11);
+    apu.write_register(0xFF14, 0x87);
+    assert_eq!(apu.read_register(0xFF26), Some(0xF0));
+
+    //A small frequency survives the trigger and overflows after a few sweeps
+    apu.write_register(0xFF13, 0x00);
+    apu.write_register(0xFF14, 0x84);
+    assert_eq!(apu.read_register(0xFF26), Some(0xF1));
+    clock_frame_sequencer(&mut apu, 8);
+    assert_eq!(apu.read_register(0xFF26), Some(0xF0));
+}
+
+#[test]
+fn test_envelope() {
+    let mut apu = powered_apu();
+    apu.write_register(0xFF25, 0x22);
+    apu.write_register(0xFF17, 0x11);
+    apu.write_register(0xFF16, 0xC0);
+    apu.write_register(0xFF19, 0x80);
+
+    let mut highest = 0.0f32;
+    for _ in 0..8 {
+        apu.tick(512);
+        highest = highest.max(1.0 - apu.get_output().0);
+    }
+    assert!(highest > 0.0);
+
+    //Volume 1 goes down to 0 after one envelope step
+    clock_frame_sequencer(&mut apu, 8);
+    for _ in 0..8 {
+        apu.tick(512);
+        assert_eq!(apu.get_output(), (1.0 / 4.0, 1.0 / 4.0));
+    }
+}
+
+#[test]
+fn test_wave_channel() {
+    let mut apu = powered_apu();
+    apu.write_register(0xFF25, 0x44);
+    for address in 0xFF30..=0xFF3F {
+        apu.write_register(address, 0xF0);
+    }
+    apu.write_register(0xFF1A, 0x80);
+    apu.write_register(0xFF1C, 0x20);
+    apu.write_register(0xFF1D, 0x00);
+    apu.write_register(0xFF1E, 0x87);
+    assert_eq!(apu.read_register(0xFF26), Some(0xF4));
+
+    let mut outputs = Vec::new();
+    for _ in 0..64 {
+        apu.tick(256);
+        outputs.push(apu.get_output().0);
+    }
+    assert!(outputs.contains(&((1.0 - 15.0 / 7.5) / 4.0)));
+    assert!(outputs.contains(&(1.0 / 4.0)));
+
+    apu.write_register(0xFF1A, 0x00);
+    assert_eq!(apu.read_register(0xFF26), Some(0xF0));
+}
+
+#[test]
+fn test_noise_channel_and_panning() {
+    let mut apu = powered_apu();
+    //Noise only on the left side
+    apu.write_register(0xFF25, 0x80);
+    apu.write_register(0xFF21, 0xF0);
+    apu.write_register(0xFF22, 0x00);
+    apu.write_register(0xFF23, 0x80);
+    assert_eq!(apu.read_register(0xFF26), Some(0xF8));
+
+    let mut high = false;
+    let mut low = false;
+    for _ in 0..256 {
+        apu.tick(16);
+        let (left, right) = apu.get_output();
+        assert_eq!(right, 0.0);
+        high |= left < 0.0;
+        low |= left > 0.0;
+    }
+    assert_eq!(high && low, true);
+}
@@ -0,0 +1,110 @@
+mod resampler;
+mod tests;
+mod wav;
+
+pub use resampler::Resampler;
+pub use wav::WavSink;
+
+//The APU output is sampled once per M-cycle
+pub const APU_SAMPLE_RATE: u32 = 4_194_304 / 4;
+
+const BUFFER_SAMPLES: usize = 2048;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleFormat {
+    F32,
+    I16,
+}
+
+/**
+ * Receives the audio of the emulator as interleaved stereo samples (left, right, left, ...)
+ * at the rate and in the format the sink asks for
+ */
+pub trait AudioSink {
+    fn get_sample_rate(&self) -> u32;
+
+    fn get_format(&self) -> SampleFormat;
+
+    fn write_f32(&mut self, _samples: &[f32]) {}
+
+    fn write_i16(&mut self, _samples: &[i16]) {}
+}
+
+/**
+ * Removes the DC offset of the channel DACs, like the capacitor on the hardware output
+ */
+struct HighPass {
+    charge: (f32, f32),
+    factor: f32,
+}
+
+impl HighPass {
+    fn new(sample_rate: u32) -> Self {
+        HighPass {
+            charge: (0.0, 0.0),
+            factor: 0.999958f32.powf(4_194_304.0 / sample_rate as f32),
+        }
+    }
+
+    fn filter(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let output = (left - self.charge.0, right - self.charge.1);
+        self.charge.0 = left - output.0 * self.factor;
+        self.charge.1 = right - output.1 * self.factor;
+        output
+    }
+}
+
+/**
+ * Connects the APU to a sink: filters and resamples every APU sample and hands them over in blocks
+ */
+pub struct AudioOutput {
+    sink: Box<dyn AudioSink>,
+    high_pass: HighPass,
+    resampler: Resampler,
+    buffer: Vec<f32>,
+}
+
+impl AudioOutput {
+    pub fn new(sink: Box<dyn AudioSink>) -> Self {
+        let sample_rate = sink.get_sample_rate();
+        AudioOutput {
+            sink,
+            high_pass: HighPass::new(APU_SAMPLE_RATE),
+            resampler: Resampler::new(APU_SAMPLE_RATE, sample_rate),
+            buffer: Vec::with_capacity(BUFFER_SAMPLES * 2),
+        }
+    }
+
+    pub fn push(&mut self, left: f32, right: f32) {
+        let (left, right) = self.high_pass.filter(left, right);
+        self.resampler.push(left, right, &mut self.buffer);
+        if self.buffer.len() >= BUFFER_SAMPLES {
+            self.flush();
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        match self.sink.get_format() {
+            SampleFormat::F32 => self.sink.write_f32(&self.buffer),
+            SampleFormat::I16 => {
+                let samples: Vec<i16> = self.buffer.iter().map(|sample| to_i16(*sample)).collect();
+                self.sink.write_i16(&samples);
+            }
+        }
+        self.buffer.clear();
+    }
+}
+
+impl Drop for AudioOutput {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+pub fn to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
@@ -0,0 +1,110 @@
+use std::{collections::VecDeque, f64::consts::PI};
+
+//Zero crossings of the sinc on each side of the output sample
+const ZERO_CROSSINGS: f64 = 8.0;
+//Cutoff as a fraction of the output Nyquist frequency, the transition band around it only aliases into
+//the inaudible top of the output band
+const CUTOFF: f64 = 0.85;
+//Kernel table entries per input sample of distance, the values in between are interpolated
+const TABLE_RESOLUTION: usize = 64;
+
+/**
+ * Converts the stereo stream sampled at the APU rate into the host rate. Every output sample is a
+ * Blackman windowed sinc taken over the input samples around it, which low-passes at the input rate and
+ * removes everything above the output Nyquist frequency instead of letting it alias back into the audible
+ * range. The kernel is computed once into a table
+ */
+pub struct Resampler {
+    history: VecDeque<(f64, f64)>,
+    position: f64,
+    step: f64,
+    half_width: usize,
+    kernel: Vec<f64>,
+}
+
+impl Resampler {
+    pub fn new(input_rate: u32, output_rate: u32) -> Self {
+        let cutoff = (output_rate as f64 / input_rate as f64).min(1.0) * CUTOFF;
+        let half_width = (ZERO_CROSSINGS / cutoff).ceil() as usize;
+        let kernel = (0..=2 * half_width * TABLE_RESOLUTION)
+            .map(|entry| {
+                let distance = entry as f64 / TABLE_RESOLUTION as f64 - half_width as f64;
+                compute_kernel(distance, cutoff, half_width)
+            })
+            .collect();
+
+        Resampler {
+            history: VecDeque::from(vec![(0.0, 0.0); half_width]),
+            position: half_width as f64,
+            step: input_rate as f64 / output_rate as f64,
+            half_width,
+            kernel,
+        }
+    }
+
+    /**
+     * Feeds one input sample, appending any output samples that became available as interleaved stereo
+     */
+    pub fn push(&mut self, left: f32, right: f32, output: &mut Vec<f32>) {
+        self.history.push_back((left as f64, right as f64));
+
+        while self.position + (self.half_width as f64) < self.history.len() as f64 {
+            let (left, right) = self.interpolate();
+            output.push(left as f32);
+            output.push(right as f32);
+            self.position += self.step;
+        }
+
+        while self.position - self.half_width as f64 > 1.0 {
+            self.history.pop_front();
+            self.position -= 1.0;
+        }
+    }
+
+    fn interpolate(&self) -> (f64, f64) {
+        let center = self.position.floor() as usize;
+        let first = center.saturating_sub(self.half_width);
+        let last = (center + self.half_width).min(self.history.len() - 1);
+
+        let mut sum = (0.0, 0.0);
+        let mut weights = 0.0;
+        for index in first..=last {
+            let weight = self.get_kernel(index as f64 - self.position);
+            let (left, right) = self.history[index];
+            sum.0 += left * weight;
+            sum.1 += right * weight;
+            weights += weight;
+        }
+
+        if weights == 0.0 {
+            return (0.0, 0.0);
+        }
+        (sum.0 / weights, sum.1 / weights)
+    }
+
+    fn get_kernel(&self, distance: f64) -> f64 {
+        let position = (distance + self.half_width as f64) * TABLE_RESOLUTION as f64;
+        if position <= 0.0 || position >= (self.kernel.len() - 1) as f64 {
+            return 0.0;
+        }
+        let entry = position as usize;
+        let fraction = position - entry as f64;
+        self.kernel[entry] * (1.0 - fraction) + self.kernel[entry + 1] * fraction
+    }
+}
+
+/**
+ * Sinc with its cutoff relative to the input rate, under a Blackman window spanning half_width input
+ * samples on each side
+ */
+fn compute_kernel(distance: f64, cutoff: f64, half_width: usize) -> f64 {
+    let window_position = distance / half_width as f64;
+    if window_position.abs() >= 1.0 {
+        return 0.0;
+    }
+
+    let x = distance * cutoff;
+    let sinc = if x == 0.0 { 1.0 } else { (PI * x).sin() / (PI * x) };
+    let window = 0.42 + 0.5 * (PI * window_position).cos() + 0.08 * (2.0 * PI * window_position).cos();
+    sinc * window
+}
@@ -0,0 +1,163 @@
+#![cfg(test)]
+
+use std::{cell::RefCell, f64::consts::PI, rc::Rc};
+
+use super::{to_i16, AudioOutput, AudioSink, Resampler, SampleFormat, WavSink, APU_SAMPLE_RATE};
+
+/**
+ * Keeps everything it receives so the tests can look at it
+ */
+struct BufferSink {
+    sample_rate: u32,
+    format: SampleFormat,
+    f32_samples: Rc<RefCell<Vec<f32>>>,
+    i16_samples: Rc<RefCell<Vec<i16>>>,
+}
+
+impl AudioSink for BufferSink {
+    fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn get_format(&self) -> SampleFormat {
+        self.format
+    }
+
+    fn write_f32(&mut self, samples: &[f32]) {
+        self.f32_samples.borrow_mut().extend_from_slice(samples);
+    }
+
+    fn write_i16(&mut self, samples: &[i16]) {
+        self.i16_samples.borrow_mut().extend_from_slice(samples);
+    }
+}
+
+/**
+ * Resamples one second of a sine wave and returns the peak amplitude of the output, skipping the start
+ */
+fn resampled_peak(frequency: f64, output_rate: u32) -> (usize, f32) {
+    let mut resampler = Resampler::new(APU_SAMPLE_RATE, output_rate);
+    let mut output = Vec::new();
+    for index in 0..APU_SAMPLE_RATE {
+        let sample = (2.0 * PI * frequency * index as f64 / APU_SAMPLE_RATE as f64).sin() as f32;
+        resampler.push(sample, -sample, &mut output);
+    }
+
+    let peak = output
+        .iter()
+        .skip(2000)
+        .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+    (output.len() / 2, peak)
+}
+
+#[test]
+fn test_resampler_rate_and_passband() {
+    for output_rate in [44100, 48000] {
+        let (samples, peak) = resampled_peak(440.0, output_rate);
+        assert!((samples as i64 - output_rate as i64).abs() < 100);
+        assert!(peak > 0.95 && peak < 1.05);
+    }
+}
+
+#[test]
+fn test_resampler_rejects_frequencies_above_nyquist() {
+    //30 kHz would alias down to 18 kHz at 48 kHz without the filter
+    let (_, peak) = resampled_peak(30000.0, 48000);
+    assert!(peak < 0.05);
+
+    //Far above the output band, folds into the audible range if it isn't filtered at the input rate
+    let (_, peak) = resampled_peak(120000.0, 48000);
+    assert!(peak < 0.01);
+}
+
+#[test]
+fn test_audio_output_formats() {
+    let f32_samples = Rc::new(RefCell::new(Vec::new()));
+    let i16_samples = Rc::new(RefCell::new(Vec::new()));
+
+    let mut output = AudioOutput::new(Box::new(BufferSink {
+        sample_rate: 48000,
+        format: SampleFormat::I16,
+        f32_samples: f32_samples.clone(),
+        i16_samples: i16_samples.clone(),
+    }));
+    for _ in 0..APU_SAMPLE_RATE / 10 {
+        output.push(0.5, -0.5);
+    }
+    drop(output);
+
+    let samples = i16_samples.borrow();
+    assert!(f32_samples.borrow().is_empty());
+    assert!((samples.len() as i64 - 9600).abs() < 20);
+    //Interleaved and opposite on each side
+    assert_eq!(samples[1000], -samples[1001]);
+    assert_eq!(to_i16(1.5), i16::MAX);
+    assert_eq!(to_i16(-1.0), -i16::MAX);
+}
+
+#[test]
+fn test_apu_feeds_sink() {
+    let f32_samples = Rc::new(RefCell::new(Vec::new()));
+    let mut apu = crate::apu::Apu::new(false);
+    apu.set_audio_sink(Box::new(BufferSink {
+        sample_rate: 44100,
+        format: SampleFormat::F32,
+        f32_samples: f32_samples.clone(),
+        i16_samples: Rc::new(RefCell::new(Vec::new())),
+    }));
+
+    apu.write_register(0xFF26, 0x80);
+    apu.write_register(0xFF24, 0x77);
+    apu.write_register(0xFF25, 0x22);
+    apu.write_register(0xFF17, 0xF0);
+    apu.write_register(0xFF16, 0x80);
+    apu.write_register(0xFF18, 0x00);
+    apu.write_register(0xFF19, 0x87);
+
+    apu.tick(4_194_304 / 10);
+    apu.flush_audio();
+
+    let samples = f32_samples.borrow();
+    assert!((samples.len() as i64 - 8820).abs() < 20);
+    assert!(samples.iter().any(|sample| *sample > 0.1));
+    assert!(samples.iter().any(|sample| *sample < -0.1));
+}
+
+#[test]
+fn test_wav_sink_header() {
+    let path = std::env::temp_dir().join(format!("gbc-wav-test-{}.wav", std::process::id()));
+
+    let mut sink = WavSink::create(&path, 44100, SampleFormat::I16).unwrap();
+    sink.write_i16(&[1, -1, 2, -2]);
+    drop(sink);
+
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(bytes.len(), 44 + 8);
+    assert_eq!(&bytes[0..4], b"RIFF");
+    assert_eq!(u32::from_le_bytes(bytes[4..8].try_into().unwrap()), 36 + 8);
+    assert_eq!(u16::from_le_bytes([bytes[20], bytes[21]]), 1);
+    assert_eq!(u16::from_le_bytes([bytes[22], bytes[23]]), 2);
+    assert_eq!(u32::from_le_bytes(bytes[24..28].try_into().unwrap()), 44100);
+    assert_eq!(u32::from_le_bytes(bytes[40..44].try_into().unwrap()), 8);
+    assert_eq!(i16::from_le_bytes([bytes[46], bytes[47]]), -1);
+}
+
+#[test]
+fn test_wav_sink_updates_header_while_recording() {
+    let path = std::env::temp_dir().join(format!("gbc-wav-update-test-{}.wav", std::process::id()));
+
+    //A second of audio is enough for the header to be written again
+    let mut sink = WavSink::create(&path, 1000, SampleFormat::I16).unwrap();
+    sink.write_i16(&[0; 1000]);
+    sink.write_i16(&[0; 1000]);
+
+    let bytes = std::fs::read(&path).unwrap();
+    drop(sink);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(bytes.len(), 44 + 4000);
+    assert_eq!(u32::from_le_bytes(bytes[4..8].try_into().unwrap()), 36 + 4000);
+    assert_eq!(u32::from_le_bytes(bytes[40..44].try_into().unwrap()), 4000);
+}
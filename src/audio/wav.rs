@@ -0,0 +1,137 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use super::{AudioSink, SampleFormat};
+
+const HEADER_SIZE: u32 = 44;
+//The RIFF size field covers everything after it, so the samples have to fit in the rest of a u32
+const MAX_DATA_SIZE: u32 = u32::MAX - (HEADER_SIZE - 8);
+
+/**
+ * Records the audio output into a stereo .wav file, as 16-bit PCM or 32-bit float. The header is
+ * rewritten after every second of audio, so a run that never ends cleanly still leaves a playable file
+ */
+pub struct WavSink {
+    writer: BufWriter<File>,
+    sample_rate: u32,
+    format: SampleFormat,
+    data_size: u32,
+    unsaved_size: u32,
+    full: bool,
+}
+
+impl WavSink {
+    pub fn create<P: AsRef<Path>>(path: P, sample_rate: u32, format: SampleFormat) -> io::Result<Self> {
+        let mut sink = WavSink {
+            writer: BufWriter::new(File::create(path)?),
+            sample_rate,
+            format,
+            data_size: 0,
+            unsaved_size: 0,
+            full: false,
+        };
+        sink.write_header()?;
+        Ok(sink)
+    }
+
+    /**
+     * The chunk sizes are only known at the end, so the header is written again with them filled in
+     */
+    fn write_header(&mut self) -> io::Result<()> {
+        let (format_tag, bits_per_sample) = self.get_format_tag();
+        let block_align = 2 * bits_per_sample / 8;
+        let byte_rate = self.get_byte_rate();
+
+        let writer = &mut self.writer;
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&(HEADER_SIZE - 8 + self.data_size).to_le_bytes())?;
+        writer.write_all(b"WAVE")?;
+        writer.write_all(b"fmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        writer.write_all(&format_tag.to_le_bytes())?;
+        writer.write_all(&2u16.to_le_bytes())?;
+        writer.write_all(&self.sample_rate.to_le_bytes())?;
+        writer.write_all(&byte_rate.to_le_bytes())?;
+        writer.write_all(&block_align.to_le_bytes())?;
+        writer.write_all(&bits_per_sample.to_le_bytes())?;
+        writer.write_all(b"data")?;
+        writer.write_all(&self.data_size.to_le_bytes())?;
+        Ok(())
+    }
+
+    fn get_format_tag(&self) -> (u16, u16) {
+        match self.format {
+            SampleFormat::I16 => (1, 16),
+            SampleFormat::F32 => (3, 32),
+        }
+    }
+
+    fn get_byte_rate(&self) -> u32 {
+        self.sample_rate * 2 * self.get_format_tag().1 as u32 / 8
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.unsaved_size = 0;
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.write_header()?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()
+    }
+
+    /**
+     * Samples past the 4 GiB limit of the format are dropped
+     */
+    fn write_data(&mut self, bytes: &[u8]) {
+        if bytes.len() as u64 > (MAX_DATA_SIZE - self.data_size) as u64 {
+            if !self.full {
+                eprintln!("The wav file reached the 4 GiB limit, the rest of the audio is dropped");
+                self.full = true;
+            }
+            return;
+        }
+
+        if let Err(error) = self.writer.write_all(bytes) {
+            eprintln!("Couldn't write audio samples: {}", error);
+            return;
+        }
+        self.data_size += bytes.len() as u32;
+        self.unsaved_size += bytes.len() as u32;
+
+        if self.unsaved_size >= self.get_byte_rate() {
+            if let Err(error) = self.finish() {
+                eprintln!("Couldn't update the wav header: {}", error);
+            }
+        }
+    }
+}
+
+impl AudioSink for WavSink {
+    fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn get_format(&self) -> SampleFormat {
+        self.format
+    }
+
+    fn write_f32(&mut self, samples: &[f32]) {
+        let bytes: Vec<u8> = samples.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        self.write_data(&bytes);
+    }
+
+    fn write_i16(&mut self, samples: &[i16]) {
+        let bytes: Vec<u8> = samples.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        self.write_data(&bytes);
+    }
+}
+
+impl Drop for WavSink {
+    fn drop(&mut self) {
+        if let Err(error) = self.finish() {
+            eprintln!("Couldn't finish the wav file: {}", error);
+        }
+    }
+}
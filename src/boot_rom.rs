@@ -0,0 +1,55 @@
+use std::{error::Error, fmt};
+
+const DMG_BOOT_ROM_SIZE: usize = 0x100;
+const CGB_BOOT_ROM_SIZE: usize = 0x900;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BootRomError {
+    InvalidSize(usize),
+}
+
+impl fmt::Display for BootRomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BootRomError::InvalidSize(size) => write!(
+                f,
+                "The boot rom is {} bytes long, expected {} (DMG) or {} (CGB)",
+                size, DMG_BOOT_ROM_SIZE, CGB_BOOT_ROM_SIZE
+            ),
+        }
+    }
+}
+
+impl Error for BootRomError {}
+
+/**
+ * Boot rom mapped over the cartridge until 0xFF50 is written. The CGB one is split in two, the
+ * cartridge header at 0x0100-0x01FF stays visible between both parts
+ */
+pub struct BootRom {
+    content: Vec<u8>,
+}
+
+impl BootRom {
+    pub fn new(content: Vec<u8>) -> Result<Self, BootRomError> {
+        match content.len() {
+            DMG_BOOT_ROM_SIZE | CGB_BOOT_ROM_SIZE => Ok(BootRom { content }),
+            size => Err(BootRomError::InvalidSize(size)),
+        }
+    }
+
+    pub fn is_cgb(&self) -> bool {
+        self.content.len() == CGB_BOOT_ROM_SIZE
+    }
+
+    /**
+     * Returns None for the addresses the boot rom doesn't cover
+     */
+    pub fn read(&self, address: u16) -> Option<u8> {
+        match address {
+            0x0000..=0x00FF => self.content.get(address as usize).copied(),
+            0x0200..=0x08FF if self.is_cgb() => self.content.get(address as usize).copied(),
+            _ => None,
+        }
+    }
+}
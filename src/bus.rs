@@ -1,33 +1,97 @@
+use std::io;
+
 use crate::{
-    cartridge::{self, Cartidge},
+    apu::Apu,
+    boot_rom::BootRom,
+    cartridge::{Cartidge, CartridgeError},
+    dma::{Hdma, HdmaMode, MemoryBus, OamDma, HDMA_BLOCK_SIZE},
+    interrupts::{Interrupt, InterruptController},
+    joypad::Joypad,
+    mbc::{self, Mbc, RtcClock, RumbleCallback},
     memory_bank::MemoryBank,
+    model::Model,
+    ppu::{CompatPalettes, Ppu},
+    save_file::SaveFile,
+    timer::Timer,
 };
+//2050 M-cycles
+const SPEED_SWITCH_CYCLES: u64 = 8200;
+
+//System counter values the game sees at 0x0100 when the boot rom finishes
+const DMG_POST_BOOT_COUNTER: u16 = 0xABCC;
+const CGB_POST_BOOT_COUNTER: u16 = 0x1EA0;
+
+//I/O registers as the boot rom leaves them, the sound registers with channel 1 already silent
+const POST_BOOT_IO: [(u16, u8); 22] = [
+    (0xFF00, 0x00),
+    (0xFF26, 0x80),
+    (0xFF10, 0x80),
+    (0xFF11, 0xBF),
+    (0xFF12, 0xF3),
+    (0xFF16, 0x3F),
+    (0xFF17, 0x00),
+    (0xFF1A, 0x7F),
+    (0xFF1B, 0xFF),
+    (0xFF1C, 0x9F),
+    (0xFF20, 0xFF),
+    (0xFF21, 0x00),
+    (0xFF22, 0x00),
+    (0xFF24, 0x77),
+    (0xFF25, 0xF3),
+    (0xFF40, 0x91),
+    (0xFF42, 0x00),
+    (0xFF43, 0x00),
+    (0xFF45, 0x00),
+    (0xFF47, 0xFC),
+    (0xFF4A, 0x00),
+    (0xFF4B, 0x00),
+];
+
 pub struct Bus {
-    cartridge_rom: MemoryBank,
-    cgb_switchable_ram: MemoryBank,
-    cartridge_switchable_ram: MemoryBank,
+    pub interrupts: InterruptController,
+    pub ppu: Ppu,
+    pub apu: Apu,
+    pub timer: Timer,
+    pub joypad: Joypad,
+    pub oam_dma: OamDma,
+    pub hdma: Hdma,
+    model: Model,
+    //CGB mode, cleared for DMG games running on CGB hardware
+    is_cgb: bool,
+    double_speed: bool,
+    speed_switch_armed: bool,
+    stall_cycles: u64,
+    boot_rom: Option<BootRom>,
+    mbc: Box<dyn Mbc>,
+    save_file: Option<SaveFile>,
     non_switchable_region: MemoryBank,
     second_cgb_switchable_ram: MemoryBank,
-    rest_ram: MemoryBank,
+    hram: [u8; 0x7F],
 }
 
 impl Bus {
-    pub fn new(cart: Cartidge) -> Self {
-        let is_cgb = cart.get_cgb();
-        let rom_banks = cart.get_rom_banks();
-        let cartridge_ram = cart.get_ram_banks();
-
-        let cartridge_rom = MemoryBank::new_from_bytes(16 * 1024, rom_banks, 1, cart.content);
+    /**
+     * A CGB boot rom starts in CGB mode and switches DMG games to compatibility mode through KEY0 itself,
+     * without one the mode is decided right away from the header
+     */
+    pub fn new(cart: Cartidge, model: Model, boot_rom: Option<BootRom>) -> Result<Self, CartridgeError> {
+        let header = cart.get_header()?;
+        let runs_cgb_boot_rom = boot_rom.as_ref().is_some_and(|boot_rom| boot_rom.is_cgb());
+        let is_cgb = model.is_cgb_hardware() && (header.is_cgb() || runs_cgb_boot_rom);
 
-        let cartridge_switchable_ram = MemoryBank::new(8 * 1024, cartridge_ram, 0);
-
-        let cgb_switchable_ram = if is_cgb {
-            MemoryBank::new(8 * 1024, 2, 0)
+        let mut save_file = if header.has_battery() {
+            cart.get_save_path().map(SaveFile::new)
         } else {
-            MemoryBank::new(8 * 1024, 1, 0)
+            None
         };
 
-        let second_cgb_switchable_ram = if is_cgb {
+        let mut mbc = mbc::new_mbc(cart)?;
+        if let Some(save_file) = save_file.as_mut() {
+            save_file.load(mbc.as_mut());
+        }
+
+        //Banks 1-7 on CGB, the DMG only has bank 1
+        let second_cgb_switchable_ram = if model.is_cgb_hardware() {
             MemoryBank::new(4 * 1024, 7, 0)
         } else {
             MemoryBank::new(4 * 1024, 1, 0)
@@ -35,34 +99,39 @@ impl Bus {
 
         let non_switchable_region = MemoryBank::new(4 * 1024, 1, 0);
 
-        let rest_ram = MemoryBank::new(8 * 1024, 1, 0);
+        let mut ppu = Ppu::new(model.is_cgb_hardware());
+        if model.is_cgb_hardware() && !is_cgb {
+            ppu.set_compat_mode();
+        }
 
-        Bus {
-            cartridge_rom,
-            cgb_switchable_ram,
-            cartridge_switchable_ram,
+        Ok(Bus {
+            interrupts: InterruptController::new(),
+            ppu,
+            apu: Apu::new(model.is_cgb_hardware()),
+            timer: Timer::new(),
+            joypad: Joypad::new(),
+            oam_dma: OamDma::new(),
+            hdma: Hdma::new(),
+            model,
+            is_cgb,
+            double_speed: false,
+            speed_switch_armed: false,
+            stall_cycles: 0,
+            boot_rom,
+            mbc,
+            save_file,
             non_switchable_region,
             second_cgb_switchable_ram,
-            rest_ram,
-        }
+            hram: [0; 0x7F],
+        })
     }
 
     #[cfg(test)]
     pub fn new_test(cart: Cartidge) -> Self
     {
         let is_cgb = true;
-        let rom_banks = 2;
-        let cartridge_ram = 1;
-
-        let cartridge_rom = MemoryBank::new_from_bytes(16 * 1024, rom_banks, 1, cart.content);
 
-        let cartridge_switchable_ram = MemoryBank::new(8 * 1024, cartridge_ram, 0);
-
-        let cgb_switchable_ram = if is_cgb {
-            MemoryBank::new(8 * 1024, 2, 0)
-        } else {
-            MemoryBank::new(8 * 1024, 1, 0)
-        };
+        let mbc: Box<dyn Mbc> = Box::new(mbc::RomOnly::new(cart.content, 1));
 
         let second_cgb_switchable_ram = if is_cgb {
             MemoryBank::new(4 * 1024, 7, 0)
@@ -72,78 +141,409 @@ impl Bus {
 
         let non_switchable_region = MemoryBank::new(4 * 1024, 1, 0);
 
-        let rest_ram = MemoryBank::new(8 * 1024, 1, 0);
-
         Bus {
-            cartridge_rom,
-            cgb_switchable_ram,
-            cartridge_switchable_ram,
+            interrupts: InterruptController::new(),
+            ppu: Ppu::new(is_cgb),
+            apu: Apu::new(is_cgb),
+            timer: Timer::new(),
+            joypad: Joypad::new(),
+            oam_dma: OamDma::new(),
+            hdma: Hdma::new(),
+            model: Model::Cgb,
+            is_cgb,
+            double_speed: false,
+            speed_switch_armed: false,
+            stall_cycles: 0,
+            boot_rom: None,
+            mbc,
+            save_file: None,
             non_switchable_region,
             second_cgb_switchable_ram,
-            rest_ram,
+            hram: [0; 0x7F],
         }
     }
 
-    pub fn write(&mut self, address: u16, value: u8) {
-        match address {
-            0x0000..=0x3FFF => {
-                //Primer banco del cartucho
-                self.cartridge_rom.write_bankless(address, value);
-            }
-            0x4000..=0x7FFF => {
-                //Banco switcheable del cartucho
-                self.cartridge_rom.write(address - 0x4000, value);
+    pub fn get_model(&self) -> Model {
+        self.model
+    }
+
+    /**
+     * Whether the CGB features are enabled, false for DMG games even on CGB hardware
+     */
+    pub fn is_cgb(&self) -> bool {
+        self.is_cgb
+    }
+
+    pub fn is_compat_mode(&self) -> bool {
+        self.model.is_cgb_hardware() && !self.is_cgb
+    }
+
+    pub fn is_boot_rom_mapped(&self) -> bool {
+        self.boot_rom.is_some()
+    }
+
+    /**
+     * Puts the hardware in the state the boot rom leaves it in, for when it's skipped
+     */
+    pub fn apply_post_boot_state(&mut self) {
+        for (address, value) in POST_BOOT_IO {
+            self.write(address, value);
+        }
+
+        if self.is_cgb {
+            //Every background color is white, the object palettes are left uninitialized
+            self.write(0xFF68, 0x80);
+            for _ in 0..32 {
+                self.write(0xFF69, 0xFF);
+                self.write(0xFF69, 0x7F);
             }
-            0x8000..=0x9FFF => {
-                //Banco switcheable
-                self.cgb_switchable_ram.write(address - 0x8000, value);
+        } else if self.is_compat_mode() {
+            let palettes = self.get_compat_palettes();
+            self.ppu.set_compat_palettes(palettes.bg, palettes.obj0, palettes.obj1);
+        }
+
+        let counter = if self.model.is_cgb_hardware() {
+            CGB_POST_BOOT_COUNTER
+        } else {
+            DMG_POST_BOOT_COUNTER
+        };
+        self.timer.set_initial_counter(counter);
+
+        self.interrupts.write_if(0x01);
+    }
+
+    /**
+     * The boot rom picks the palettes of DMG games from the title and licensee in the cartridge header
+     */
+    fn get_compat_palettes(&self) -> CompatPalettes {
+        let read = |address: u16| self.mbc.read_rom(address).unwrap_or(0xFF);
+        let mut title = [0; 16];
+        for (offset, byte) in title.iter_mut().enumerate() {
+            *byte = read(0x0134 + offset as u16);
+        }
+        CompatPalettes::from_header(&title, read(0x014B), [read(0x0144), read(0x0145)])
+    }
+
+    pub fn request_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupts.request(interrupt);
+    }
+
+    /**
+     * Advances the components that run alongside the cpu by the given amount of cpu cycles. The timer
+     * and OAM DMA follow the cpu clock, everything else keeps the normal speed clock, so in double
+     * speed they only see half of the cycles
+     */
+    pub fn tick(&mut self, cycles: u64) {
+        let normal_cycles = if self.double_speed { cycles / 2 } else { cycles };
+
+        self.mbc.tick(normal_cycles);
+        self.ppu.tick(normal_cycles, &mut self.interrupts);
+        if self.ppu.take_hblank_started() && self.hdma.get_mode() == HdmaMode::HBlank {
+            self.copy_hdma_block();
+        }
+        self.timer.tick(cycles, &mut self.interrupts);
+        self.tick_oam_dma(cycles);
+        self.apu.tick(normal_cycles);
+        self.clock_frame_sequencer();
+        if let Some(save_file) = self.save_file.as_mut() {
+            save_file.tick(normal_cycles, self.mbc.as_ref());
+        }
+    }
+
+    /**
+     * The APU frame sequencer steps on the falling edges of a bit of the system counter inside the timer
+     */
+    fn clock_frame_sequencer(&mut self) {
+        for _ in 0..self.timer.take_frame_sequencer_clocks() {
+            self.apu.clock_frame_sequencer();
+        }
+    }
+
+    fn tick_oam_dma(&mut self, cycles: u64) {
+        for _ in 0..cycles {
+            if let Some((source, index)) = self.oam_dma.tick() {
+                let value = self.read_unrestricted(source).unwrap_or(0xFF);
+                self.ppu.write_oam_dma(index, value);
+                self.oam_dma.complete_transfer(value);
             }
-            0xA000..=0xBFFF => {
-                //Banco de ram switcheable del cartucho
-                self.cartridge_switchable_ram.write(address - 0xA000, value);
+        }
+    }
+
+    /**
+     * Copies the next 16 bytes of the VRAM DMA. The cpu is halted for 8 microseconds per block, twice
+     * as many cpu cycles in double speed
+     */
+    fn copy_hdma_block(&mut self) {
+        let (source, destination) = self.hdma.take_block();
+        for offset in 0..HDMA_BLOCK_SIZE {
+            let value = self.read_unrestricted(source.wrapping_add(offset)).unwrap_or(0xFF);
+            self.ppu.write_vram_dma(destination + offset, value);
+        }
+        self.stall_cycles += if self.double_speed { 64 } else { 32 };
+    }
+
+    /**
+     * A general transfer completes before the cpu goes on. A HBlank transfer started outside of the
+     * HBlank of a visible line (e.g. with the LCD off) copies its first block right away
+     */
+    fn write_hdma(&mut self, address: u16, value: u8) {
+        self.hdma.write_register(address, value);
+        match self.hdma.get_mode() {
+            HdmaMode::General => {
+                while self.hdma.get_mode() == HdmaMode::General {
+                    self.copy_hdma_block();
+                }
             }
-            0xC000..=0xCFFF => {
-                self.non_switchable_region.write(address - 0xC000, value);
+            HdmaMode::HBlank if address == 0xFF55 && !self.ppu.is_enabled() => self.copy_hdma_block(),
+            _ => {}
+        }
+    }
+
+    /**
+     * Cycles the cpu has to spend halted because of VRAM DMA or a speed switch
+     */
+    pub fn take_stall_cycles(&mut self) -> u64 {
+        std::mem::take(&mut self.stall_cycles)
+    }
+
+    /**
+     * While OAM DMA runs the cpu can't reach OAM, and using the bus the transfer is on reads the byte
+     * being copied and drops writes. HRAM and the I/O registers stay available
+     */
+    fn is_blocked_by_oam_dma(&self, address: u16) -> bool {
+        match self.oam_dma.get_blocked_bus() {
+            Some(bus) => {
+                matches!(address, 0xFE00..=0xFEFF) || MemoryBus::from_address(address) == Some(bus)
             }
-            0xD000..=0xDFFF => {
-                //Banco Switcheable
-                self.second_cgb_switchable_ram
-                    .write(address - 0xDFFF, value)
+            None => false,
+        }
+    }
+
+    /**
+     * STOP resets the system counter like a write to DIV does
+     */
+    pub fn reset_div(&mut self) {
+        self.timer.write_register(0xFF04, 0);
+        self.clock_frame_sequencer();
+    }
+
+    /**
+     * Battery backed contents of the cartridge, in the same layout as the .sav file
+     */
+    pub fn export_save_data(&self) -> Vec<u8> {
+        self.mbc.get_save_data()
+    }
+
+    pub fn import_save_data(&mut self, data: &[u8]) {
+        self.mbc.load_save_data(data);
+    }
+
+    pub fn flush_save_file(&mut self) -> io::Result<()> {
+        match self.save_file.as_mut() {
+            Some(save_file) => save_file.flush(self.mbc.as_ref()),
+            None => Ok(()),
+        }
+    }
+
+    pub fn set_rtc_clock(&mut self, clock: RtcClock) {
+        self.mbc.set_rtc_clock(clock);
+    }
+
+    pub fn set_rumble_callback(&mut self, callback: RumbleCallback) {
+        self.mbc.set_rumble_callback(callback);
+    }
+
+    pub fn set_accelerometer(&mut self, x: f32, y: f32) {
+        self.mbc.set_accelerometer(x, y);
+    }
+
+    pub fn is_double_speed(&self) -> bool {
+        self.double_speed
+    }
+
+    pub fn is_speed_switch_armed(&self) -> bool {
+        self.speed_switch_armed
+    }
+
+    /**
+     * Toggles between normal and double speed, called by STOP once KEY1 has armed the switch.
+     * The cpu stays halted while the clock settles
+     */
+    pub fn switch_speed(&mut self) {
+        self.double_speed = !self.double_speed;
+        self.speed_switch_armed = false;
+        self.timer.set_double_speed(self.double_speed);
+        self.stall_cycles += SPEED_SWITCH_CYCLES;
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        if self.is_blocked_by_oam_dma(address) {
+            return;
+        }
+
+        match address {
+            0x0000..=0x7FFF => {
+                //Registros del controlador del cartucho
+                self.mbc.write_rom(address, value);
             }
-            0xE000.. => {
-                //Resto de la ram
-                self.rest_ram.write(address - 0xE000, value)
+            0x8000..=0x9FFF => self.ppu.write_vram(address - 0x8000, value),
+            0xA000..=0xBFFF => {
+                //Banco de ram switcheable del cartucho
+                self.mbc.write_ram(address - 0xA000, value);
             }
+            0xC000..=0xDFFF => self.write_wram(address, value),
+            //Echo RAM, mirrors 0xC000-0xDDFF
+            0xE000..=0xFDFF => self.write_wram(address - 0x2000, value),
+            0xFE00..=0xFE9F => self.ppu.write_oam(address - 0xFE00, value),
+            0xFEA0..=0xFEFF => {}
+            0xFF00..=0xFF7F => self.write_io(address, value),
+            0xFF80..=0xFFFE => self.hram[(address - 0xFF80) as usize] = value,
+            0xFFFF => self.interrupts.write_ie(value),
         }
     }
 
     pub fn read(&self, address: u16) -> Option<u8> {
+        if self.is_blocked_by_oam_dma(address) {
+            return match address {
+                0xFE00..=0xFEFF => Some(0xFF),
+                _ => Some(self.oam_dma.get_last_value()),
+            };
+        }
+        self.read_unrestricted(address)
+    }
+
+    /**
+     * Reads without the OAM DMA restrictions, as seen by the DMA engines
+     */
+    fn read_unrestricted(&self, address: u16) -> Option<u8> {
         match address {
-            0x0000..=0x3FFF => {
-                //Primer banco del cartucho
-                self.cartridge_rom.read_bankless(address)
-            }
-            0x4000..=0x7FFF => {
-                //Banco switcheable del cartucho
-                self.cartridge_rom.read(address - 0x4000)
-            }
-            0x8000..=0x9FFF => {
-                //Banco switcheable
-                self.cartridge_switchable_ram.read(address - 0x8000)
+            0x0000..=0x7FFF => {
+                //Bancos del cartucho, el controlador decide cual es visible
+                self.boot_rom
+                    .as_ref()
+                    .and_then(|boot_rom| boot_rom.read(address))
+                    .or_else(|| self.mbc.read_rom(address))
             }
+            0x8000..=0x9FFF => self.ppu.read_vram(address - 0x8000),
             0xA000..=0xBFFF => {
                 //Banco de ram switcheable del cartucho
-                self.cartridge_switchable_ram.read(address - 0xA000)
+                self.mbc.read_ram(address - 0xA000)
             }
+            0xC000..=0xDFFF => self.read_wram(address),
+            0xE000..=0xFDFF => self.read_wram(address - 0x2000),
+            0xFE00..=0xFE9F => self.ppu.read_oam(address - 0xFE00),
+            0xFEA0..=0xFEFF => Some(self.read_unusable()),
+            0xFF00..=0xFF7F => Some(self.read_io(address)),
+            0xFF80..=0xFFFE => Some(self.hram[(address - 0xFF80) as usize]),
+            0xFFFF => Some(self.interrupts.read_ie()),
+        }
+    }
+
+    fn write_wram(&mut self, address: u16, value: u8) {
+        match address {
+            0xC000..=0xCFFF => self.non_switchable_region.write(address - 0xC000, value),
+            //Banco Switcheable
+            _ => self.second_cgb_switchable_ram.write(address - 0xD000, value),
+        }
+    }
+
+    fn read_wram(&self, address: u16) -> Option<u8> {
+        match address {
             0xC000..=0xCFFF => self.non_switchable_region.read(address - 0xC000),
-            0xD000..=0xDFFF => {
-                //Banco Switcheable
-                self.second_cgb_switchable_ram.read(address - 0xD000)
+            //Banco Switcheable
+            _ => self.second_cgb_switchable_ram.read(address - 0xD000),
+        }
+    }
+
+    /**
+     * KEY0 is only writable by the CGB boot rom, which sets bit 2 to run DMG games in compatibility mode
+     */
+    fn write_key0(&mut self, value: u8) {
+        if value & 0x04 != 0 {
+            self.is_cgb = false;
+            self.second_cgb_switchable_ram.set_current_bank(0);
+            self.ppu.set_compat_mode();
+        }
+    }
+
+    /**
+     * SVBK selects which of the banks 1-7 is mapped at 0xD000, writing 0 selects bank 1 too
+     */
+    fn set_wram_bank(&mut self, value: u8) {
+        let bank = (value & 0x07).max(1);
+        self.second_cgb_switchable_ram.set_current_bank(bank as usize - 1);
+    }
+
+    fn get_wram_bank(&self) -> u8 {
+        self.second_cgb_switchable_ram.get_current_bank() as u8 + 1
+    }
+
+    /**
+     * The DMG reads 0x00 from the region between OAM and the I/O registers, the CGB drives 0xFF
+     */
+    fn read_unusable(&self) -> u8 {
+        if self.model.is_cgb_hardware() {
+            0xFF
+        } else {
+            0x00
+        }
+    }
+
+    /**
+     * Routes the I/O registers to the component that owns them, writes to unmapped ones are dropped
+     */
+    fn write_io(&mut self, address: u16, value: u8) {
+        match address {
+            0xFF00 => self.joypad.write_register(value, &mut self.interrupts),
+            0xFF04..=0xFF07 => {
+                self.timer.write_register(address, value);
+                self.clock_frame_sequencer();
+            }
+            0xFF0F => self.interrupts.write_if(value),
+            0xFF46 => self.oam_dma.start(value),
+            0xFF10..=0xFF3F => self.apu.write_register(address, value),
+            0xFF40..=0xFF45 | 0xFF47..=0xFF4B | 0xFF4F | 0xFF68..=0xFF6C => {
+                self.ppu.write_register(address, value, &mut self.interrupts)
             }
-            0xE000.. => {
-                //Resto de la ram
-                self.rest_ram.read(address - 0xE000)
+            0xFF4C if self.is_cgb && self.boot_rom.is_some() => self.write_key0(value),
+            //Any write with bit 0 set unmaps the boot rom until the next reset
+            0xFF50 if value & 0x01 != 0 => self.boot_rom = None,
+            0xFF51..=0xFF55 if self.is_cgb => self.write_hdma(address, value),
+            0xFF70 if self.is_cgb => self.set_wram_bank(value),
+            //KEY1, only the prepare speed switch bit is writable
+            0xFF4D if self.is_cgb => self.speed_switch_armed = value & 0x01 != 0,
+            _ => {}
+        }
+    }
+
+    /**
+     * Unmapped I/O registers read as 0xFF
+     */
+    fn read_io(&self, address: u16) -> u8 {
+        let value = match address {
+            0xFF00 => Some(self.joypad.read_register()),
+            0xFF04..=0xFF07 => self.timer.read_register(address),
+            0xFF0F => Some(self.interrupts.read_if()),
+            0xFF46 => Some(self.oam_dma.read_register()),
+            0xFF10..=0xFF3F => self.apu.read_register(address),
+            0xFF40..=0xFF45 | 0xFF47..=0xFF4B | 0xFF4F | 0xFF68..=0xFF6C => {
+                self.ppu.read_register(address)
+            }
+            0xFF4D if self.is_cgb => {
+                Some((self.double_speed as u8) << 7 | 0x7E | self.speed_switch_armed as u8)
             }
+            0xFF51..=0xFF55 if self.is_cgb => Some(self.hdma.read_register(address)),
+            0xFF70 if self.is_cgb => Some(0xF8 | self.get_wram_bank()),
+            _ => None,
+        };
+        value.unwrap_or(0xFF)
+    }
+}
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        if let Err(error) = self.flush_save_file() {
+            eprintln!("Couldn't write save file: {}", error);
         }
     }
 }
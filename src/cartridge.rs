@@ -1,60 +1,107 @@
-use std::fs;
+use std::{error::Error, fmt, fs, path::PathBuf};
+
+use crate::header::{CartridgeHeader, HeaderError};
+
+/**
+ * Memory bank controller a cartridge needs, as reported by the cartridge type byte (0x147)
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mapper {
+    RomOnly,
+    Mbc1,
+    Mbc2,
+    Mbc3,
+    Mbc5,
+    Mbc6,
+    Mbc7,
+    Mmm01,
+    HuC1,
+    HuC3,
+    PocketCamera,
+    BandaiTama5,
+}
+
+impl fmt::Display for Mapper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Mapper::RomOnly => "ROM only",
+            Mapper::Mbc1 => "MBC1",
+            Mapper::Mbc2 => "MBC2",
+            Mapper::Mbc3 => "MBC3",
+            Mapper::Mbc5 => "MBC5",
+            Mapper::Mbc6 => "MBC6",
+            Mapper::Mbc7 => "MBC7",
+            Mapper::Mmm01 => "MMM01",
+            Mapper::HuC1 => "HuC1",
+            Mapper::HuC3 => "HuC3",
+            Mapper::PocketCamera => "Pocket Camera",
+            Mapper::BandaiTama5 => "Bandai TAMA5",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CartridgeError {
+    InvalidHeader(HeaderError),
+    UnsupportedMapper(Mapper),
+}
+
+impl fmt::Display for CartridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CartridgeError::InvalidHeader(error) => write!(f, "Invalid header: {}", error),
+            CartridgeError::UnsupportedMapper(mapper) => {
+                write!(f, "The {} mapper is not supported", mapper)
+            }
+        }
+    }
+}
+
+impl Error for CartridgeError {}
+
+impl From<HeaderError> for CartridgeError {
+    fn from(error: HeaderError) -> Self {
+        CartridgeError::InvalidHeader(error)
+    }
+}
 
 pub struct Cartidge {
     pub content: Vec<u8>,
+    path: Option<PathBuf>,
 }
 
 impl Cartidge {
     pub fn new(path: String) -> Self {
         let content =
-            fs::read(path.clone()).expect(format!("Couldn't find rom at {}", path).as_str());
-        Cartidge { content }
+            fs::read(path.clone()).unwrap_or_else(|_| panic!("Couldn't find rom at {}", path));
+        Cartidge {
+            content,
+            path: Some(PathBuf::from(path)),
+        }
     }
 
     #[cfg(test)]
     pub fn new_from_bytes(content: Vec<u8>) -> Self
     {
-        Cartidge {content}
-    }
-
-    pub fn get_rom_banks(&self) -> usize {
-        match self.content[0x148] {
-            0x00 => 2,
-            0x01 => 4,
-            0x02 => 8,
-            0x03 => 16,
-            0x04 => 32,
-            0x05 => 64,
-            0x06 => 128,
-            0x07 => 256,
-            0x08 => 512,
-            0x52 => 72,
-            0x53 => 80,
-            0x54 => 96,
-            _ => {
-                panic!("Not supported Rom Size|");
-            }
-        }
+        Cartidge {content, path: None}
     }
 
-    pub fn get_ram_banks(&self) -> usize {
-        match self.content[0x149] {
-            0x0 => 0,
-            0x02 => 1,
-            0x03 => 4,
-            0x04 => 16,
-            0x05 => 8,
-            _ => {
-                panic!("Not supported Ram Size!");
-            }
-        }
+    /**
+     * Battery backed cartridges are saved next to the rom with the .sav extension
+     */
+    pub fn get_save_path(&self) -> Option<PathBuf> {
+        self.path.as_ref().map(|path| path.with_extension("sav"))
     }
 
-    pub fn get_cgb(&self) -> bool {
-        match self.content[0x143] {
-            0x80 => true,
-            0xC0 => true,
-            _ => false,
-        }
+    pub fn get_header(&self) -> Result<CartridgeHeader, HeaderError> {
+        CartridgeHeader::parse(&self.content)
+    }
+
+    /**
+     * Checks the logo and checksums, a failure here doesn't stop the rom from running
+     */
+    pub fn verify_header(&self) -> Result<(), HeaderError> {
+        self.get_header()?.verify(&self.content)
     }
 }
@@ -1,8 +1,6 @@
-use core::panic;
-
 use enumn::N;
 
-#[allow(non_camel_case_types)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum Operand 
 {
@@ -73,7 +71,7 @@ pub enum JumpType
     Relative
 }
 
-#[allow(non_camel_case_types)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
 pub enum Instruction 
 {
     ADD(Operand, Operand),
@@ -113,12 +111,23 @@ pub enum Instruction
     CCF,
     HALT,
     DI,
-    EI
+    EI,
+    RLC(Operand),
+    RRC(Operand),
+    RL(Operand),
+    RR(Operand),
+    SLA(Operand),
+    SRA(Operand),
+    SWAP(Operand),
+    SRL(Operand),
+    BIT(u8, Operand),
+    RES(u8, Operand),
+    SET(u8, Operand)
 }
 
 #[derive(N, Clone, PartialEq, Debug)]
 #[repr(u8)]
-#[allow(non_camel_case_types)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
 pub enum Opcode
 {
     NOP=0o00,
@@ -379,6 +388,269 @@ pub enum Opcode
     RST_38=0o377
 }
 
+#[derive(N, Clone, PartialEq, Debug)]
+#[repr(u8)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub enum PrefixedOpcode
+{
+    RLC_B=0o0,
+    RLC_C=0o1,
+    RLC_D=0o2,
+    RLC_E=0o3,
+    RLC_H=0o4,
+    RLC_L=0o5,
+    RLC_iHL=0o6,
+    RLC_A=0o7,
+    RRC_B=0o10,
+    RRC_C=0o11,
+    RRC_D=0o12,
+    RRC_E=0o13,
+    RRC_H=0o14,
+    RRC_L=0o15,
+    RRC_iHL=0o16,
+    RRC_A=0o17,
+    RL_B=0o20,
+    RL_C=0o21,
+    RL_D=0o22,
+    RL_E=0o23,
+    RL_H=0o24,
+    RL_L=0o25,
+    RL_iHL=0o26,
+    RL_A=0o27,
+    RR_B=0o30,
+    RR_C=0o31,
+    RR_D=0o32,
+    RR_E=0o33,
+    RR_H=0o34,
+    RR_L=0o35,
+    RR_iHL=0o36,
+    RR_A=0o37,
+    SLA_B=0o40,
+    SLA_C=0o41,
+    SLA_D=0o42,
+    SLA_E=0o43,
+    SLA_H=0o44,
+    SLA_L=0o45,
+    SLA_iHL=0o46,
+    SLA_A=0o47,
+    SRA_B=0o50,
+    SRA_C=0o51,
+    SRA_D=0o52,
+    SRA_E=0o53,
+    SRA_H=0o54,
+    SRA_L=0o55,
+    SRA_iHL=0o56,
+    SRA_A=0o57,
+    SWAP_B=0o60,
+    SWAP_C=0o61,
+    SWAP_D=0o62,
+    SWAP_E=0o63,
+    SWAP_H=0o64,
+    SWAP_L=0o65,
+    SWAP_iHL=0o66,
+    SWAP_A=0o67,
+    SRL_B=0o70,
+    SRL_C=0o71,
+    SRL_D=0o72,
+    SRL_E=0o73,
+    SRL_H=0o74,
+    SRL_L=0o75,
+    SRL_iHL=0o76,
+    SRL_A=0o77,
+    BIT_0_B=0o100,
+    BIT_0_C=0o101,
+    BIT_0_D=0o102,
+    BIT_0_E=0o103,
+    BIT_0_H=0o104,
+    BIT_0_L=0o105,
+    BIT_0_iHL=0o106,
+    BIT_0_A=0o107,
+    BIT_1_B=0o110,
+    BIT_1_C=0o111,
+    BIT_1_D=0o112,
+    BIT_1_E=0o113,
+    BIT_1_H=0o114,
+    BIT_1_L=0o115,
+    BIT_1_iHL=0o116,
+    BIT_1_A=0o117,
+    BIT_2_B=0o120,
+    BIT_2_C=0o121,
+    BIT_2_D=0o122,
+    BIT_2_E=0o123,
+    BIT_2_H=0o124,
+    BIT_2_L=0o125,
+    BIT_2_iHL=0o126,
+    BIT_2_A=0o127,
+    BIT_3_B=0o130,
+    BIT_3_C=0o131,
+    BIT_3_D=0o132,
+    BIT_3_E=0o133,
+    BIT_3_H=0o134,
+    BIT_3_L=0o135,
+    BIT_3_iHL=0o136,
+    BIT_3_A=0o137,
+    BIT_4_B=0o140,
+    BIT_4_C=0o141,
+    BIT_4_D=0o142,
+    BIT_4_E=0o143,
+    BIT_4_H=0o144,
+    BIT_4_L=0o145,
+    BIT_4_iHL=0o146,
+    BIT_4_A=0o147,
+    BIT_5_B=0o150,
+    BIT_5_C=0o151,
+    BIT_5_D=0o152,
+    BIT_5_E=0o153,
+    BIT_5_H=0o154,
+    BIT_5_L=0o155,
+    BIT_5_iHL=0o156,
+    BIT_5_A=0o157,
+    BIT_6_B=0o160,
+    BIT_6_C=0o161,
+    BIT_6_D=0o162,
+    BIT_6_E=0o163,
+    BIT_6_H=0o164,
+    BIT_6_L=0o165,
+    BIT_6_iHL=0o166,
+    BIT_6_A=0o167,
+    BIT_7_B=0o170,
+    BIT_7_C=0o171,
+    BIT_7_D=0o172,
+    BIT_7_E=0o173,
+    BIT_7_H=0o174,
+    BIT_7_L=0o175,
+    BIT_7_iHL=0o176,
+    BIT_7_A=0o177,
+    RES_0_B=0o200,
+    RES_0_C=0o201,
+    RES_0_D=0o202,
+    RES_0_E=0o203,
+    RES_0_H=0o204,
+    RES_0_L=0o205,
+    RES_0_iHL=0o206,
+    RES_0_A=0o207,
+    RES_1_B=0o210,
+    RES_1_C=0o211,
+    RES_1_D=0o212,
+    RES_1_E=0o213,
+    RES_1_H=0o214,
+    RES_1_L=0o215,
+    RES_1_iHL=0o216,
+    RES_1_A=0o217,
+    RES_2_B=0o220,
+    RES_2_C=0o221,
+    RES_2_D=0o222,
+    RES_2_E=0o223,
+    RES_2_H=0o224,
+    RES_2_L=0o225,
+    RES_2_iHL=0o226,
+    RES_2_A=0o227,
+    RES_3_B=0o230,
+    RES_3_C=0o231,
+    RES_3_D=0o232,
+    RES_3_E=0o233,
+    RES_3_H=0o234,
+    RES_3_L=0o235,
+    RES_3_iHL=0o236,
+    RES_3_A=0o237,
+    RES_4_B=0o240,
+    RES_4_C=0o241,
+    RES_4_D=0o242,
+    RES_4_E=0o243,
+    RES_4_H=0o244,
+    RES_4_L=0o245,
+    RES_4_iHL=0o246,
+    RES_4_A=0o247,
+    RES_5_B=0o250,
+    RES_5_C=0o251,
+    RES_5_D=0o252,
+    RES_5_E=0o253,
+    RES_5_H=0o254,
+    RES_5_L=0o255,
+    RES_5_iHL=0o256,
+    RES_5_A=0o257,
+    RES_6_B=0o260,
+    RES_6_C=0o261,
+    RES_6_D=0o262,
+    RES_6_E=0o263,
+    RES_6_H=0o264,
+    RES_6_L=0o265,
+    RES_6_iHL=0o266,
+    RES_6_A=0o267,
+    RES_7_B=0o270,
+    RES_7_C=0o271,
+    RES_7_D=0o272,
+    RES_7_E=0o273,
+    RES_7_H=0o274,
+    RES_7_L=0o275,
+    RES_7_iHL=0o276,
+    RES_7_A=0o277,
+    SET_0_B=0o300,
+    SET_0_C=0o301,
+    SET_0_D=0o302,
+    SET_0_E=0o303,
+    SET_0_H=0o304,
+    SET_0_L=0o305,
+    SET_0_iHL=0o306,
+    SET_0_A=0o307,
+    SET_1_B=0o310,
+    SET_1_C=0o311,
+    SET_1_D=0o312,
+    SET_1_E=0o313,
+    SET_1_H=0o314,
+    SET_1_L=0o315,
+    SET_1_iHL=0o316,
+    SET_1_A=0o317,
+    SET_2_B=0o320,
+    SET_2_C=0o321,
+    SET_2_D=0o322,
+    SET_2_E=0o323,
+    SET_2_H=0o324,
+    SET_2_L=0o325,
+    SET_2_iHL=0o326,
+    SET_2_A=0o327,
+    SET_3_B=0o330,
+    SET_3_C=0o331,
+    SET_3_D=0o332,
+    SET_3_E=0o333,
+    SET_3_H=0o334,
+    SET_3_L=0o335,
+    SET_3_iHL=0o336,
+    SET_3_A=0o337,
+    SET_4_B=0o340,
+    SET_4_C=0o341,
+    SET_4_D=0o342,
+    SET_4_E=0o343,
+    SET_4_H=0o344,
+    SET_4_L=0o345,
+    SET_4_iHL=0o346,
+    SET_4_A=0o347,
+    SET_5_B=0o350,
+    SET_5_C=0o351,
+    SET_5_D=0o352,
+    SET_5_E=0o353,
+    SET_5_H=0o354,
+    SET_5_L=0o355,
+    SET_5_iHL=0o356,
+    SET_5_A=0o357,
+    SET_6_B=0o360,
+    SET_6_C=0o361,
+    SET_6_D=0o362,
+    SET_6_E=0o363,
+    SET_6_H=0o364,
+    SET_6_L=0o365,
+    SET_6_iHL=0o366,
+    SET_6_A=0o367,
+    SET_7_B=0o370,
+    SET_7_C=0o371,
+    SET_7_D=0o372,
+    SET_7_E=0o373,
+    SET_7_H=0o374,
+    SET_7_L=0o375,
+    SET_7_iHL=0o376,
+    SET_7_A=0o377
+}
+
 pub fn get_instruction_specs_from_code(code: u8) -> Option<(Instruction,u64)>
 {
     match code
@@ -415,7 +687,7 @@ pub fn get_instruction_specs_from_code(code: u8) -> Option<(Instruction,u64)>
         0o35 => Some((Instruction::DEC(Operand::E), 4)),
         0o36 => Some((Instruction::LD(Operand::E, Operand::N8), 8)),
         0o37 => Some((Instruction::RRA, 4)),
-        0o40 => Some((Instruction::JP_ZERO(JumpType::Relative, false, Operand::E8), 12)),
+        0o40 => Some((Instruction::JP_ZERO(JumpType::Relative, false, Operand::E8), 8)),
         0o41 => Some((Instruction::LD(Operand::HL, Operand::N16), 12)),
         0o42 => Some((Instruction::LD(Operand::iHLPLUS, Operand::A), 8)),
         0o43 => Some((Instruction::INC(Operand::HL), 8)),
@@ -423,7 +695,7 @@ pub fn get_instruction_specs_from_code(code: u8) -> Option<(Instruction,u64)>
         0o45 => Some((Instruction::DEC(Operand::H), 4)),
         0o46 => Some((Instruction::LD(Operand::H, Operand::N8), 8)),
         0o47 => Some((Instruction::DAA, 4)),
-        0o50 => Some((Instruction::JP_ZERO(JumpType::Relative, true, Operand::E8), 12)),
+        0o50 => Some((Instruction::JP_ZERO(JumpType::Relative, true, Operand::E8), 8)),
         0o51 => Some((Instruction::ADD(Operand::HL, Operand::HL), 8)),
         0o52 => Some((Instruction::LD(Operand::A, Operand::iHLPLUS), 8)),
         0o53 => Some((Instruction::DEC(Operand::HL), 8)),
@@ -431,7 +703,7 @@ pub fn get_instruction_specs_from_code(code: u8) -> Option<(Instruction,u64)>
         0o55 => Some((Instruction::DEC(Operand::L), 4)),
         0o56 => Some((Instruction::LD(Operand::L, Operand::N8), 8)),
         0o57 => Some((Instruction::CPL, 4)),
-        0o60 => Some((Instruction::JP_CARRY(JumpType::Relative, false, Operand::E8), 12)),
+        0o60 => Some((Instruction::JP_CARRY(JumpType::Relative, false, Operand::E8), 8)),
         0o61 => Some((Instruction::LD(Operand::SP, Operand::N16), 12)),
         0o62 => Some((Instruction::LD(Operand::iHLMINUS, Operand::A), 8)),
         0o63 => Some((Instruction::INC(Operand::SP), 8)),
@@ -439,7 +711,7 @@ pub fn get_instruction_specs_from_code(code: u8) -> Option<(Instruction,u64)>
         0o65 => Some((Instruction::DEC(Operand::iHL), 12)),
         0o66 => Some((Instruction::LD(Operand::iHL, Operand::N8), 12)),
         0o67 => Some((Instruction::SCF, 4)),
-        0o70 => Some((Instruction::JP_CARRY(JumpType::Relative, true, Operand::E8), 12)),
+        0o70 => Some((Instruction::JP_CARRY(JumpType::Relative, true, Operand::E8), 8)),
         0o71 => Some((Instruction::ADD(Operand::HL, Operand::SP), 8)),
         0o72 => Some((Instruction::LD(Operand::A, Operand::iHLMINUS), 8)),
         0o73 => Some((Instruction::DEC(Operand::SP), 8)),
@@ -578,7 +850,7 @@ pub fn get_instruction_specs_from_code(code: u8) -> Option<(Instruction,u64)>
         0o300 => Some((Instruction::RET_ZERO(false), 8)),
         0o301 => Some((Instruction::POP(Operand::BC), 12)),
         0o302 => Some((Instruction::JP_ZERO(JumpType::Absolute, false, Operand::N16), 12)),
-        0o303 => Some((Instruction::JP(JumpType::Absolute, Operand::N16), 12)),
+        0o303 => Some((Instruction::JP(JumpType::Absolute, Operand::N16), 16)),
         0o304 => Some((Instruction::CALL_ZERO(false, Operand::N16), 12)),
         0o305 => Some((Instruction::PUSH(Operand::BC), 16)),
         0o306 => Some((Instruction::ADD(Operand::A, Operand::N8), 8)),
@@ -588,7 +860,7 @@ pub fn get_instruction_specs_from_code(code: u8) -> Option<(Instruction,u64)>
         0o312 => Some((Instruction::JP_ZERO(JumpType::Absolute, true, Operand::N16), 12)),
         0o313 => Some((Instruction::PREFIX, 8)),
         0o314 => Some((Instruction::CALL_ZERO(true, Operand::N16), 12)),
-        0o315 => Some((Instruction::CALL(Operand::N16), 12)),
+        0o315 => Some((Instruction::CALL(Operand::N16), 24)),
         0o316 => Some((Instruction::ADC(Operand::A, Operand::N8), 8)),
         0o317 => Some((Instruction::RST(0x08), 16)),
         0o320 => Some((Instruction::RET_CARRY(false), 8)),
@@ -612,10 +884,10 @@ pub fn get_instruction_specs_from_code(code: u8) -> Option<(Instruction,u64)>
         0o347 => Some((Instruction::RST(0x20), 16)),
         0o350 => Some((Instruction::ADD(Operand::SP, Operand::E8), 16)),
         0o351 => Some((Instruction::JP(JumpType::Absolute, Operand::HL), 4)),
-        0o352 => Some((Instruction::LD(Operand::A, Operand::A16), 12)),
+        0o352 => Some((Instruction::LD(Operand::A16, Operand::A), 16)),
         0o356 => Some((Instruction::XOR(Operand::A, Operand::N8), 8)),
         0o357 => Some((Instruction::RST(0x28), 16)),
-        0o360 => Some((Instruction::LD(Operand::A8, Operand::SP), 20)),
+        0o360 => Some((Instruction::LD(Operand::A, Operand::A8), 12)),
         0o361 => Some((Instruction::POP(Operand::AF), 12)),
         0o362 => Some((Instruction::LD(Operand::A, Operand::iC), 8)),
         0o363 => Some((Instruction::DI, 4)),
@@ -624,7 +896,7 @@ pub fn get_instruction_specs_from_code(code: u8) -> Option<(Instruction,u64)>
         0o367 => Some((Instruction::RST(0x30), 16)),
         0o370 => Some((Instruction::LD(Operand::HL, Operand::SP_PLUS_E8), 12)),
         0o371 => Some((Instruction::LD(Operand::SP, Operand::HL), 8)),
-        0o372 => Some((Instruction::LD(Operand::A, Operand::A16), 12)),
+        0o372 => Some((Instruction::LD(Operand::A, Operand::A16), 16)),
         0o373 => Some((Instruction::EI, 4)),
         0o376 => Some((Instruction::CP(Operand::A, Operand::N8), 8)),
         0o377 => Some((Instruction::RST(0x38), 16)),
@@ -632,3 +904,285 @@ pub fn get_instruction_specs_from_code(code: u8) -> Option<(Instruction,u64)>
     
     }
 }
+
+/**
+ * Decodes the opcode following a 0xCB prefix. The cycles include the fetch of the prefix itself
+ */
+pub fn get_prefixed_instruction_specs_from_code(code: u8) -> Option<(Instruction,u64)>
+{
+    match code
+    {
+        0o0 => Some((Instruction::RLC(Operand::B), 8)),
+        0o1 => Some((Instruction::RLC(Operand::C), 8)),
+        0o2 => Some((Instruction::RLC(Operand::D), 8)),
+        0o3 => Some((Instruction::RLC(Operand::E), 8)),
+        0o4 => Some((Instruction::RLC(Operand::H), 8)),
+        0o5 => Some((Instruction::RLC(Operand::L), 8)),
+        0o6 => Some((Instruction::RLC(Operand::iHL), 16)),
+        0o7 => Some((Instruction::RLC(Operand::A), 8)),
+        0o10 => Some((Instruction::RRC(Operand::B), 8)),
+        0o11 => Some((Instruction::RRC(Operand::C), 8)),
+        0o12 => Some((Instruction::RRC(Operand::D), 8)),
+        0o13 => Some((Instruction::RRC(Operand::E), 8)),
+        0o14 => Some((Instruction::RRC(Operand::H), 8)),
+        0o15 => Some((Instruction::RRC(Operand::L), 8)),
+        0o16 => Some((Instruction::RRC(Operand::iHL), 16)),
+        0o17 => Some((Instruction::RRC(Operand::A), 8)),
+        0o20 => Some((Instruction::RL(Operand::B), 8)),
+        0o21 => Some((Instruction::RL(Operand::C), 8)),
+        0o22 => Some((Instruction::RL(Operand::D), 8)),
+        0o23 => Some((Instruction::RL(Operand::E), 8)),
+        0o24 => Some((Instruction::RL(Operand::H), 8)),
+        0o25 => Some((Instruction::RL(Operand::L), 8)),
+        0o26 => Some((Instruction::RL(Operand::iHL), 16)),
+        0o27 => Some((Instruction::RL(Operand::A), 8)),
+        0o30 => Some((Instruction::RR(Operand::B), 8)),
+        0o31 => Some((Instruction::RR(Operand::C), 8)),
+        0o32 => Some((Instruction::RR(Operand::D), 8)),
+        0o33 => Some((Instruction::RR(Operand::E), 8)),
+        0o34 => Some((Instruction::RR(Operand::H), 8)),
+        0o35 => Some((Instruction::RR(Operand::L), 8)),
+        0o36 => Some((Instruction::RR(Operand::iHL), 16)),
+        0o37 => Some((Instruction::RR(Operand::A), 8)),
+        0o40 => Some((Instruction::SLA(Operand::B), 8)),
+        0o41 => Some((Instruction::SLA(Operand::C), 8)),
+        0o42 => Some((Instruction::SLA(Operand::D), 8)),
+        0o43 => Some((Instruction::SLA(Operand::E), 8)),
+        0o44 => Some((Instruction::SLA(Operand::H), 8)),
+        0o45 => Some((Instruction::SLA(Operand::L), 8)),
+        0o46 => Some((Instruction::SLA(Operand::iHL), 16)),
+        0o47 => Some((Instruction::SLA(Operand::A), 8)),
+        0o50 => Some((Instruction::SRA(Operand::B), 8)),
+        0o51 => Some((Instruction::SRA(Operand::C), 8)),
+        0o52 => Some((Instruction::SRA(Operand::D), 8)),
+        0o53 => Some((Instruction::SRA(Operand::E), 8)),
+        0o54 => Some((Instruction::SRA(Operand::H), 8)),
+        0o55 => Some((Instruction::SRA(Operand::L), 8)),
+        0o56 => Some((Instruction::SRA(Operand::iHL), 16)),
+        0o57 => Some((Instruction::SRA(Operand::A), 8)),
+        0o60 => Some((Instruction::SWAP(Operand::B), 8)),
+        0o61 => Some((Instruction::SWAP(Operand::C), 8)),
+        0o62 => Some((Instruction::SWAP(Operand::D), 8)),
+        0o63 => Some((Instruction::SWAP(Operand::E), 8)),
+        0o64 => Some((Instruction::SWAP(Operand::H), 8)),
+        0o65 => Some((Instruction::SWAP(Operand::L), 8)),
+        0o66 => Some((Instruction::SWAP(Operand::iHL), 16)),
+        0o67 => Some((Instruction::SWAP(Operand::A), 8)),
+        0o70 => Some((Instruction::SRL(Operand::B), 8)),
+        0o71 => Some((Instruction::SRL(Operand::C), 8)),
+        0o72 => Some((Instruction::SRL(Operand::D), 8)),
+        0o73 => Some((Instruction::SRL(Operand::E), 8)),
+        0o74 => Some((Instruction::SRL(Operand::H), 8)),
+        0o75 => Some((Instruction::SRL(Operand::L), 8)),
+        0o76 => Some((Instruction::SRL(Operand::iHL), 16)),
+        0o77 => Some((Instruction::SRL(Operand::A), 8)),
+        0o100 => Some((Instruction::BIT(0, Operand::B), 8)),
+        0o101 => Some((Instruction::BIT(0, Operand::C), 8)),
+        0o102 => Some((Instruction::BIT(0, Operand::D), 8)),
+        0o103 => Some((Instruction::BIT(0, Operand::E), 8)),
+        0o104 => Some((Instruction::BIT(0, Operand::H), 8)),
+        0o105 => Some((Instruction::BIT(0, Operand::L), 8)),
+        0o106 => Some((Instruction::BIT(0, Operand::iHL), 12)),
+        0o107 => Some((Instruction::BIT(0, Operand::A), 8)),
+        0o110 => Some((Instruction::BIT(1, Operand::B), 8)),
+        0o111 => Some((Instruction::BIT(1, Operand::C), 8)),
+        0o112 => Some((Instruction::BIT(1, Operand::D), 8)),
+        0o113 => Some((Instruction::BIT(1, Operand::E), 8)),
+        0o114 => Some((Instruction::BIT(1, Operand::H), 8)),
+        0o115 => Some((Instruction::BIT(1, Operand::L), 8)),
+        0o116 => Some((Instruction::BIT(1, Operand::iHL), 12)),
+        0o117 => Some((Instruction::BIT(1, Operand::A), 8)),
+        0o120 => Some((Instruction::BIT(2, Operand::B), 8)),
+        0o121 => Some((Instruction::BIT(2, Operand::C), 8)),
+        0o122 => Some((Instruction::BIT(2, Operand::D), 8)),
+        0o123 => Some((Instruction::BIT(2, Operand::E), 8)),
+        0o124 => Some((Instruction::BIT(2, Operand::H), 8)),
+        0o125 => Some((Instruction::BIT(2, Operand::L), 8)),
+        0o126 => Some((Instruction::BIT(2, Operand::iHL), 12)),
+        0o127 => Some((Instruction::BIT(2, Operand::A), 8)),
+        0o130 => Some((Instruction::BIT(3, Operand::B), 8)),
+        0o131 => Some((Instruction::BIT(3, Operand::C), 8)),
+        0o132 => Some((Instruction::BIT(3, Operand::D), 8)),
+        0o133 => Some((Instruction::BIT(3, Operand::E), 8)),
+        0o134 => Some((Instruction::BIT(3, Operand::H), 8)),
+        0o135 => Some((Instruction::BIT(3, Operand::L), 8)),
+        0o136 => Some((Instruction::BIT(3, Operand::iHL), 12)),
+        0o137 => Some((Instruction::BIT(3, Operand::A), 8)),
+        0o140 => Some((Instruction::BIT(4, Operand::B), 8)),
+        0o141 => Some((Instruction::BIT(4, Operand::C), 8)),
+        0o142 => Some((Instruction::BIT(4, Operand::D), 8)),
+        0o143 => Some((Instruction::BIT(4, Operand::E), 8)),
+        0o144 => Some((Instruction::BIT(4, Operand::H), 8)),
+        0o145 => Some((Instruction::BIT(4, Operand::L), 8)),
+        0o146 => Some((Instruction::BIT(4, Operand::iHL), 12)),
+        0o147 => Some((Instruction::BIT(4, Operand::A), 8)),
+        0o150 => Some((Instruction::BIT(5, Operand::B), 8)),
+        0o151 => Some((Instruction::BIT(5, Operand::C), 8)),
+        0o152 => Some((Instruction::BIT(5, Operand::D), 8)),
+        0o153 => Some((Instruction::BIT(5, Operand::E), 8)),
+        0o154 => Some((Instruction::BIT(5, Operand::H), 8)),
+        0o155 => Some((Instruction::BIT(5, Operand::L), 8)),
+        0o156 => Some((Instruction::BIT(5, Operand::iHL), 12)),
+        0o157 => Some((Instruction::BIT(5, Operand::A), 8)),
+        0o160 => Some((Instruction::BIT(6, Operand::B), 8)),
+        0o161 => Some((Instruction::BIT(6, Operand::C), 8)),
+        0o162 => Some((Instruction::BIT(6, Operand::D), 8)),
+        0o163 => Some((Instruction::BIT(6, Operand::E), 8)),
+        0o164 => Some((Instruction::BIT(6, Operand::H), 8)),
+        0o165 => Some((Instruction::BIT(6, Operand::L), 8)),
+        0o166 => Some((Instruction::BIT(6, Operand::iHL), 12)),
+        0o167 => Some((Instruction::BIT(6, Operand::A), 8)),
+        0o170 => Some((Instruction::BIT(7, Operand::B), 8)),
+        0o171 => Some((Instruction::BIT(7, Operand::C), 8)),
+        0o172 => Some((Instruction::BIT(7, Operand::D), 8)),
+        0o173 => Some((Instruction::BIT(7, Operand::E), 8)),
+        0o174 => Some((Instruction::BIT(7, Operand::H), 8)),
+        0o175 => Some((Instruction::BIT(7, Operand::L), 8)),
+        0o176 => Some((Instruction::BIT(7, Operand::iHL), 12)),
+        0o177 => Some((Instruction::BIT(7, Operand::A), 8)),
+        0o200 => Some((Instruction::RES(0, Operand::B), 8)),
+        0o201 => Some((Instruction::RES(0, Operand::C), 8)),
+        0o202 => Some((Instruction::RES(0, Operand::D), 8)),
+        0o203 => Some((Instruction::RES(0, Operand::E), 8)),
+        0o204 => Some((Instruction::RES(0, Operand::H), 8)),
+        0o205 => Some((Instruction::RES(0, Operand::L), 8)),
+        0o206 => Some((Instruction::RES(0, Operand::iHL), 16)),
+        0o207 => Some((Instruction::RES(0, Operand::A), 8)),
+        0o210 => Some((Instruction::RES(1, Operand::B), 8)),
+        0o211 => Some((Instruction::RES(1, Operand::C), 8)),
+        0o212 => Some((Instruction::RES(1, Operand::D), 8)),
+        0o213 => Some((Instruction::RES(1, Operand::E), 8)),
+        0o214 => Some((Instruction::RES(1, Operand::H), 8)),
+        0o215 => Some((Instruction::RES(1, Operand::L), 8)),
+        0o216 => Some((Instruction::RES(1, Operand::iHL), 16)),
+        0o217 => Some((Instruction::RES(1, Operand::A), 8)),
+        0o220 => Some((Instruction::RES(2, Operand::B), 8)),
+        0o221 => Some((Instruction::RES(2, Operand::C), 8)),
+        0o222 => Some((Instruction::RES(2, Operand::D), 8)),
+        0o223 => Some((Instruction::RES(2, Operand::E), 8)),
+        0o224 => Some((Instruction::RES(2, Operand::H), 8)),
+        0o225 => Some((Instruction::RES(2, Operand::L), 8)),
+        0o226 => Some((Instruction::RES(2, Operand::iHL), 16)),
+        0o227 => Some((Instruction::RES(2, Operand::A), 8)),
+        0o230 => Some((Instruction::RES(3, Operand::B), 8)),
+        0o231 => Some((Instruction::RES(3, Operand::C), 8)),
+        0o232 => Some((Instruction::RES(3, Operand::D), 8)),
+        0o233 => Some((Instruction::RES(3, Operand::E), 8)),
+        0o234 => Some((Instruction::RES(3, Operand::H), 8)),
+        0o235 => Some((Instruction::RES(3, Operand::L), 8)),
+        0o236 => Some((Instruction::RES(3, Operand::iHL), 16)),
+        0o237 => Some((Instruction::RES(3, Operand::A), 8)),
+        0o240 => Some((Instruction::RES(4, Operand::B), 8)),
+        0o241 => Some((Instruction::RES(4, Operand::C), 8)),
+        0o242 => Some((Instruction::RES(4, Operand::D), 8)),
+        0o243 => Some((Instruction::RES(4, Operand::E), 8)),
+        0o244 => Some((Instruction::RES(4, Operand::H), 8)),
+        0o245 => Some((Instruction::RES(4, Operand::L), 8)),
+        0o246 => Some((Instruction::RES(4, Operand::iHL), 16)),
+        0o247 => Some((Instruction::RES(4, Operand::A), 8)),
+        0o250 => Some((Instruction::RES(5, Operand::B), 8)),
+        0o251 => Some((Instruction::RES(5, Operand::C), 8)),
+        0o252 => Some((Instruction::RES(5, Operand::D), 8)),
+        0o253 => Some((Instruction::RES(5, Operand::E), 8)),
+        0o254 => Some((Instruction::RES(5, Operand::H), 8)),
+        0o255 => Some((Instruction::RES(5, Operand::L), 8)),
+        0o256 => Some((Instruction::RES(5, Operand::iHL), 16)),
+        0o257 => Some((Instruction::RES(5, Operand::A), 8)),
+        0o260 => Some((Instruction::RES(6, Operand::B), 8)),
+        0o261 => Some((Instruction::RES(6, Operand::C), 8)),
+        0o262 => Some((Instruction::RES(6, Operand::D), 8)),
+        0o263 => Some((Instruction::RES(6, Operand::E), 8)),
+        0o264 => Some((Instruction::RES(6, Operand::H), 8)),
+        0o265 => Some((Instruction::RES(6, Operand::L), 8)),
+        0o266 => Some((Instruction::RES(6, Operand::iHL), 16)),
+        0o267 => Some((Instruction::RES(6, Operand::A), 8)),
+        0o270 => Some((Instruction::RES(7, Operand::B), 8)),
+        0o271 => Some((Instruction::RES(7, Operand::C), 8)),
+        0o272 => Some((Instruction::RES(7, Operand::D), 8)),
+        0o273 => Some((Instruction::RES(7, Operand::E), 8)),
+        0o274 => Some((Instruction::RES(7, Operand::H), 8)),
+        0o275 => Some((Instruction::RES(7, Operand::L), 8)),
+        0o276 => Some((Instruction::RES(7, Operand::iHL), 16)),
+        0o277 => Some((Instruction::RES(7, Operand::A), 8)),
+        0o300 => Some((Instruction::SET(0, Operand::B), 8)),
+        0o301 => Some((Instruction::SET(0, Operand::C), 8)),
+        0o302 => Some((Instruction::SET(0, Operand::D), 8)),
+        0o303 => Some((Instruction::SET(0, Operand::E), 8)),
+        0o304 => Some((Instruction::SET(0, Operand::H), 8)),
+        0o305 => Some((Instruction::SET(0, Operand::L), 8)),
+        0o306 => Some((Instruction::SET(0, Operand::iHL), 16)),
+        0o307 => Some((Instruction::SET(0, Operand::A), 8)),
+        0o310 => Some((Instruction::SET(1, Operand::B), 8)),
+        0o311 => Some((Instruction::SET(1, Operand::C), 8)),
+        0o312 => Some((Instruction::SET(1, Operand::D), 8)),
+        0o313 => Some((Instruction::SET(1, Operand::E), 8)),
+        0o314 => Some((Instruction::SET(1, Operand::H), 8)),
+        0o315 => Some((Instruction::SET(1, Operand::L), 8)),
+        0o316 => Some((Instruction::SET(1, Operand::iHL), 16)),
+        0o317 => Some((Instruction::SET(1, Operand::A), 8)),
+        0o320 => Some((Instruction::SET(2, Operand::B), 8)),
+        0o321 => Some((Instruction::SET(2, Operand::C), 8)),
+        0o322 => Some((Instruction::SET(2, Operand::D), 8)),
+        0o323 => Some((Instruction::SET(2, Operand::E), 8)),
+        0o324 => Some((Instruction::SET(2, Operand::H), 8)),
+        0o325 => Some((Instruction::SET(2, Operand::L), 8)),
+        0o326 => Some((Instruction::SET(2, Operand::iHL), 16)),
+        0o327 => Some((Instruction::SET(2, Operand::A), 8)),
+        0o330 => Some((Instruction::SET(3, Operand::B), 8)),
+        0o331 => Some((Instruction::SET(3, Operand::C), 8)),
+        0o332 => Some((Instruction::SET(3, Operand::D), 8)),
+        0o333 => Some((Instruction::SET(3, Operand::E), 8)),
+        0o334 => Some((Instruction::SET(3, Operand::H), 8)),
+        0o335 => Some((Instruction::SET(3, Operand::L), 8)),
+        0o336 => Some((Instruction::SET(3, Operand::iHL), 16)),
+        0o337 => Some((Instruction::SET(3, Operand::A), 8)),
+        0o340 => Some((Instruction::SET(4, Operand::B), 8)),
+        0o341 => Some((Instruction::SET(4, Operand::C), 8)),
+        0o342 => Some((Instruction::SET(4, Operand::D), 8)),
+        0o343 => Some((Instruction::SET(4, Operand::E), 8)),
+        0o344 => Some((Instruction::SET(4, Operand::H), 8)),
+        0o345 => Some((Instruction::SET(4, Operand::L), 8)),
+        0o346 => Some((Instruction::SET(4, Operand::iHL), 16)),
+        0o347 => Some((Instruction::SET(4, Operand::A), 8)),
+        0o350 => Some((Instruction::SET(5, Operand::B), 8)),
+        0o351 => Some((Instruction::SET(5, Operand::C), 8)),
+        0o352 => Some((Instruction::SET(5, Operand::D), 8)),
+        0o353 => Some((Instruction::SET(5, Operand::E), 8)),
+        0o354 => Some((Instruction::SET(5, Operand::H), 8)),
+        0o355 => Some((Instruction::SET(5, Operand::L), 8)),
+        0o356 => Some((Instruction::SET(5, Operand::iHL), 16)),
+        0o357 => Some((Instruction::SET(5, Operand::A), 8)),
+        0o360 => Some((Instruction::SET(6, Operand::B), 8)),
+        0o361 => Some((Instruction::SET(6, Operand::C), 8)),
+        0o362 => Some((Instruction::SET(6, Operand::D), 8)),
+        0o363 => Some((Instruction::SET(6, Operand::E), 8)),
+        0o364 => Some((Instruction::SET(6, Operand::H), 8)),
+        0o365 => Some((Instruction::SET(6, Operand::L), 8)),
+        0o366 => Some((Instruction::SET(6, Operand::iHL), 16)),
+        0o367 => Some((Instruction::SET(6, Operand::A), 8)),
+        0o370 => Some((Instruction::SET(7, Operand::B), 8)),
+        0o371 => Some((Instruction::SET(7, Operand::C), 8)),
+        0o372 => Some((Instruction::SET(7, Operand::D), 8)),
+        0o373 => Some((Instruction::SET(7, Operand::E), 8)),
+        0o374 => Some((Instruction::SET(7, Operand::H), 8)),
+        0o375 => Some((Instruction::SET(7, Operand::L), 8)),
+        0o376 => Some((Instruction::SET(7, Operand::iHL), 16)),
+        0o377 => Some((Instruction::SET(7, Operand::A), 8)),
+    }
+}
+
+/**
+ * Returns the cycles spent by a conditional instruction when its condition is met.
+ * get_instruction_specs_from_code reports the cost of the not taken path.
+ */
+pub fn get_taken_branch_cycles_from_code(code: u8) -> Option<u64>
+{
+    match code
+    {
+        0o40 | 0o50 | 0o60 | 0o70 => Some(12),
+        0o302 | 0o312 | 0o322 | 0o332 => Some(16),
+        0o304 | 0o314 | 0o324 | 0o334 => Some(24),
+        0o300 | 0o310 | 0o320 | 0o330 => Some(20),
+        _ => None
+    }
+}
@@ -1,16 +1,25 @@
 mod tests;
 
-    use core::{panic};
-    use std::fmt::format;
+use std::io;
 
 use byteorder::{ByteOrder, LittleEndian};
 
 use crate::{
+    audio::AudioSink,
+    boot_rom::BootRom,
     bus::Bus,
-    cartridge::{self, Cartidge},
+    cartridge::{Cartidge, CartridgeError},
     code,
+    interrupts::Interrupt,
+    joypad::Button,
+    mbc::{RtcClock, RumbleCallback},
+    model::Model,
+    ppu::Renderer,
 };
 
+//154 lines of 456 dots
+const CYCLES_PER_FRAME: u64 = 70224;
+
 pub struct Cpu {
     memory: Bus,
     a: u8,
@@ -23,13 +32,50 @@ pub struct Cpu {
     l: u8,
     sp: u16,
     pc: u16,
-    cycles: u64
+    cycles: u64,
+    ime: bool,
+    ime_scheduled: bool,
+    halted: bool,
+    halt_bug: bool,
+    stopped: bool,
+    //Cycles of the current step the bus has already been ticked through by memory accesses
+    ticked_cycles: u64
 }
 
 impl Cpu {
-    pub fn new(cart: Cartidge) -> Self {
+    /**
+     * Creates a cpu for the model the game is meant for, see new_with_model
+     */
+    pub fn new(cart: Cartidge) -> Result<Self, CartridgeError> {
+        let model = Model::detect(&cart.get_header()?);
+        Cpu::new_with_model(cart, model)
+    }
+
+    /**
+     * Creates a cpu in the state the boot rom of the model leaves the hardware in, ready to run the game
+     * at 0x0100
+     */
+    pub fn new_with_model(cart: Cartidge, model: Model) -> Result<Self, CartridgeError> {
+        let mut cpu = Cpu::with_bus(Bus::new(cart, model, None)?);
+        cpu.apply_post_boot_state();
+        Ok(cpu)
+    }
+
+    /**
+     * Creates a cpu that runs the given boot rom from 0x0000 before handing control to the game.
+     * The boot rom has to be one of the model
+     */
+    pub fn new_with_boot_rom(cart: Cartidge, model: Model, boot_rom: BootRom) -> Result<Self, CartridgeError> {
+        Ok(Cpu::with_bus(Bus::new(cart, model, Some(boot_rom))?))
+    }
+
+    pub fn get_model(&self) -> Model {
+        self.memory.get_model()
+    }
+
+    fn with_bus(memory: Bus) -> Self {
         Cpu {
-            memory: Bus::new(cart),
+            memory,
             a: 0,
             f: 0,
             b: 0,
@@ -40,7 +86,13 @@ impl Cpu {
             l: 0,
             sp: 0,
             pc: 0,
-            cycles: 0
+            cycles: 0,
+            ime: false,
+            ime_scheduled: false,
+            halted: false,
+            halt_bug: false,
+            stopped: false,
+            ticked_cycles: 0
         }
     }
 
@@ -50,20 +102,110 @@ impl Cpu {
     #[cfg(test)]
     pub fn new_test(cart:Cartidge) -> Self
     {
-        Cpu {
-            memory: Bus::new_test(cart),
-            a: 0,
-            f: 0,
-            b: 0,
-            c: 0,
-            d: 0,
-            e: 0,
-            h: 0,
-            l: 0,
-            sp: 0,
-            pc: 0,
-            cycles: 0
-        }
+        Cpu::with_bus(Bus::new_test(cart))
+    }
+
+    /**
+     * Selects whether the cartridge real time clock follows the emulated cycles or the host clock
+     */
+    pub fn set_rtc_clock(&mut self, clock: RtcClock) {
+        self.memory.set_rtc_clock(clock);
+    }
+
+    /**
+     * Registers the function notified when a rumble cartridge turns its motor on or off
+     */
+    pub fn set_rumble_callback(&mut self, callback: RumbleCallback) {
+        self.memory.set_rumble_callback(callback);
+    }
+
+    /**
+     * Tilts the cartridge for MBC7 games, in g along each axis
+     */
+    pub fn set_accelerometer(&mut self, x: f32, y: f32) {
+        self.memory.set_accelerometer(x, y);
+    }
+
+    /**
+     * Raw battery backed save data, laid out like the .sav file so tools can inspect or replace it
+     */
+    pub fn export_save_data(&self) -> Vec<u8> {
+        self.memory.export_save_data()
+    }
+
+    pub fn import_save_data(&mut self, data: &[u8]) {
+        self.memory.import_save_data(data);
+    }
+
+    /**
+     * Writes the .sav file right away instead of waiting for the next periodic flush
+     */
+    pub fn flush_save_file(&mut self) -> io::Result<()> {
+        self.memory.flush_save_file()
+    }
+
+    /**
+     * Returns the 160x144 framebuffer once per finished frame
+     */
+    pub fn take_frame(&mut self) -> Option<&[u8]> {
+        self.memory.ppu.take_frame()
+    }
+
+    pub fn get_rgb_framebuffer(&self) -> Vec<u8> {
+        self.memory.ppu.get_rgb_framebuffer()
+    }
+
+    pub fn set_renderer(&mut self, renderer: Renderer) {
+        self.memory.ppu.set_renderer(renderer);
+    }
+
+    pub fn set_audio_sink(&mut self, sink: Box<dyn AudioSink>) {
+        self.memory.apu.set_audio_sink(sink);
+    }
+
+    pub fn remove_audio_sink(&mut self) {
+        self.memory.apu.remove_audio_sink();
+    }
+
+    pub fn flush_audio(&mut self) {
+        self.memory.apu.flush_audio();
+    }
+
+    /**
+     * Presses a button, requesting the joypad interrupt if the game is watching its group
+     */
+    pub fn press(&mut self, button: Button) {
+        self.memory.joypad.press(button, &mut self.memory.interrupts);
+    }
+
+    pub fn release(&mut self, button: Button) {
+        self.memory.joypad.release(button, &mut self.memory.interrupts);
+    }
+
+    /**
+     * Registers as left by the boot rom of each model. On DMG and MGB the half carry and carry flags
+     * depend on the header checksum, the AGB boot rom differs from the CGB one in B and the flags
+     */
+    fn apply_post_boot_state(&mut self) {
+        let header_checksum = self.memory.read(0x014D).unwrap_or(0);
+        let dmg_flags = if header_checksum == 0 { 0x80 } else { 0xB0 };
+
+        let (af, bc, de, hl) = match self.memory.get_model() {
+            Model::Dmg => (0x0100 | dmg_flags, 0x0013, 0x00D8, 0x014D),
+            Model::Mgb => (0xFF00 | dmg_flags, 0x0013, 0x00D8, 0x014D),
+            Model::Sgb => (0x0100, 0x0014, 0x0000, 0xC060),
+            Model::Cgb if self.memory.is_cgb() => (0x1180, 0x0000, 0xFF56, 0x000D),
+            Model::Cgb => (0x1180, 0x0000, 0x0008, 0x007C),
+            Model::Agb if self.memory.is_cgb() => (0x1100, 0x0100, 0xFF56, 0x000D),
+            Model::Agb => (0x1100, 0x0100, 0x0008, 0x007C),
+        };
+        self.set_af(af);
+        self.set_bc(bc);
+        self.set_de(de);
+        self.set_hl(hl);
+        self.sp = 0xFFFE;
+        self.pc = 0x0100;
+        self.memory.apply_post_boot_state();
     }
 
     fn get_af(&self) -> u16 {
@@ -110,10 +252,12 @@ impl Cpu {
         (self.f & 0b10000000) != 0
     }
 
+    #[allow(dead_code)]
     fn get_substraction_flag(&self) -> bool {
         (self.f & 0b01000000) != 0
     }
-    
+
+    #[allow(dead_code)]
     fn get_half_carry_flag(&self) -> bool {
         (self.f & 0b00100000) != 0
     }
@@ -138,18 +282,43 @@ impl Cpu {
         self.f = (self.f & !0b00010000) | (state as u8) << 4;
     }
 
+    /**
+     * Every memory access takes a M-cycle, the hardware is brought up to the end of it before the access
+     * so registers like DIV and TIMA are seen at the right time within the instruction
+     */
+    fn tick_memory_cycle(&mut self)
+    {
+        self.memory.tick(4);
+        self.ticked_cycles += 4;
+    }
+
+    fn read_memory(&mut self, address: u16) -> Option<u8>
+    {
+        self.tick_memory_cycle();
+        self.memory.read(address)
+    }
+
+    fn write_memory(&mut self, address: u16, value: u8)
+    {
+        self.tick_memory_cycle();
+        self.memory.write(address, value);
+    }
+
     fn fetch(&mut self) -> Option<u8> {
-        let result = self
-            .memory
-            .read(self.pc);
-        self.pc = self.pc + 1;
+        let result = self.read_memory(self.pc);
+        if self.halt_bug {
+            //The byte after HALT is read twice since the pc fails to increment
+            self.halt_bug = false;
+        } else {
+            self.pc += 1;
+        }
         result
     }
 
     fn fetch_16(&mut self) -> Option<u16> {
         let first_byte = self.fetch()?;
         let second_byte = self.fetch()?;
-        Some(LittleEndian::read_u16(&[second_byte, first_byte]))
+        Some(LittleEndian::read_u16(&[first_byte, second_byte]))
     }
 
     fn fetch_operand_value(&mut self, op_type: code::Operand) -> i32
@@ -178,17 +347,17 @@ impl Cpu {
  
             code::Operand::A16 => {
                 let address = self.fetch_16();
-             self.memory.read(address.unwrap()).expect("Error accessing memory") as i32
+                self.read_memory(address.unwrap()).expect("Error accessing memory") as i32
             },
             code::Operand::A8 => {
                 let address = self.fetch().unwrap() as u16 + 0xFF00;
-                self.memory.read(address).expect("Error accessing memory") as i32
+                self.read_memory(address).expect("Error accessing memory") as i32
             },
 
-            code::Operand::iC => self.memory.read(0xFF00 + self.c as u16).expect("Error accessing to memory") as i32,
-            code::Operand::iBC => self.memory.read(self.get_bc()).expect("Error accessing memory") as i32,
-            code::Operand::iDE => self.memory.read(self.get_bc()).expect("Error accessing memory") as i32,
-            code::Operand::iHL => self.memory.read(self.get_hl()).expect("Error accessing memory") as i32,
+            code::Operand::iC => self.read_memory(0xFF00 + self.c as u16).expect("Error accessing to memory") as i32,
+            code::Operand::iBC => self.read_memory(self.get_bc()).expect("Error accessing memory") as i32,
+            code::Operand::iDE => self.read_memory(self.get_bc()).expect("Error accessing memory") as i32,
+            code::Operand::iHL => self.read_memory(self.get_hl()).expect("Error accessing memory") as i32,
             code::Operand::iHLPLUS => {
                 let result = self.fetch_operand_value(code::Operand::iHL);
                 self.set_hl(self.get_hl() + 1);
@@ -203,6 +372,25 @@ impl Cpu {
         }
     }
 
+    /**
+     * Writes an 8 bit value into a register or (HL), counterpart of fetch_operand_value for the CB prefixed instructions
+     */
+    fn write_operand_value(&mut self, op_type: code::Operand, value: u8)
+    {
+        match op_type
+        {
+            code::Operand::A => self.a = value,
+            code::Operand::B => self.b = value,
+            code::Operand::C => self.c = value,
+            code::Operand::D => self.d = value,
+            code::Operand::E => self.e = value,
+            code::Operand::H => self.h = value,
+            code::Operand::L => self.l = value,
+            code::Operand::iHL => self.write_memory(self.get_hl(), value),
+            _ => panic!("WRITE NOT SUPPORTED FOR {:?}", op_type),
+        }
+    }
+
     fn handle_add_op(&mut self, op1_type: code::Operand, op2_type: code::Operand)
     {
         assert_eq!(op1_type.get_operand_size(), op2_type.get_operand_size(), "Wrong operand size");
@@ -223,14 +411,14 @@ impl Cpu {
                 let hl_value = self.get_hl();
                 let other_value = self.fetch_operand_value(op2_type);
                 self.set_half_carry_flag(((hl_value & 0xFFF) + (other_value as u16 & 0xFFF)) & 0x1000 == 0x1000);
-                let unceiled_value:i32 = hl_value as i32 + other_value as i32;
+                let unceiled_value:i32 = hl_value as i32 + other_value;
                 self.set_hl(unceiled_value as u16);
                 self.set_carry_flag(unceiled_value > 0xFFFF);
             },
             code::Operand::SP => 
             {
                 let other_value = self.fetch_operand_value(op2_type);
-                let unceiled_value = (self.sp as i32 + other_value);
+                let unceiled_value = self.sp as i32 + other_value;
                 self.set_half_carry_flag(((self.sp & 0xFFF) + (other_value as u16 & 0xFFF)) & 0x1000 == 0x1000);
                 self.sp = unceiled_value as u16;
                 self.set_zero_flag(false);
@@ -288,8 +476,9 @@ impl Cpu {
                 self.set_zero_flag(self.h == 0);
             }
             code::Operand::iHL => {
-                self.memory.write(self.get_hl(), self.memory.read(self.get_hl()).expect("Wrong memory access!").wrapping_sub(1));
-                self.set_zero_flag(self.memory.read(self.get_hl()).expect("Wrong memory access!") == 0);
+                let result = (value as u8).wrapping_sub(1);
+                self.write_memory(self.get_hl(), result);
+                self.set_zero_flag(result == 0);
             }
             code::Operand::BC => {
                 self.set_bc(self.get_bc().wrapping_sub(1));
@@ -345,9 +534,11 @@ impl Cpu {
                 self.set_zero_flag(self.h == 0);
             }
             code::Operand::iHL => {
-                self.set_half_carry_flag((self.memory.read(self.get_hl()).expect("Wrong access memory!") & 0x0F + 1) & 0x10 == 0x10);
-                self.memory.write(self.get_hl(), self.memory.read(self.get_hl()).expect("wrong access memory!").wrapping_add(1));
-                self.set_zero_flag(self.memory.read(self.get_hl()).expect("Wrong memory access!") == 0);
+                let value = self.read_memory(self.get_hl()).expect("Wrong access memory!");
+                self.set_half_carry_flag(((value & 0x0F) + 1) & 0x10 == 0x10);
+                let result = value.wrapping_add(1);
+                self.write_memory(self.get_hl(), result);
+                self.set_zero_flag(result == 0);
             }
             code::Operand::C => {
                 self.set_half_carry_flag(((self.c & 0x0F) + 1) & 0x10 == 0x10);
@@ -421,7 +612,7 @@ impl Cpu {
         if let code::Operand::A = op1_type
         {
             let other_value = self.fetch_operand_value(op2_type);
-            self.a = self.a & other_value as u8;
+            self.a &= other_value as u8;
             self.set_zero_flag(self.a == 0);
         }
         else {
@@ -470,22 +661,33 @@ impl Cpu {
                 self.l = other_value as u8;
             }
             code::Operand::iDE => {
-                self.memory.write(self.get_de(), other_value as u8);
+                self.write_memory(self.get_de(), other_value as u8);
             }
             code::Operand::iHL => {
-                self.memory.write(self.get_hl(), other_value as u8);
+                self.write_memory(self.get_hl(), other_value as u8);
             }
             code::Operand::iBC => {
-                self.memory.write(self.get_bc(), other_value as u8);
+                self.write_memory(self.get_bc(), other_value as u8);
             }
             code::Operand::iHLMINUS => {
-                self.memory.write(self.get_hl(), other_value as u8);
+                self.write_memory(self.get_hl(), other_value as u8);
                 self.set_hl(self.get_hl() - 1);
             }
             code::Operand::iHLPLUS => {
-                self.memory.write(self.get_hl(), other_value as u8);
+                self.write_memory(self.get_hl(), other_value as u8);
                 self.set_hl(self.get_hl() + 1);
             }
+            code::Operand::iC => {
+                self.write_memory(0xFF00 + self.c as u16, other_value as u8);
+            }
+            code::Operand::A8 => {
+                let address = self.fetch().unwrap() as u16 + 0xFF00;
+                self.write_memory(address, other_value as u8);
+            }
+            code::Operand::A16 => {
+                let address = self.fetch_16().unwrap();
+                self.write_memory(address, other_value as u8);
+            }
             _ => {
                 panic!("LD NOT SUPPORTED FOR {:?} and {:?}", op1_type, op2_type);
             }
@@ -553,7 +755,7 @@ impl Cpu {
         {
             let other_operand = self.fetch_operand_value(op2_type);
 
-            self.a = self.a ^ other_operand as u8;
+            self.a ^= other_operand as u8;
 
             self.set_zero_flag(self.a == 0);
             self.set_substraction_flag(false);
@@ -572,7 +774,7 @@ impl Cpu {
         if let code::Operand::A = op1_type
         {
             let other_operand = self.fetch_operand_value(op2_type);
-            self.a = self.a | other_operand as u8;
+            self.a |= other_operand as u8;
 
             self.set_zero_flag(self.a == 0);
             self.set_substraction_flag(false);
@@ -584,13 +786,234 @@ impl Cpu {
         }
     }
 
+    fn set_shift_flags(&mut self, result: u8, carry: bool)
+    {
+        self.set_zero_flag(result == 0);
+        self.set_substraction_flag(false);
+        self.set_half_carry_flag(false);
+        self.set_carry_flag(carry);
+    }
+
+    fn handle_rlc_op(&mut self, op1_type: code::Operand)
+    {
+        let value = self.fetch_operand_value(op1_type.clone()) as u8;
+        let result = value.rotate_left(1);
+        self.write_operand_value(op1_type, result);
+        self.set_shift_flags(result, value & 0x80 != 0);
+    }
+
+    fn handle_rrc_op(&mut self, op1_type: code::Operand)
+    {
+        let value = self.fetch_operand_value(op1_type.clone()) as u8;
+        let result = value.rotate_right(1);
+        self.write_operand_value(op1_type, result);
+        self.set_shift_flags(result, value & 0x01 != 0);
+    }
+
+    fn handle_rl_op(&mut self, op1_type: code::Operand)
+    {
+        let value = self.fetch_operand_value(op1_type.clone()) as u8;
+        let result = (value << 1) | self.get_carry_flag() as u8;
+        self.write_operand_value(op1_type, result);
+        self.set_shift_flags(result, value & 0x80 != 0);
+    }
+
+    fn handle_rr_op(&mut self, op1_type: code::Operand)
+    {
+        let value = self.fetch_operand_value(op1_type.clone()) as u8;
+        let result = (value >> 1) | (self.get_carry_flag() as u8) << 7;
+        self.write_operand_value(op1_type, result);
+        self.set_shift_flags(result, value & 0x01 != 0);
+    }
+
+    fn handle_sla_op(&mut self, op1_type: code::Operand)
+    {
+        let value = self.fetch_operand_value(op1_type.clone()) as u8;
+        let result = value << 1;
+        self.write_operand_value(op1_type, result);
+        self.set_shift_flags(result, value & 0x80 != 0);
+    }
+
+    fn handle_sra_op(&mut self, op1_type: code::Operand)
+    {
+        let value = self.fetch_operand_value(op1_type.clone()) as u8;
+        let result = (value >> 1) | (value & 0x80);
+        self.write_operand_value(op1_type, result);
+        self.set_shift_flags(result, value & 0x01 != 0);
+    }
+
+    fn handle_swap_op(&mut self, op1_type: code::Operand)
+    {
+        let value = self.fetch_operand_value(op1_type.clone()) as u8;
+        let result = value.rotate_left(4);
+        self.write_operand_value(op1_type, result);
+        self.set_shift_flags(result, false);
+    }
+
+    fn handle_srl_op(&mut self, op1_type: code::Operand)
+    {
+        let value = self.fetch_operand_value(op1_type.clone()) as u8;
+        let result = value >> 1;
+        self.write_operand_value(op1_type, result);
+        self.set_shift_flags(result, value & 0x01 != 0);
+    }
+
+    fn handle_bit_op(&mut self, bit: u8, op1_type: code::Operand)
+    {
+        let value = self.fetch_operand_value(op1_type) as u8;
+        self.set_zero_flag(value & (1 << bit) == 0);
+        self.set_substraction_flag(false);
+        self.set_half_carry_flag(true);
+    }
+
+    fn handle_res_op(&mut self, bit: u8, op1_type: code::Operand)
+    {
+        let value = self.fetch_operand_value(op1_type.clone()) as u8;
+        self.write_operand_value(op1_type, value & !(1 << bit));
+    }
+
+    fn handle_set_op(&mut self, bit: u8, op1_type: code::Operand)
+    {
+        let value = self.fetch_operand_value(op1_type.clone()) as u8;
+        self.write_operand_value(op1_type, value | (1 << bit));
+    }
+
+    /**
+     * Decodes and executes the instruction following a 0xCB prefix, returns the cycles of the whole instruction
+     */
+    fn handle_prefix_op(&mut self) -> u64
+    {
+        let c = self.fetch().expect("Missing opcode after prefix");
+        let (instruction, cycles) = code::get_prefixed_instruction_specs_from_code(c).unwrap_or_else(|| panic!("Non Valid Prefixed Opcode: {}", c));
+
+        match instruction
+        {
+            code::Instruction::RLC(op1_type) => self.handle_rlc_op(op1_type),
+            code::Instruction::RRC(op1_type) => self.handle_rrc_op(op1_type),
+            code::Instruction::RL(op1_type) => self.handle_rl_op(op1_type),
+            code::Instruction::RR(op1_type) => self.handle_rr_op(op1_type),
+            code::Instruction::SLA(op1_type) => self.handle_sla_op(op1_type),
+            code::Instruction::SRA(op1_type) => self.handle_sra_op(op1_type),
+            code::Instruction::SWAP(op1_type) => self.handle_swap_op(op1_type),
+            code::Instruction::SRL(op1_type) => self.handle_srl_op(op1_type),
+            code::Instruction::BIT(bit, op1_type) => self.handle_bit_op(bit, op1_type),
+            code::Instruction::RES(bit, op1_type) => self.handle_res_op(bit, op1_type),
+            code::Instruction::SET(bit, op1_type) => self.handle_set_op(bit, op1_type),
+            _ => panic!("Not supported prefixed instruction type Opcode: {}", c),
+        }
+
+        cycles
+    }
+
+    /**
+     * HALT sleeps until an enabled interrupt is pending. With IME disabled and an interrupt already pending
+     * the cpu doesn't halt, triggering the HALT bug instead. An EI right before counts as IME enabled: the
+     * interrupt is serviced with HALT as return address, so HALT runs again after RETI
+     */
+    fn handle_halt_op(&mut self)
+    {
+        let pending = self.memory.interrupts.get_pending().is_some();
+
+        if self.ime_scheduled && pending
+        {
+            self.pc = self.pc.wrapping_sub(1);
+        }
+        else if !self.ime && pending
+        {
+            self.halt_bug = true;
+        }
+        else
+        {
+            self.halted = true;
+        }
+    }
+
+    /**
+     * STOP performs the speed switch when it has been armed through KEY1 on CGB,
+     * otherwise it enters the stop state until a joypad input arrives
+     */
+    fn handle_stop_op(&mut self, op1_type: code::Operand)
+    {
+        //STOP is encoded with a padding byte that is skipped
+        self.fetch_operand_value(op1_type);
+        self.memory.reset_div();
+
+        if self.memory.is_speed_switch_armed()
+        {
+            self.memory.switch_speed();
+        }
+        else
+        {
+            self.stopped = true;
+        }
+    }
+
+    /**
+     * Keeps the cpu idle while halted or stopped, returns the cycles spent idling or None once it wakes up
+     */
+    fn handle_low_power(&mut self) -> Option<u64>
+    {
+        if self.stopped
+        {
+            if !self.memory.interrupts.is_requested(Interrupt::Joypad)
+            {
+                return Some(4);
+            }
+            self.stopped = false;
+        }
+
+        if self.halted
+        {
+            if self.memory.interrupts.get_pending().is_none()
+            {
+                return Some(4);
+            }
+            self.halted = false;
+        }
+
+        None
+    }
+
+    /**
+     * Dispatches the highest priority interrupt that is both enabled and requested, if IME allows it.
+     * Returns the cycles spent on the dispatch
+     */
+    fn handle_interrupts(&mut self) -> Option<u64>
+    {
+        if !self.ime
+        {
+            return None;
+        }
+
+        let interrupt = self.memory.interrupts.get_pending()?;
+        self.memory.interrupts.clear(interrupt);
+        self.ime = false;
+        self.halt_bug = false;
+        self.handle_push_op(code::Operand::PC);
+        self.pc = interrupt.get_vector();
+        Some(20)
+    }
+
+    fn push_16(&mut self, value: u16)
+    {
+        self.sp = self.sp.wrapping_sub(1);
+        self.write_memory(self.sp, ((value & 0xFF00) >> 8) as u8);
+        self.sp = self.sp.wrapping_sub(1);
+        self.write_memory(self.sp, (value & 0x00FF) as u8);
+    }
+
+    fn pop_16(&mut self) -> u16
+    {
+        let lsb = self.read_memory(self.sp).expect("Error reading during pop operation");
+        self.sp = self.sp.wrapping_add(1);
+        let msb = self.read_memory(self.sp).expect("Error reading during pop operation");
+        self.sp = self.sp.wrapping_add(1);
+        LittleEndian::read_u16(&[lsb, msb])
+    }
+
     fn handle_pop_op(&mut self, op1_type: code::Operand)
     {
-        let lsb = self.memory.read(self.sp).expect("Error reading during pop operation");
-        self.sp += 1;
-        let msb = self.memory.read(self.sp).expect("Error reading during pop operation");
-        self.sp += 1;
-        let value = (msb as u16) << 8 + lsb;
+        let value = self.pop_16();
         match op1_type
         {
             code::Operand::AF =>
@@ -636,107 +1059,283 @@ impl Cpu {
             {
                 self.get_hl()
             }
+            code::Operand::PC =>
+            {
+                self.pc
+            }
             _=> {
                 panic!("PUSH NOT SUPPORTED FOR {:?}", op1_type);
             }
         };
 
-        self.sp -= 1;
-        self.memory.write(self.sp, ((value & 0xFF00) >> 8) as u8);
-        self.sp -= 1;
-        self.memory.write(self.sp, (value & 0x00FF) as u8);
+        self.push_16(value);
+    }
+
+    /**
+     * Jumps are always decoded fully, even when the condition is not met, so the pc skips the operand.
+     * Returns whether the jump was taken
+     */
+    fn handle_jump_op(&mut self, jump_type: code::JumpType, op1_type: code::Operand, condition: bool) -> bool
+    {
+        match jump_type
+        {
+            code::JumpType::Absolute =>
+            {
+                self.handle_jump_absolute_op(op1_type, condition)
+            }
+            code::JumpType::Relative =>
+            {
+                self.handle_jump_relative_op(op1_type, condition)
+            }
+        }
+    }
 
+    fn handle_jump_relative_op(&mut self, op1_type: code::Operand, condition: bool) -> bool
+    {
+        let offset = self.fetch_operand_value(op1_type);
+        if condition
+        {
+            self.pc = (self.pc as i32 + offset) as u16;
+        }
+        condition
     }
 
-    fn handle_jump_relative_op(&mut self, op1_type: code::Operand)
+    fn handle_jump_absolute_op(&mut self, op1_type: code::Operand, condition: bool) -> bool
     {
-        
+        let address = self.fetch_operand_value(op1_type);
+        if condition
+        {
+            self.pc = address as u16;
+        }
+        condition
+    }
+
+    fn handle_call_op(&mut self, op1_type: code::Operand, condition: bool) -> bool
+    {
+        let address = self.fetch_operand_value(op1_type);
+        if condition
+        {
+            self.handle_push_op(code::Operand::PC);
+            self.pc = address as u16;
+        }
+        condition
     }
 
-    fn handle_jump_absolute_op(&mut self, op1_type: code::Operand)
+    fn handle_ret_op(&mut self, condition: bool) -> bool
     {
+        if condition
+        {
+            self.pc = self.pop_16();
+        }
+        condition
+    }
 
+    fn handle_rst_op(&mut self, vector: u8)
+    {
+        self.handle_push_op(code::Operand::PC);
+        self.pc = vector as u16;
     }
 
     pub fn run(&mut self) {
-        while let Some(c) = self.fetch() {
-            let (instruction, cycles) = code::get_instruction_specs_from_code(c).expect(format!("Non Valid Opcode: {}", c).as_str());
-
-            match instruction
-            {
-                code::Instruction::ADD(op1_type, op2_type) => 
-                {
-                    self.handle_add_op(op1_type, op2_type);
-                }
-                code::Instruction::SUB(op1_type, op2_type) =>
-                {
-                    self.handle_sub_op(op1_type, op2_type);
-                }
-                code::Instruction::DEC(op1_type) => {
-                    self.handle_dec_op(op1_type);
-                }
-                code::Instruction::INC(op1_type) => {
-                    self.handle_inc_op(op1_type);
-                }
-                code::Instruction::AND(op1_type, op2_type) => {
-                    self.handle_and_op(op1_type, op2_type);
-                }
-                code::Instruction::NOP => {
-
-                }
-                code::Instruction::LD(op1_type, op2_type) => 
-                {
-                    self.handle_ld_op(op1_type, op2_type);
-                }
-                code::Instruction::CP(op1_type, op2_type) => 
-                {
-                    self.handle_cp_op(op1_type, op2_type);
-                }
-                code::Instruction::ADC(op1_type, op2_type) => 
-                {
-                    self.handle_adc_op(op1_type, op2_type);
-                }
-                code::Instruction::SBC(op1_type, op2_type) => 
-                {
-                    self.handle_sbc_op(op1_type, op2_type);
-                }
-                code::Instruction::XOR(op1_type, op2_type) => 
-                {
-                    self.handle_xor_op(op1_type, op2_type);
-                }
-                code::Instruction::OR(op1_type, op2_type) => 
-                {
-                    self.handle_or_op(op1_type, op2_type);
-                }
-                code::Instruction::POP(op1_type) => 
-                {
-                    self.handle_pop_op(op1_type);
-                }
-                code::Instruction::PUSH(op1_type) => 
-                {
-                    self.handle_push_op(op1_type);
-                }
-                code::Instruction::JP(jump_type,op1_type) => 
-                {
-                    match jump_type
-                    {
-                        code::JumpType::Absolute =>
-                        {
-                            self.handle_jump_absolute_op(op1_type);
-                        }
-                        code::JumpType::Relative =>
-                        {
-                            self.handle_jump_relative_op(op1_type);
-                        }
-                    }
-                }
-                _ => 
-                {
-                    panic!("Not supported instruction type Opcode: {}", c);
-                }
-            }
-
-            self.cycles += cycles; 
+        while self.step().is_some() {}
+    }
+
+    /**
+     * Runs for as long as the given amount of frames take, whether the LCD is on or not. Returns false
+     * when the pc runs out of readable memory before that
+     */
+    pub fn run_frames(&mut self, frames: u64) -> bool
+    {
+        let mut remaining = frames * CYCLES_PER_FRAME;
+        while remaining > 0
+        {
+            let double_speed = self.memory.is_double_speed();
+            let cycles = match self.step()
+            {
+                Some(cycles) => cycles,
+                None => return false,
+            };
+            remaining = remaining.saturating_sub(if double_speed { cycles / 2 } else { cycles });
         }
+        true
+    }
+
+    /**
+     * Services a pending interrupt or executes a single instruction, then advances the rest of the hardware
+     * through the cycles its memory accesses didn't cover. Returns the cycles spent, or None once the pc runs
+     * out of readable memory
+     */
+    pub fn step(&mut self) -> Option<u64> {
+        self.ticked_cycles = 0;
+        let mut cycles = self.execute()?.max(self.ticked_cycles);
+        self.memory.tick(cycles - self.ticked_cycles);
+
+        //VRAM DMA and speed switches halt the cpu while the rest of the hardware keeps running
+        loop {
+            let stall = self.memory.take_stall_cycles();
+            if stall == 0 {
+                break;
+            }
+            self.memory.tick(stall);
+            cycles += stall;
+        }
+
+        self.cycles += cycles;
+        Some(cycles)
+    }
+
+    fn execute(&mut self) -> Option<u64> {
+        if let Some(cycles) = self.handle_low_power() {
+            return Some(cycles);
+        }
+
+        if let Some(cycles) = self.handle_interrupts() {
+            return Some(cycles);
+        }
+
+        let c = self.fetch()?;
+        let ime_was_scheduled = self.ime_scheduled;
+        let (instruction, mut cycles) = code::get_instruction_specs_from_code(c).unwrap_or_else(|| panic!("Non Valid Opcode: {}", c));
+        let mut branch_taken = false;
+
+        match instruction
+        {
+            code::Instruction::ADD(op1_type, op2_type) => 
+            {
+                self.handle_add_op(op1_type, op2_type);
+            }
+            code::Instruction::SUB(op1_type, op2_type) =>
+            {
+                self.handle_sub_op(op1_type, op2_type);
+            }
+            code::Instruction::DEC(op1_type) => {
+                self.handle_dec_op(op1_type);
+            }
+            code::Instruction::INC(op1_type) => {
+                self.handle_inc_op(op1_type);
+            }
+            code::Instruction::AND(op1_type, op2_type) => {
+                self.handle_and_op(op1_type, op2_type);
+            }
+            code::Instruction::NOP => {
+
+            }
+            code::Instruction::LD(op1_type, op2_type) => 
+            {
+                self.handle_ld_op(op1_type, op2_type);
+            }
+            code::Instruction::CP(op1_type, op2_type) => 
+            {
+                self.handle_cp_op(op1_type, op2_type);
+            }
+            code::Instruction::ADC(op1_type, op2_type) => 
+            {
+                self.handle_adc_op(op1_type, op2_type);
+            }
+            code::Instruction::SBC(op1_type, op2_type) => 
+            {
+                self.handle_sbc_op(op1_type, op2_type);
+            }
+            code::Instruction::XOR(op1_type, op2_type) => 
+            {
+                self.handle_xor_op(op1_type, op2_type);
+            }
+            code::Instruction::OR(op1_type, op2_type) => 
+            {
+                self.handle_or_op(op1_type, op2_type);
+            }
+            code::Instruction::POP(op1_type) => 
+            {
+                self.handle_pop_op(op1_type);
+            }
+            code::Instruction::PUSH(op1_type) => 
+            {
+                self.handle_push_op(op1_type);
+            }
+            code::Instruction::JP(jump_type,op1_type) => 
+            {
+                self.handle_jump_op(jump_type, op1_type, true);
+            }
+            code::Instruction::JP_ZERO(jump_type, flag, op1_type) =>
+            {
+                branch_taken = self.handle_jump_op(jump_type, op1_type, self.get_zero_flag() == flag);
+            }
+            code::Instruction::JP_CARRY(jump_type, flag, op1_type) =>
+            {
+                branch_taken = self.handle_jump_op(jump_type, op1_type, self.get_carry_flag() == flag);
+            }
+            code::Instruction::CALL(op1_type) =>
+            {
+                self.handle_call_op(op1_type, true);
+            }
+            code::Instruction::CALL_ZERO(flag, op1_type) =>
+            {
+                branch_taken = self.handle_call_op(op1_type, self.get_zero_flag() == flag);
+            }
+            code::Instruction::CALL_CARRY(flag, op1_type) =>
+            {
+                branch_taken = self.handle_call_op(op1_type, self.get_carry_flag() == flag);
+            }
+            code::Instruction::RET =>
+            {
+                self.handle_ret_op(true);
+            }
+            code::Instruction::RETI =>
+            {
+                self.handle_ret_op(true);
+                self.ime = true;
+            }
+            code::Instruction::DI =>
+            {
+                self.ime = false;
+                self.ime_scheduled = false;
+            }
+            code::Instruction::EI =>
+            {
+                self.ime_scheduled = true;
+            }
+            code::Instruction::HALT =>
+            {
+                self.handle_halt_op();
+            }
+            code::Instruction::STOP(op1_type) =>
+            {
+                self.handle_stop_op(op1_type);
+            }
+            code::Instruction::RET_ZERO(flag) =>
+            {
+                branch_taken = self.handle_ret_op(self.get_zero_flag() == flag);
+            }
+            code::Instruction::RET_CARRY(flag) =>
+            {
+                branch_taken = self.handle_ret_op(self.get_carry_flag() == flag);
+            }
+            code::Instruction::RST(vector) =>
+            {
+                self.handle_rst_op(vector);
+            }
+            code::Instruction::PREFIX =>
+            {
+                cycles = self.handle_prefix_op();
+            }
+            _ => 
+            {
+                panic!("Not supported instruction type Opcode: {}", c);
+            }
+        }
+
+        if branch_taken
+        {
+            cycles = code::get_taken_branch_cycles_from_code(c).unwrap_or(cycles);
+        }
+
+        //EI only takes effect after the instruction that follows it
+        if ime_was_scheduled && self.ime_scheduled
+        {
+            self.ime = true;
+            self.ime_scheduled = false;
+        }
+
+        Some(cycles)
     }
 }
@@ -1,6 +1,5 @@
-use crate::cartridge;
-
-#[cfg(test)]
+#![cfg(test)]
+#![allow(clippy::bool_assert_comparison)]
 
 #[test]
 fn test_adding_op_hl()
@@ -107,6 +106,7 @@ fn test_and_op_d()
 }
 
 #[test]
+#[allow(non_snake_case)]
 fn test_ld_a_iHL()
 {
     let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::LD_A_iHL as u8, 20]);
@@ -189,4 +189,794 @@ fn test_or_a_b()
     assert_eq!(cpu.get_substraction_flag(), false);
     assert_eq!(cpu.get_half_carry_flag(), false);
     assert_eq!(cpu.get_carry_flag(), false)
-}
\ No newline at end of file
+}
+#[test]
+fn test_jp_a16()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::JP_A16 as u8, 0x04, 0x00, crate::code::Opcode::INC_C as u8, crate::code::Opcode::INC_B as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.run();
+
+    assert_eq!(cpu.b, 1);
+    assert_eq!(cpu.c, 0);
+    assert_eq!(cpu.cycles, 20);
+}
+
+#[test]
+fn test_jr_nz_e8_not_taken()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::JR_NZ_E8 as u8, 0x01, crate::code::Opcode::INC_B as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.set_zero_flag(true);
+
+    cpu.run();
+
+    assert_eq!(cpu.b, 1);
+    assert_eq!(cpu.cycles, 12);
+}
+
+#[test]
+fn test_jr_nz_e8_taken()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::JR_NZ_E8 as u8, 0x01, crate::code::Opcode::INC_B as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.set_zero_flag(false);
+
+    cpu.run();
+
+    assert_eq!(cpu.b, 0);
+    assert_eq!(cpu.cycles, 12);
+}
+
+#[test]
+fn test_jr_e8_backwards()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::DEC_B as u8, crate::code::Opcode::JR_NZ_E8 as u8, 0xFD]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.b = 3;
+
+    cpu.run();
+
+    assert_eq!(cpu.b, 0);
+    assert_eq!(cpu.get_zero_flag(), true);
+}
+
+#[test]
+fn test_call_and_ret()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![
+        crate::code::Opcode::CALL_A16 as u8, 0x07, 0x00,
+        crate::code::Opcode::INC_C as u8,
+        crate::code::Opcode::JP_A16 as u8, 0x09, 0x00,
+        crate::code::Opcode::INC_B as u8,
+        crate::code::Opcode::RET as u8,
+    ]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.sp = 0xFFFE;
+
+    cpu.run();
+
+    assert_eq!(cpu.b, 1);
+    assert_eq!(cpu.c, 1);
+    assert_eq!(cpu.sp, 0xFFFE);
+    assert_eq!(cpu.cycles, 64);
+}
+
+#[test]
+fn test_call_c_not_taken()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::CALL_C_A16 as u8, 0x00, 0x00]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.sp = 0xFFFE;
+    cpu.set_carry_flag(false);
+
+    cpu.run();
+
+    assert_eq!(cpu.sp, 0xFFFE);
+    assert_eq!(cpu.cycles, 12);
+}
+
+#[test]
+fn test_ret_z_taken()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::RET_Z as u8, crate::code::Opcode::INC_B as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.sp = 0xFFFC;
+    cpu.memory.write(0xFFFC, 0x02);
+    cpu.memory.write(0xFFFD, 0x00);
+    cpu.set_zero_flag(true);
+
+    cpu.run();
+
+    assert_eq!(cpu.b, 0);
+    assert_eq!(cpu.sp, 0xFFFE);
+    assert_eq!(cpu.cycles, 20);
+}
+
+#[test]
+fn test_rst_08()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::RST_08 as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.sp = 0xFFFE;
+
+    cpu.run();
+
+    assert_eq!(cpu.sp, 0xFFFC);
+    assert_eq!(cpu.memory.read(0xFFFC), Some(0x01));
+    assert_eq!(cpu.memory.read(0xFFFD), Some(0x00));
+    assert_eq!(cpu.pc, 0x09);
+}
+
+#[test]
+fn test_rlc_b()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::PREFIX as u8, crate::code::PrefixedOpcode::RLC_B as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.b = 0x85;
+
+    cpu.run();
+
+    assert_eq!(cpu.b, 0x0B);
+    assert_eq!(cpu.get_zero_flag(), false);
+    assert_eq!(cpu.get_carry_flag(), true);
+    assert_eq!(cpu.cycles, 8);
+}
+
+#[test]
+fn test_rr_a_with_carry()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::PREFIX as u8, crate::code::PrefixedOpcode::RR_A as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.a = 0x01;
+    cpu.set_carry_flag(true);
+
+    cpu.run();
+
+    assert_eq!(cpu.a, 0x80);
+    assert_eq!(cpu.get_zero_flag(), false);
+    assert_eq!(cpu.get_carry_flag(), true);
+}
+
+#[test]
+fn test_sra_d()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::PREFIX as u8, crate::code::PrefixedOpcode::SRA_D as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.d = 0x8A;
+
+    cpu.run();
+
+    assert_eq!(cpu.d, 0xC5);
+    assert_eq!(cpu.get_carry_flag(), false);
+}
+
+#[test]
+fn test_swap_ihl()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::PREFIX as u8, crate::code::PrefixedOpcode::SWAP_iHL as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.set_hl(0xC000);
+    cpu.memory.write(0xC000, 0xF0);
+
+    cpu.run();
+
+    assert_eq!(cpu.memory.read(0xC000), Some(0x0F));
+    assert_eq!(cpu.get_zero_flag(), false);
+    assert_eq!(cpu.get_carry_flag(), false);
+    assert_eq!(cpu.cycles, 16);
+}
+
+#[test]
+fn test_bit_7_h()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::PREFIX as u8, crate::code::PrefixedOpcode::BIT_7_H as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.h = 0x7F;
+    cpu.set_carry_flag(true);
+
+    cpu.run();
+
+    assert_eq!(cpu.h, 0x7F);
+    assert_eq!(cpu.get_zero_flag(), true);
+    assert_eq!(cpu.get_substraction_flag(), false);
+    assert_eq!(cpu.get_half_carry_flag(), true);
+    assert_eq!(cpu.get_carry_flag(), true);
+}
+
+#[test]
+fn test_res_and_set_ihl()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![
+        crate::code::Opcode::PREFIX as u8, crate::code::PrefixedOpcode::RES_0_iHL as u8,
+        crate::code::Opcode::PREFIX as u8, crate::code::PrefixedOpcode::SET_7_iHL as u8,
+    ]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.set_hl(0xC010);
+    cpu.memory.write(0xC010, 0x01);
+
+    cpu.run();
+
+    assert_eq!(cpu.memory.read(0xC010), Some(0x80));
+    assert_eq!(cpu.cycles, 32);
+}
+
+#[test]
+fn test_interrupt_dispatch()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::NOP as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.sp = 0xFFFE;
+    cpu.ime = true;
+    cpu.memory.write(0xFFFF, 0x1F);
+    cpu.memory.request_interrupt(crate::interrupts::Interrupt::Timer);
+    cpu.memory.request_interrupt(crate::interrupts::Interrupt::Stat);
+
+    assert_eq!(cpu.step(), Some(20));
+
+    assert_eq!(cpu.pc, 0x48);
+    assert_eq!(cpu.ime, false);
+    assert_eq!(cpu.sp, 0xFFFC);
+    assert_eq!(cpu.memory.read(0xFF0F), Some(0xE4));
+}
+
+#[test]
+fn test_interrupt_not_dispatched_when_disabled()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::NOP as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.ime = true;
+    cpu.memory.write(0xFFFF, 0x00);
+    cpu.memory.request_interrupt(crate::interrupts::Interrupt::VBlank);
+
+    assert_eq!(cpu.step(), Some(4));
+    assert_eq!(cpu.pc, 1);
+}
+
+#[test]
+fn test_ei_delay()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::EI as u8, crate::code::Opcode::INC_B as u8, crate::code::Opcode::INC_B as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.sp = 0xFFFE;
+    cpu.memory.write(0xFFFF, 0x01);
+    cpu.memory.request_interrupt(crate::interrupts::Interrupt::VBlank);
+
+    cpu.step();
+    assert_eq!(cpu.ime, false);
+
+    cpu.step();
+    assert_eq!(cpu.ime, true);
+    assert_eq!(cpu.b, 1);
+
+    cpu.step();
+    assert_eq!(cpu.b, 1);
+    assert_eq!(cpu.pc, 0x40);
+}
+
+#[test]
+fn test_reti_enables_interrupts()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::RETI as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.sp = 0xFFFC;
+    cpu.memory.write(0xFFFC, 0x34);
+    cpu.memory.write(0xFFFD, 0x12);
+
+    cpu.step();
+
+    assert_eq!(cpu.pc, 0x1234);
+    assert_eq!(cpu.ime, true);
+}
+
+#[test]
+fn test_halt_waits_for_interrupt()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::HALT as u8, crate::code::Opcode::INC_B as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.memory.write(0xFFFF, 0x04);
+
+    cpu.step();
+    assert_eq!(cpu.halted, true);
+
+    assert_eq!(cpu.step(), Some(4));
+    assert_eq!(cpu.pc, 1);
+    assert_eq!(cpu.b, 0);
+
+    cpu.memory.request_interrupt(crate::interrupts::Interrupt::Timer);
+    cpu.step();
+
+    assert_eq!(cpu.halted, false);
+    assert_eq!(cpu.b, 1);
+    assert_eq!(cpu.pc, 2);
+}
+
+#[test]
+fn test_halt_bug()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::HALT as u8, crate::code::Opcode::INC_B as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.memory.write(0xFFFF, 0x01);
+    cpu.memory.request_interrupt(crate::interrupts::Interrupt::VBlank);
+
+    cpu.run();
+
+    assert_eq!(cpu.halted, false);
+    assert_eq!(cpu.b, 2);
+}
+
+#[test]
+fn test_ei_halt_with_pending_interrupt()
+{
+    let mut content = vec![0x00; 0x100];
+    content[0x00] = crate::code::Opcode::EI as u8;
+    content[0x01] = crate::code::Opcode::HALT as u8;
+    content[0x02] = crate::code::Opcode::INC_B as u8;
+    content[0x40] = crate::code::Opcode::INC_C as u8;
+    content[0x41] = crate::code::Opcode::RETI as u8;
+    let cart = crate::Cartidge::new_from_bytes(content);
+    let mut cpu = super::Cpu::new_test(cart);
+    cpu.sp = 0xFFFE;
+
+    cpu.memory.write(0xFFFF, 0x01);
+    cpu.memory.request_interrupt(crate::interrupts::Interrupt::VBlank);
+
+    //EI, HALT, dispatch, INC C and RETI
+    for _ in 0..5
+    {
+        cpu.step();
+    }
+    assert_eq!(cpu.c, 1);
+    assert_eq!(cpu.pc, 0x01);
+
+    //HALT runs again and sleeps since nothing is pending anymore
+    cpu.step();
+    assert_eq!(cpu.halted, true);
+    assert_eq!(cpu.halt_bug, false);
+    assert_eq!(cpu.b, 0);
+}
+
+#[test]
+fn test_stop_switches_speed()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::STOP_N8 as u8, 0x00, crate::code::Opcode::INC_B as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.memory.write(0xFF4D, 0x01);
+    assert_eq!(cpu.memory.read(0xFF4D), Some(0x7F));
+
+    cpu.run();
+
+    assert_eq!(cpu.stopped, false);
+    assert_eq!(cpu.b, 1);
+    assert_eq!(cpu.memory.is_double_speed(), true);
+    assert_eq!(cpu.memory.read(0xFF4D), Some(0xFE));
+}
+
+#[test]
+fn test_stop_waits_for_joypad()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::STOP_N8 as u8, 0x00, crate::code::Opcode::INC_B as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.step();
+    assert_eq!(cpu.stopped, true);
+
+    cpu.step();
+    assert_eq!(cpu.b, 0);
+
+    cpu.memory.request_interrupt(crate::interrupts::Interrupt::Joypad);
+    cpu.step();
+
+    assert_eq!(cpu.stopped, false);
+    assert_eq!(cpu.b, 1);
+}
+
+#[test]
+fn test_button_press_wakes_stop()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::STOP_N8 as u8, 0x00, crate::code::Opcode::INC_B as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+    cpu.memory.write(0xFF00, 0x20);
+
+    cpu.step();
+    assert_eq!(cpu.stopped, true);
+
+    cpu.press(crate::joypad::Button::Down);
+    assert_eq!(cpu.memory.read(0xFF00), Some(0xE7));
+    cpu.step();
+
+    assert_eq!(cpu.stopped, false);
+    assert_eq!(cpu.b, 1);
+}
+
+#[test]
+fn test_echo_ram_mirrors_wram()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![0x00]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.memory.write(0xE123, 0x42);
+    assert_eq!(cpu.memory.read(0xC123), Some(0x42));
+
+    cpu.memory.write(0xC456, 0x24);
+    assert_eq!(cpu.memory.read(0xE456), Some(0x24));
+}
+
+#[test]
+fn test_high_memory_regions()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![0x00]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    cpu.memory.write(0xFEA0, 0x12);
+    assert_eq!(cpu.memory.read(0xFEA0), Some(0xFF));
+
+    //Unmapped I/O reads back as 0xFF
+    cpu.memory.write(0xFF7F, 0x12);
+    assert_eq!(cpu.memory.read(0xFF7F), Some(0xFF));
+
+    cpu.memory.write(0xFF80, 0x34);
+    cpu.memory.write(0xFFFE, 0x56);
+    assert_eq!(cpu.memory.read(0xFF80), Some(0x34));
+    assert_eq!(cpu.memory.read(0xFFFE), Some(0x56));
+
+    cpu.memory.write(0xFFFF, 0x1F);
+    assert_eq!(cpu.memory.read(0xFFFF), Some(0x1F));
+    assert_eq!(cpu.memory.read(0xFF0F), Some(0xE0));
+}
+
+#[test]
+fn test_svbk_switches_wram_bank()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![0x00]);
+    let mut cpu = super::Cpu::new_test(cart);
+
+    assert_eq!(cpu.memory.read(0xFF70), Some(0xF9));
+    cpu.memory.write(0xD000, 0x11);
+
+    cpu.memory.write(0xFF70, 0x07);
+    assert_eq!(cpu.memory.read(0xFF70), Some(0xFF));
+    assert_eq!(cpu.memory.read(0xD000), Some(0x00));
+    cpu.memory.write(0xDDFF, 0x77);
+    assert_eq!(cpu.memory.read(0xFDFF), Some(0x77));
+
+    //Bank 0 selects bank 1
+    cpu.memory.write(0xFF70, 0x00);
+    assert_eq!(cpu.memory.read(0xFF70), Some(0xF9));
+    assert_eq!(cpu.memory.read(0xD000), Some(0x11));
+    assert_eq!(cpu.memory.read(0xDDFF), Some(0x00));
+
+    //Bank 0 at 0xC000 is never switched
+    cpu.memory.write(0xC000, 0x22);
+    cpu.memory.write(0xFF70, 0x03);
+    assert_eq!(cpu.memory.read(0xC000), Some(0x22));
+}
+
+#[test]
+fn test_oam_dma()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![0x00; 0x100]);
+    let mut cpu = super::Cpu::new_test(cart);
+    for index in 0..0xA0
+    {
+        cpu.memory.write(0xC100 + index, index as u8 ^ 0x5A);
+    }
+    cpu.memory.write(0xFF80, 0x33);
+
+    cpu.memory.write(0xFF46, 0xC1);
+    assert_eq!(cpu.memory.read(0xFF46), Some(0xC1));
+    cpu.memory.tick(8);
+
+    //Only HRAM and the I/O registers are usable during the transfer
+    assert_eq!(cpu.memory.read(0xFF80), Some(0x33));
+    assert_eq!(cpu.memory.read(0xFE00), Some(0xFF));
+    assert_eq!(cpu.memory.read(0x0010), Some(0x5A));
+    cpu.memory.write(0xC000, 0x12);
+
+    cpu.memory.tick(159 * 4);
+    assert_eq!(cpu.memory.read(0xC000), Some(0x00));
+    for index in 0..0xA0
+    {
+        assert_eq!(cpu.memory.read(0xFE00 + index), Some(index as u8 ^ 0x5A));
+    }
+}
+
+#[test]
+fn test_general_hdma()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![0x00; 0x100]);
+    let mut cpu = super::Cpu::new_test(cart);
+    for index in 0..0x20
+    {
+        cpu.memory.write(0xC000 + index, index as u8 + 1);
+    }
+
+    cpu.memory.write(0xFF51, 0xC0);
+    cpu.memory.write(0xFF52, 0x00);
+    cpu.memory.write(0xFF53, 0x81);
+    cpu.memory.write(0xFF54, 0x00);
+    cpu.memory.write(0xFF55, 0x01);
+
+    assert_eq!(cpu.memory.read(0xFF55), Some(0xFF));
+    assert_eq!(cpu.memory.take_stall_cycles(), 64);
+    for index in 0..0x20
+    {
+        assert_eq!(cpu.memory.read(0x8100 + index), Some(index as u8 + 1));
+    }
+}
+
+#[test]
+fn test_hblank_hdma()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![0x00; 0x100]);
+    let mut cpu = super::Cpu::new_test(cart);
+    for index in 0..0x20
+    {
+        cpu.memory.write(0xC000 + index, 0xA0 + index as u8);
+    }
+    cpu.memory.write(0xFF40, 0x80);
+
+    cpu.memory.write(0xFF51, 0xC0);
+    cpu.memory.write(0xFF52, 0x00);
+    cpu.memory.write(0xFF53, 0x00);
+    cpu.memory.write(0xFF54, 0x00);
+    cpu.memory.write(0xFF55, 0x81);
+    assert_eq!(cpu.memory.read(0xFF55), Some(0x01));
+
+    //First HBlank
+    cpu.memory.tick(80 + 172 + 1);
+    assert_eq!(cpu.memory.read(0xFF55), Some(0x00));
+    assert_eq!(cpu.memory.take_stall_cycles(), 32);
+    assert_eq!(cpu.memory.read(0x800F), Some(0xAF));
+    assert_eq!(cpu.memory.read(0x8010), Some(0x00));
+
+    cpu.memory.tick(456);
+    assert_eq!(cpu.memory.read(0xFF55), Some(0xFF));
+    assert_eq!(cpu.memory.read(0x801F), Some(0xBF));
+}
+
+#[test]
+fn test_double_speed_timing()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::STOP_N8 as u8, 0x00, crate::code::Opcode::INC_B as u8]);
+    let mut cpu = super::Cpu::new_test(cart);
+    cpu.memory.write(0xFF4D, 0x01);
+
+    //The cpu waits for the clock to settle after the switch
+    let cycles = cpu.step().unwrap();
+    assert_eq!(cycles >= 8200, true);
+
+    //The ppu sees half of the cpu cycles
+    cpu.memory.write(0xFF40, 0x80);
+    cpu.memory.tick(456 * 2 - 4);
+    assert_eq!(cpu.memory.ppu.get_ly(), 0);
+    cpu.memory.tick(4);
+    assert_eq!(cpu.memory.ppu.get_ly(), 1);
+
+    //The timer follows the cpu clock
+    cpu.memory.write(0xFF04, 0x00);
+    cpu.memory.tick(256);
+    assert_eq!(cpu.memory.read(0xFF04), Some(1));
+}
+
+#[test]
+fn test_post_boot_state()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![0x00; 0x8000]);
+    let cpu = super::Cpu::new(cart).unwrap();
+
+    assert_eq!(cpu.pc, 0x0100);
+    assert_eq!(cpu.sp, 0xFFFE);
+    assert_eq!(cpu.get_af(), 0x0180);
+    assert_eq!(cpu.get_bc(), 0x0013);
+    assert_eq!(cpu.get_de(), 0x00D8);
+    assert_eq!(cpu.get_hl(), 0x014D);
+    assert_eq!(cpu.memory.read(0xFF40), Some(0x91));
+    assert_eq!(cpu.memory.read(0xFF47), Some(0xFC));
+    assert_eq!(cpu.memory.read(0xFF04), Some(0xAB));
+    assert_eq!(cpu.memory.read(0xFF0F), Some(0xE1));
+    assert_eq!(cpu.memory.read(0xFF26), Some(0xF0));
+}
+
+#[test]
+fn test_boot_rom_mapping()
+{
+    let mut content = vec![0x00; 0x8000];
+    content[0x0000] = 0x11;
+    content[0x0100] = 0x22;
+    let cart = crate::Cartidge::new_from_bytes(content);
+    let boot_rom = crate::boot_rom::BootRom::new(vec![0x33; 0x100]).unwrap();
+    let mut cpu = super::Cpu::new_with_boot_rom(cart, crate::model::Model::Dmg, boot_rom).unwrap();
+
+    assert_eq!(cpu.pc, 0x0000);
+    assert_eq!(cpu.memory.read(0x0000), Some(0x33));
+    assert_eq!(cpu.memory.read(0x0100), Some(0x22));
+
+    cpu.memory.write(0xFF50, 0x01);
+    assert_eq!(cpu.memory.is_boot_rom_mapped(), false);
+    assert_eq!(cpu.memory.read(0x0000), Some(0x11));
+}
+
+#[test]
+fn test_cgb_boot_rom_skips_header()
+{
+    let boot_rom = crate::boot_rom::BootRom::new(vec![0x44; 0x900]).unwrap();
+    assert_eq!(boot_rom.read(0x00FF), Some(0x44));
+    assert_eq!(boot_rom.read(0x0150), None);
+    assert_eq!(boot_rom.read(0x0200), Some(0x44));
+    assert_eq!(boot_rom.read(0x0900), None);
+
+    assert_eq!(crate::boot_rom::BootRom::new(vec![0; 0x200]).is_err(), true);
+}
+
+#[test]
+fn test_model_post_boot_registers()
+{
+    use crate::model::Model;
+
+    let mut cgb_content = vec![0x00; 0x8000];
+    cgb_content[0x0143] = 0x80;
+
+    let cases = [
+        (Model::Mgb, false, 0xFF80, 0x0013, 0x00D8, 0x014D),
+        (Model::Sgb, false, 0x0100, 0x0014, 0x0000, 0xC060),
+        (Model::Cgb, true, 0x1180, 0x0000, 0xFF56, 0x000D),
+        (Model::Cgb, false, 0x1180, 0x0000, 0x0008, 0x007C),
+        (Model::Agb, true, 0x1100, 0x0100, 0xFF56, 0x000D),
+    ];
+    for (model, cgb_game, af, bc, de, hl) in cases
+    {
+        let content = if cgb_game { cgb_content.clone() } else { vec![0x00; 0x8000] };
+        let cpu = super::Cpu::new_with_model(crate::Cartidge::new_from_bytes(content), model).unwrap();
+
+        assert_eq!(cpu.get_model(), model);
+        assert_eq!((cpu.get_af(), cpu.get_bc(), cpu.get_de(), cpu.get_hl()), (af, bc, de, hl));
+    }
+
+    let cpu = super::Cpu::new(crate::Cartidge::new_from_bytes(cgb_content)).unwrap();
+    assert_eq!(cpu.get_model(), Model::Cgb);
+
+    //The SGB flag only counts along with the new licensee code
+    let mut sgb_content = vec![0x00; 0x8000];
+    sgb_content[0x0146] = 0x03;
+    let cpu = super::Cpu::new(crate::Cartidge::new_from_bytes(sgb_content.clone())).unwrap();
+    assert_eq!(cpu.get_model(), Model::Dmg);
+
+    sgb_content[0x014B] = 0x33;
+    let cpu = super::Cpu::new(crate::Cartidge::new_from_bytes(sgb_content)).unwrap();
+    assert_eq!(cpu.get_model(), Model::Sgb);
+}
+
+#[test]
+fn test_dmg_game_on_cgb_runs_in_compat_mode()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![0x00; 0x8000]);
+    let mut cpu = super::Cpu::new_with_model(cart, crate::model::Model::Cgb).unwrap();
+
+    assert_eq!(cpu.memory.is_cgb(), false);
+    assert_eq!(cpu.memory.is_compat_mode(), true);
+    assert_eq!(cpu.memory.ppu.is_compat_mode(), true);
+
+    //CGB registers are locked, the CGB hardware quirks remain
+    cpu.memory.write(0xFF70, 0x03);
+    assert_eq!(cpu.memory.read(0xFF70), Some(0xFF));
+    assert_eq!(cpu.memory.read(0xFF4F), Some(0xFF));
+    assert_eq!(cpu.memory.read(0xFEA0), Some(0xFF));
+}
+
+#[test]
+fn test_cgb_boot_rom_switches_to_compat_mode()
+{
+    let cart = crate::Cartidge::new_from_bytes(vec![0x00; 0x8000]);
+    let boot_rom = crate::boot_rom::BootRom::new(vec![0x00; 0x900]).unwrap();
+    let mut cpu = super::Cpu::new_with_boot_rom(cart, crate::model::Model::Cgb, boot_rom).unwrap();
+
+    assert_eq!(cpu.memory.is_cgb(), true);
+    cpu.memory.write(0xFF4C, 0x04);
+    cpu.memory.write(0xFF50, 0x11);
+
+    assert_eq!(cpu.memory.is_compat_mode(), true);
+    assert_eq!(cpu.memory.is_boot_rom_mapped(), false);
+
+    //KEY0 is locked once the boot rom is gone
+    let cart = crate::Cartidge::new_from_bytes(vec![0x00; 0x8000]);
+    let mut cpu = super::Cpu::new_with_model(cart, crate::model::Model::Dmg).unwrap();
+    cpu.memory.write(0xFF4C, 0x04);
+    assert_eq!(cpu.memory.is_compat_mode(), false);
+}
+
+#[test]
+fn test_run_frames()
+{
+    //JR -2 keeps the cpu in place
+    let mut content = vec![0x00; 0x8000];
+    content[0x0100] = 0x18;
+    content[0x0101] = 0xFE;
+    let mut cpu = super::Cpu::new(crate::Cartidge::new_from_bytes(content)).unwrap();
+
+    assert_eq!(cpu.run_frames(2), true);
+    assert_eq!(cpu.pc, 0x0100);
+    assert_eq!(cpu.take_frame().is_some(), true);
+}
+
+#[test]
+fn test_timer_read_within_instruction()
+{
+    //LDH A,(DIV) reads on its third M-cycle
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::LDH_A_iA8 as u8, 0x04]);
+    let mut cpu = super::Cpu::new_test(cart);
+    cpu.memory.timer.set_initial_counter(0x00F4);
+
+    assert_eq!(cpu.step(), Some(12));
+    assert_eq!(cpu.a, 0x01);
+}
+
+#[test]
+fn test_timer_write_within_instruction()
+{
+    //LDH (DIV),A resets the counter on its last M-cycle, nothing is left to tick afterwards
+    let cart = crate::Cartidge::new_from_bytes(vec![crate::code::Opcode::LDH_iA8_A as u8, 0x04]);
+    let mut cpu = super::Cpu::new_test(cart);
+    cpu.memory.timer.set_initial_counter(0x0100);
+
+    assert_eq!(cpu.step(), Some(12));
+    assert_eq!(cpu.memory.timer.get_counter(), 0);
+}
+
+const MOONEYE_TIMER_ROMS: [&str; 13] = [
+    "div_write",
+    "rapid_toggle",
+    "tim00",
+    "tim00_div_trigger",
+    "tim01",
+    "tim01_div_trigger",
+    "tim10",
+    "tim10_div_trigger",
+    "tim11",
+    "tim11_div_trigger",
+    "tima_reload",
+    "tima_write_reloading",
+    "tma_write_reloading",
+];
+
+#[test]
+#[ignore = "needs the mooneye acceptance/timer roms in the directory set by MOONEYE_TIMER_ROMS"]
+fn test_mooneye_timer_roms()
+{
+    let directory = std::env::var("MOONEYE_TIMER_ROMS").expect("MOONEYE_TIMER_ROMS is not set");
+    for name in MOONEYE_TIMER_ROMS
+    {
+        let content = std::fs::read(format!("{}/{}.gb", directory, name)).unwrap_or_else(|error| panic!("Can't read {}: {}", name, error));
+        let mut cpu = super::Cpu::new_with_model(crate::Cartidge::new_from_bytes(content), crate::model::Model::Dmg).unwrap();
+
+        //The roms end with LD B,B after storing the result in the registers
+        let mut cycles = 0;
+        while cpu.memory.read(cpu.pc) != Some(crate::code::Opcode::LD_B_B as u8)
+        {
+            cycles += cpu.step().unwrap_or_else(|| panic!("{} ran out of memory", name));
+            assert_eq!(cycles < 600 * super::CYCLES_PER_FRAME, true, "{} timed out", name);
+        }
+        assert_eq!([cpu.b, cpu.c, cpu.d, cpu.e, cpu.h, cpu.l], [3, 5, 8, 13, 21, 34], "{} failed", name);
+    }
+}
@@ -0,0 +1,244 @@
+mod tests;
+
+const OAM_SIZE: u8 = 0xA0;
+pub const HDMA_BLOCK_SIZE: u16 = 0x10;
+
+/**
+ * The two memory buses of the DMG. OAM DMA occupies the one its source lives on, the cpu can
+ * still use the other one and HRAM
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemoryBus {
+    External,
+    Video,
+}
+
+impl MemoryBus {
+    pub fn from_address(address: u16) -> Option<Self> {
+        match address {
+            0x0000..=0x7FFF | 0xA000..=0xFDFF => Some(MemoryBus::External),
+            0x8000..=0x9FFF => Some(MemoryBus::Video),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * OAM DMA started by writing the source page to 0xFF46. After a M-cycle of setup it copies a byte per
+ * M-cycle from XX00-XX9F into OAM, 160 M-cycles in total
+ */
+pub struct OamDma {
+    source: u8,
+    index: u8,
+    active: bool,
+    blocking: bool,
+    setup: u8,
+    cycles: u8,
+    last_value: u8,
+}
+
+impl OamDma {
+    pub fn new() -> Self {
+        OamDma {
+            source: 0xFF,
+            index: 0,
+            active: false,
+            blocking: false,
+            setup: 0,
+            cycles: 0,
+            last_value: 0xFF,
+        }
+    }
+
+    /**
+     * Restarting a running transfer keeps the bus blocked while the new one sets up
+     */
+    pub fn start(&mut self, source: u8) {
+        self.source = source;
+        self.index = 0;
+        self.active = true;
+        self.setup = 1;
+        self.cycles = 0;
+    }
+
+    pub fn read_register(&self) -> u8 {
+        self.source
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /**
+     * Bus the transfer is holding, None while no transfer is copying bytes
+     */
+    pub fn get_blocked_bus(&self) -> Option<MemoryBus> {
+        if self.blocking {
+            MemoryBus::from_address(self.get_source_address(self.index))
+        } else {
+            None
+        }
+    }
+
+    /**
+     * Byte on the blocked bus, which is what the cpu reads when it tries to use it
+     */
+    pub fn get_last_value(&self) -> u8 {
+        self.last_value
+    }
+
+    /**
+     * Sources above 0xDFFF read from echo RAM
+     */
+    fn get_source_address(&self, index: u8) -> u16 {
+        let address = (self.source as u16) << 8 | index as u16;
+        if address >= 0xE000 {
+            address - 0x2000
+        } else {
+            address
+        }
+    }
+
+    /**
+     * Advances the transfer by a T-cycle. Returns the source address and OAM offset of the byte to copy
+     * when one is due
+     */
+    pub fn tick(&mut self) -> Option<(u16, u8)> {
+        if !self.active {
+            return None;
+        }
+
+        self.cycles = (self.cycles + 1) & 0x03;
+        if self.cycles != 0 {
+            return None;
+        }
+
+        if self.setup > 0 {
+            self.setup -= 1;
+            return None;
+        }
+
+        self.blocking = true;
+        let index = self.index;
+        self.index += 1;
+        if self.index == OAM_SIZE {
+            self.active = false;
+        }
+        Some((self.get_source_address(index), index))
+    }
+
+    /**
+     * Called by the bus once the byte has been copied, the bus stays blocked until then
+     */
+    pub fn complete_transfer(&mut self, value: u8) {
+        self.last_value = value;
+        if !self.active {
+            self.blocking = false;
+        }
+    }
+}
+
+impl Default for OamDma {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HdmaMode {
+    Idle,
+    General,
+    HBlank,
+}
+
+/**
+ * CGB VRAM DMA, HDMA1-HDMA5 at 0xFF51-0xFF55. A general transfer copies every block at once while the
+ * cpu is halted, a HBlank transfer copies a 16 byte block at the start of each HBlank
+ */
+pub struct Hdma {
+    source: u16,
+    destination: u16,
+    remaining: u8,
+    mode: HdmaMode,
+}
+
+impl Hdma {
+    pub fn new() -> Self {
+        Hdma {
+            source: 0,
+            destination: 0,
+            remaining: 0x7F,
+            mode: HdmaMode::Idle,
+        }
+    }
+
+    pub fn get_mode(&self) -> HdmaMode {
+        self.mode
+    }
+
+    /**
+     * The source is aligned to 16 bytes and the destination always falls inside VRAM
+     */
+    pub fn write_register(&mut self, address: u16, value: u8) {
+        match address {
+            0xFF51 => self.source = (value as u16) << 8 | (self.source & 0x00F0),
+            0xFF52 => self.source = (self.source & 0xFF00) | (value & 0xF0) as u16,
+            0xFF53 => self.destination = ((value & 0x1F) as u16) << 8 | (self.destination & 0x00F0),
+            0xFF54 => self.destination = (self.destination & 0x1F00) | (value & 0xF0) as u16,
+            0xFF55 => self.write_control(value),
+            _ => {}
+        }
+    }
+
+    /**
+     * HDMA1-4 are write only. HDMA5 reads the remaining blocks minus one, with bit 7 set once the
+     * transfer is over or has been cancelled
+     */
+    pub fn read_register(&self, address: u16) -> u8 {
+        match address {
+            0xFF55 if self.mode == HdmaMode::HBlank => self.remaining,
+            0xFF55 => 0x80 | self.remaining,
+            _ => 0xFF,
+        }
+    }
+
+    /**
+     * Writing bit 7 clear during a HBlank transfer cancels it and keeps the remaining length
+     */
+    fn write_control(&mut self, value: u8) {
+        if self.mode == HdmaMode::HBlank && value & 0x80 == 0 {
+            self.mode = HdmaMode::Idle;
+            return;
+        }
+
+        self.remaining = value & 0x7F;
+        self.mode = if value & 0x80 != 0 {
+            HdmaMode::HBlank
+        } else {
+            HdmaMode::General
+        };
+    }
+
+    /**
+     * Source and VRAM offset of the next block, advancing the transfer past it
+     */
+    pub fn take_block(&mut self) -> (u16, u16) {
+        let block = (self.source, self.destination);
+        self.source = self.source.wrapping_add(HDMA_BLOCK_SIZE);
+        self.destination = (self.destination + HDMA_BLOCK_SIZE) & 0x1FF0;
+
+        if self.remaining == 0 {
+            self.remaining = 0x7F;
+            self.mode = HdmaMode::Idle;
+        } else {
+            self.remaining -= 1;
+        }
+        block
+    }
+}
+
+impl Default for Hdma {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,111 @@
+#![cfg(test)]
+#![allow(clippy::bool_assert_comparison)]
+
+use super::{Hdma, HdmaMode, MemoryBus, OamDma};
+
+fn tick_m_cycle(dma: &mut OamDma) -> Option<(u16, u8)> {
+    (0..4).filter_map(|_| dma.tick()).last()
+}
+
+#[test]
+fn test_transfer_timing() {
+    let mut dma = OamDma::new();
+    dma.start(0xC1);
+    assert_eq!(dma.read_register(), 0xC1);
+
+    //Setup M-cycle
+    assert_eq!(tick_m_cycle(&mut dma), None);
+    assert_eq!(dma.get_blocked_bus(), None);
+
+    for index in 0..0xA0 {
+        assert_eq!(tick_m_cycle(&mut dma), Some((0xC100 + index as u16, index)));
+        dma.complete_transfer(index);
+        assert_eq!(dma.get_blocked_bus().is_some(), index != 0x9F);
+    }
+
+    assert_eq!(dma.is_active(), false);
+    assert_eq!(tick_m_cycle(&mut dma), None);
+}
+
+#[test]
+fn test_blocked_bus() {
+    let mut dma = OamDma::new();
+    dma.start(0x80);
+    tick_m_cycle(&mut dma);
+    tick_m_cycle(&mut dma);
+    dma.complete_transfer(0x12);
+
+    assert_eq!(dma.get_blocked_bus(), Some(MemoryBus::Video));
+    assert_eq!(dma.get_last_value(), 0x12);
+}
+
+#[test]
+fn test_echo_source() {
+    let mut dma = OamDma::new();
+    dma.start(0xFE);
+    tick_m_cycle(&mut dma);
+
+    assert_eq!(tick_m_cycle(&mut dma), Some((0xDE00, 0)));
+}
+
+#[test]
+fn test_restart_keeps_bus_blocked() {
+    let mut dma = OamDma::new();
+    dma.start(0xC0);
+    for _ in 0..10 {
+        if tick_m_cycle(&mut dma).is_some() {
+            dma.complete_transfer(0);
+        }
+    }
+
+    dma.start(0x40);
+    assert_eq!(tick_m_cycle(&mut dma), None);
+    assert_eq!(dma.get_blocked_bus(), Some(MemoryBus::External));
+    assert_eq!(tick_m_cycle(&mut dma), Some((0x4000, 0)));
+}
+
+#[test]
+fn test_hdma_address_masking() {
+    let mut hdma = Hdma::new();
+    hdma.write_register(0xFF51, 0x12);
+    hdma.write_register(0xFF52, 0x3F);
+    hdma.write_register(0xFF53, 0xE5);
+    hdma.write_register(0xFF54, 0x6F);
+    hdma.write_register(0xFF55, 0x00);
+
+    assert_eq!(hdma.take_block(), (0x1230, 0x0560));
+    assert_eq!(hdma.read_register(0xFF51), 0xFF);
+}
+
+#[test]
+fn test_general_transfer() {
+    let mut hdma = Hdma::new();
+    hdma.write_register(0xFF55, 0x02);
+    assert_eq!(hdma.get_mode(), HdmaMode::General);
+    assert_eq!(hdma.read_register(0xFF55), 0x82);
+
+    assert_eq!(hdma.take_block(), (0x0000, 0x0000));
+    assert_eq!(hdma.take_block(), (0x0010, 0x0010));
+    assert_eq!(hdma.take_block(), (0x0020, 0x0020));
+    assert_eq!(hdma.get_mode(), HdmaMode::Idle);
+    assert_eq!(hdma.read_register(0xFF55), 0xFF);
+}
+
+#[test]
+fn test_hblank_transfer_cancel() {
+    let mut hdma = Hdma::new();
+    hdma.write_register(0xFF53, 0x1F);
+    hdma.write_register(0xFF54, 0xF0);
+    hdma.write_register(0xFF55, 0x83);
+    assert_eq!(hdma.get_mode(), HdmaMode::HBlank);
+    assert_eq!(hdma.read_register(0xFF55), 0x03);
+
+    //The destination wraps around inside VRAM
+    assert_eq!(hdma.take_block(), (0x0000, 0x1FF0));
+    assert_eq!(hdma.take_block(), (0x0010, 0x0000));
+    assert_eq!(hdma.read_register(0xFF55), 0x01);
+
+    hdma.write_register(0xFF55, 0x00);
+    assert_eq!(hdma.get_mode(), HdmaMode::Idle);
+    assert_eq!(hdma.read_register(0xFF55), 0x81);
+}
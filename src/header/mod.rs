@@ -0,0 +1,295 @@
+mod tests;
+
+use std::{error::Error, fmt};
+
+use crate::cartridge::Mapper;
+
+const HEADER_END: usize = 0x150;
+
+const LOGO_START: usize = 0x104;
+const TITLE_START: usize = 0x134;
+const MANUFACTURER_START: usize = 0x13F;
+const CGB_FLAG: usize = 0x143;
+const NEW_LICENSEE_START: usize = 0x144;
+const SGB_FLAG: usize = 0x146;
+const CARTRIDGE_TYPE: usize = 0x147;
+const ROM_SIZE: usize = 0x148;
+const RAM_SIZE: usize = 0x149;
+const DESTINATION: usize = 0x14A;
+const OLD_LICENSEE: usize = 0x14B;
+const VERSION: usize = 0x14C;
+const HEADER_CHECKSUM: usize = 0x14D;
+const GLOBAL_CHECKSUM: usize = 0x14E;
+
+/**
+ * Bitmap the boot rom scrolls down the screen, cartridges that don't carry it are locked out on real hardware
+ */
+pub const NINTENDO_LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeaderError {
+    TooShort(usize),
+    UnknownCartridgeType(u8),
+    UnknownRomSize(u8),
+    UnknownRamSize(u8),
+    InvalidLogo,
+    HeaderChecksumMismatch { expected: u8, computed: u8 },
+    GlobalChecksumMismatch { expected: u16, computed: u16 },
+}
+
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeaderError::TooShort(size) => {
+                write!(f, "The rom is {} bytes long, too short to hold a header", size)
+            }
+            HeaderError::UnknownCartridgeType(cartridge_type) => {
+                write!(f, "Unknown cartridge type {:#04X}", cartridge_type)
+            }
+            HeaderError::UnknownRomSize(size) => write!(f, "Unknown rom size {:#04X}", size),
+            HeaderError::UnknownRamSize(size) => write!(f, "Unknown ram size {:#04X}", size),
+            HeaderError::InvalidLogo => write!(f, "The Nintendo logo doesn't match"),
+            HeaderError::HeaderChecksumMismatch { expected, computed } => write!(
+                f,
+                "Header checksum is {:#04X} but the header sums to {:#04X}",
+                expected, computed
+            ),
+            HeaderError::GlobalChecksumMismatch { expected, computed } => write!(
+                f,
+                "Global checksum is {:#06X} but the rom sums to {:#06X}",
+                expected, computed
+            ),
+        }
+    }
+}
+
+impl Error for HeaderError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CgbSupport {
+    None,
+    Compatible,
+    Only,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Licensee {
+    Old(u8),
+    New(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Destination {
+    Japan,
+    Overseas,
+    //Real hardware never looks at this byte, so odd values don't stop the game from booting
+    Unknown(u8),
+}
+
+/**
+ * Parsed contents of the cartridge header (0x100-0x14F)
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct CartridgeHeader {
+    pub title: String,
+    pub manufacturer_code: Option<String>,
+    pub cgb_support: CgbSupport,
+    pub sgb_support: bool,
+    pub cartridge_type: u8,
+    pub mapper: Mapper,
+    pub licensee: Licensee,
+    pub rom_banks: usize,
+    pub ram_banks: usize,
+    pub destination: Destination,
+    pub version: u8,
+    pub header_checksum: u8,
+    pub global_checksum: u16,
+}
+
+impl CartridgeHeader {
+    pub fn parse(content: &[u8]) -> Result<Self, HeaderError> {
+        if content.len() < HEADER_END {
+            return Err(HeaderError::TooShort(content.len()));
+        }
+
+        let cgb_support = match content[CGB_FLAG] {
+            0x80 => CgbSupport::Compatible,
+            0xC0 => CgbSupport::Only,
+            _ => CgbSupport::None,
+        };
+
+        let licensee = if content[OLD_LICENSEE] == 0x33 {
+            Licensee::New(read_text(&content[NEW_LICENSEE_START..NEW_LICENSEE_START + 2]))
+        } else {
+            Licensee::Old(content[OLD_LICENSEE])
+        };
+
+        //Newer cartridges shortened the title to make room for a 4 character manufacturer code,
+        //there is no flag for it so it's only trusted when it looks like one
+        let manufacturer = &content[MANUFACTURER_START..CGB_FLAG];
+        let has_manufacturer_code = cgb_support != CgbSupport::None
+            && matches!(licensee, Licensee::New(_))
+            && manufacturer
+                .iter()
+                .all(|byte| byte.is_ascii_uppercase() || byte.is_ascii_digit());
+
+        let (title, manufacturer_code) = if has_manufacturer_code {
+            (
+                read_text(&content[TITLE_START..MANUFACTURER_START]),
+                Some(read_text(manufacturer)),
+            )
+        } else if cgb_support != CgbSupport::None {
+            (read_text(&content[TITLE_START..CGB_FLAG]), None)
+        } else {
+            (read_text(&content[TITLE_START..NEW_LICENSEE_START]), None)
+        };
+
+        let cartridge_type = content[CARTRIDGE_TYPE];
+
+        Ok(CartridgeHeader {
+            title,
+            manufacturer_code,
+            cgb_support,
+            sgb_support: content[SGB_FLAG] == 0x03,
+            cartridge_type,
+            mapper: get_mapper(cartridge_type)?,
+            licensee,
+            rom_banks: get_rom_banks(content[ROM_SIZE])?,
+            ram_banks: get_ram_banks(content[RAM_SIZE])?,
+            destination: match content[DESTINATION] {
+                0x00 => Destination::Japan,
+                0x01 => Destination::Overseas,
+                destination => Destination::Unknown(destination),
+            },
+            version: content[VERSION],
+            header_checksum: content[HEADER_CHECKSUM],
+            global_checksum: u16::from_be_bytes([
+                content[GLOBAL_CHECKSUM],
+                content[GLOBAL_CHECKSUM + 1],
+            ]),
+        })
+    }
+
+    /**
+     * Runs the same checks as the boot rom (logo and header checksum) plus the global checksum,
+     * which real hardware ignores but is handy to spot bad dumps
+     */
+    pub fn verify(&self, content: &[u8]) -> Result<(), HeaderError> {
+        if content.len() < HEADER_END {
+            return Err(HeaderError::TooShort(content.len()));
+        }
+
+        if content[LOGO_START..TITLE_START] != NINTENDO_LOGO {
+            return Err(HeaderError::InvalidLogo);
+        }
+
+        let computed = compute_header_checksum(content);
+        if computed != self.header_checksum {
+            return Err(HeaderError::HeaderChecksumMismatch {
+                expected: self.header_checksum,
+                computed,
+            });
+        }
+
+        let computed = compute_global_checksum(content);
+        if computed != self.global_checksum {
+            return Err(HeaderError::GlobalChecksumMismatch {
+                expected: self.global_checksum,
+                computed,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn is_cgb(&self) -> bool {
+        self.cgb_support != CgbSupport::None
+    }
+
+    pub fn has_battery(&self) -> bool {
+        matches!(
+            self.cartridge_type,
+            0x03 | 0x06 | 0x09 | 0x0D | 0x0F | 0x10 | 0x13 | 0x1B | 0x1E | 0x20 | 0x22 | 0xFC | 0xFE | 0xFF
+        )
+    }
+
+    pub fn has_rtc(&self) -> bool {
+        matches!(self.cartridge_type, 0x0F | 0x10)
+    }
+
+    pub fn has_rumble(&self) -> bool {
+        matches!(self.cartridge_type, 0x1C..=0x1E)
+    }
+}
+
+pub fn compute_header_checksum(content: &[u8]) -> u8 {
+    content[TITLE_START..=VERSION]
+        .iter()
+        .fold(0u8, |checksum, byte| checksum.wrapping_sub(*byte).wrapping_sub(1))
+}
+
+/**
+ * Sum of every byte of the rom except the two holding the checksum itself
+ */
+pub fn compute_global_checksum(content: &[u8]) -> u16 {
+    content
+        .iter()
+        .enumerate()
+        .filter(|(address, _)| *address != GLOBAL_CHECKSUM && *address != GLOBAL_CHECKSUM + 1)
+        .fold(0u16, |checksum, (_, byte)| checksum.wrapping_add(*byte as u16))
+}
+
+fn read_text(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .take_while(|byte| **byte != 0)
+        .map(|byte| *byte as char)
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+fn get_mapper(cartridge_type: u8) -> Result<Mapper, HeaderError> {
+    match cartridge_type {
+        0x00 | 0x08 | 0x09 => Ok(Mapper::RomOnly),
+        0x01..=0x03 => Ok(Mapper::Mbc1),
+        0x05 | 0x06 => Ok(Mapper::Mbc2),
+        0x0B..=0x0D => Ok(Mapper::Mmm01),
+        0x0F..=0x13 => Ok(Mapper::Mbc3),
+        0x19..=0x1E => Ok(Mapper::Mbc5),
+        0x20 => Ok(Mapper::Mbc6),
+        0x22 => Ok(Mapper::Mbc7),
+        0xFC => Ok(Mapper::PocketCamera),
+        0xFD => Ok(Mapper::BandaiTama5),
+        0xFE => Ok(Mapper::HuC3),
+        0xFF => Ok(Mapper::HuC1),
+        _ => Err(HeaderError::UnknownCartridgeType(cartridge_type)),
+    }
+}
+
+fn get_rom_banks(rom_size: u8) -> Result<usize, HeaderError> {
+    match rom_size {
+        0x00..=0x08 => Ok(2 << rom_size),
+        0x52 => Ok(72),
+        0x53 => Ok(80),
+        0x54 => Ok(96),
+        _ => Err(HeaderError::UnknownRomSize(rom_size)),
+    }
+}
+
+fn get_ram_banks(ram_size: u8) -> Result<usize, HeaderError> {
+    match ram_size {
+        0x00 => Ok(0),
+        //Officially unused, a few early carts report a partial 2 KiB bank with it
+        0x01 => Ok(1),
+        0x02 => Ok(1),
+        0x03 => Ok(4),
+        0x04 => Ok(16),
+        0x05 => Ok(8),
+        _ => Err(HeaderError::UnknownRamSize(ram_size)),
+    }
+}
@@ -0,0 +1,131 @@
+#![cfg(test)]
+#![allow(clippy::bool_assert_comparison)]
+
+use crate::cartridge::Mapper;
+
+use super::{
+    compute_global_checksum, compute_header_checksum, CartridgeHeader, CgbSupport, Destination,
+    HeaderError, Licensee, NINTENDO_LOGO,
+};
+
+/**
+ * Builds a 32 KiB rom with a valid logo and both checksums filled in
+ */
+fn build_rom(title: &[u8], cgb_flag: u8, old_licensee: u8) -> Vec<u8> {
+    let mut content = vec![0; 32 * 1024];
+    content[0x104..0x134].copy_from_slice(&NINTENDO_LOGO);
+    content[0x134..0x134 + title.len()].copy_from_slice(title);
+    content[0x143] = cgb_flag;
+    content[0x14B] = old_licensee;
+    fix_checksums(&mut content);
+    content
+}
+
+fn fix_checksums(content: &mut [u8]) {
+    content[0x14D] = compute_header_checksum(content);
+    let global_checksum = compute_global_checksum(content);
+    content[0x14E..0x150].copy_from_slice(&global_checksum.to_be_bytes());
+}
+
+#[test]
+fn test_parse_dmg_header() {
+    let mut content = build_rom(b"TETRIS", 0x00, 0x01);
+    content[0x148] = 0x01;
+    content[0x14A] = 0x01;
+    content[0x14C] = 0x02;
+    fix_checksums(&mut content);
+
+    let header = CartridgeHeader::parse(&content).unwrap();
+
+    assert_eq!(header.title, "TETRIS");
+    assert_eq!(header.manufacturer_code, None);
+    assert_eq!(header.cgb_support, CgbSupport::None);
+    assert_eq!(header.is_cgb(), false);
+    assert_eq!(header.sgb_support, false);
+    assert_eq!(header.mapper, Mapper::RomOnly);
+    assert_eq!(header.licensee, Licensee::Old(0x01));
+    assert_eq!(header.rom_banks, 4);
+    assert_eq!(header.ram_banks, 0);
+    assert_eq!(header.destination, Destination::Overseas);
+    assert_eq!(header.version, 2);
+    assert_eq!(header.verify(&content), Ok(()));
+}
+
+#[test]
+fn test_parse_cgb_header_with_manufacturer_code() {
+    let mut content = build_rom(b"POKEMON_SLVAAXE", 0x80, 0x33);
+    content[0x144..0x146].copy_from_slice(b"01");
+    content[0x146] = 0x03;
+    content[0x147] = 0x10;
+    content[0x149] = 0x03;
+    fix_checksums(&mut content);
+
+    let header = CartridgeHeader::parse(&content).unwrap();
+
+    assert_eq!(header.title, "POKEMON_SLV");
+    assert_eq!(header.manufacturer_code, Some("AAXE".to_string()));
+    assert_eq!(header.cgb_support, CgbSupport::Compatible);
+    assert_eq!(header.sgb_support, true);
+    assert_eq!(header.mapper, Mapper::Mbc3);
+    assert_eq!(header.has_rtc(), true);
+    assert_eq!(header.has_battery(), true);
+    assert_eq!(header.licensee, Licensee::New("01".to_string()));
+    assert_eq!(header.ram_banks, 4);
+    assert_eq!(header.destination, Destination::Japan);
+}
+
+#[test]
+fn test_parse_cgb_only_header() {
+    let content = build_rom(b"DRYMOUTH", 0xC0, 0x00);
+
+    let header = CartridgeHeader::parse(&content).unwrap();
+
+    assert_eq!(header.title, "DRYMOUTH");
+    assert_eq!(header.cgb_support, CgbSupport::Only);
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(CartridgeHeader::parse(&[0; 0x100]), Err(HeaderError::TooShort(0x100)));
+
+    let mut content = build_rom(b"BROKEN", 0x00, 0x00);
+    content[0x147] = 0x42;
+    assert_eq!(CartridgeHeader::parse(&content), Err(HeaderError::UnknownCartridgeType(0x42)));
+
+    content[0x147] = 0x00;
+    content[0x148] = 0x09;
+    assert_eq!(CartridgeHeader::parse(&content), Err(HeaderError::UnknownRomSize(0x09)));
+
+    content[0x148] = 0x00;
+    content[0x149] = 0x06;
+    assert_eq!(CartridgeHeader::parse(&content), Err(HeaderError::UnknownRamSize(0x06)));
+}
+
+#[test]
+fn test_unknown_destination() {
+    let mut content = build_rom(b"REGION", 0x00, 0x00);
+    content[0x14A] = 0x02;
+
+    assert_eq!(CartridgeHeader::parse(&content).unwrap().destination, Destination::Unknown(0x02));
+}
+
+#[test]
+fn test_verify_errors() {
+    let mut content = build_rom(b"CHECKS", 0x00, 0x00);
+    let header = CartridgeHeader::parse(&content).unwrap();
+
+    content[0x2000] = 0x01;
+    assert!(matches!(
+        header.verify(&content),
+        Err(HeaderError::GlobalChecksumMismatch { .. })
+    ));
+
+    content[0x134] = b'X';
+    assert!(matches!(
+        header.verify(&content),
+        Err(HeaderError::HeaderChecksumMismatch { .. })
+    ));
+
+    content[0x104] = 0x00;
+    assert_eq!(header.verify(&content), Err(HeaderError::InvalidLogo));
+}
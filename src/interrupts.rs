@@ -0,0 +1,104 @@
+/**
+ * Interrupt sources, declared in priority order (VBlank has the highest priority)
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interrupt {
+    VBlank,
+    Stat,
+    Timer,
+    Serial,
+    Joypad,
+}
+
+impl Interrupt {
+    const PRIORITY: [Interrupt; 5] = [
+        Interrupt::VBlank,
+        Interrupt::Stat,
+        Interrupt::Timer,
+        Interrupt::Serial,
+        Interrupt::Joypad,
+    ];
+
+    pub fn get_bit(&self) -> u8 {
+        match self {
+            Interrupt::VBlank => 0b00001,
+            Interrupt::Stat => 0b00010,
+            Interrupt::Timer => 0b00100,
+            Interrupt::Serial => 0b01000,
+            Interrupt::Joypad => 0b10000,
+        }
+    }
+
+    pub fn get_vector(&self) -> u16 {
+        match self {
+            Interrupt::VBlank => 0x40,
+            Interrupt::Stat => 0x48,
+            Interrupt::Timer => 0x50,
+            Interrupt::Serial => 0x58,
+            Interrupt::Joypad => 0x60,
+        }
+    }
+}
+
+/**
+ * Holds the IE (0xFFFF) and IF (0xFF0F) registers. Other components request interrupts through it
+ * and the cpu polls it between instructions
+ */
+pub struct InterruptController {
+    enabled: u8,
+    requested: u8,
+}
+
+impl InterruptController {
+    pub fn new() -> Self {
+        InterruptController {
+            enabled: 0,
+            requested: 0,
+        }
+    }
+
+    pub fn request(&mut self, interrupt: Interrupt) {
+        self.requested |= interrupt.get_bit();
+    }
+
+    pub fn clear(&mut self, interrupt: Interrupt) {
+        self.requested &= !interrupt.get_bit();
+    }
+
+    pub fn is_requested(&self, interrupt: Interrupt) -> bool {
+        self.requested & interrupt.get_bit() != 0
+    }
+
+    /**
+     * Returns the highest priority interrupt that is both requested and enabled
+     */
+    pub fn get_pending(&self) -> Option<Interrupt> {
+        let pending = self.enabled & self.requested;
+        Interrupt::PRIORITY
+            .into_iter()
+            .find(|interrupt| pending & interrupt.get_bit() != 0)
+    }
+
+    pub fn read_ie(&self) -> u8 {
+        self.enabled
+    }
+
+    pub fn write_ie(&mut self, value: u8) {
+        self.enabled = value;
+    }
+
+    pub fn read_if(&self) -> u8 {
+        //Upper 3 bits are unused and always read as 1
+        self.requested | 0xE0
+    }
+
+    pub fn write_if(&mut self, value: u8) {
+        self.requested = value & 0x1F;
+    }
+}
+
+impl Default for InterruptController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
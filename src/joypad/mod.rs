@@ -0,0 +1,111 @@
+mod tests;
+
+use crate::interrupts::{Interrupt, InterruptController};
+
+const SELECT_DIRECTIONS: u8 = 0b0001_0000;
+const SELECT_ACTIONS: u8 = 0b0010_0000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Button {
+    Right,
+    Left,
+    Up,
+    Down,
+    A,
+    B,
+    Select,
+    Start,
+}
+
+impl Button {
+    /**
+     * Bit of the button in the combined state, directions in the low nibble and actions in the high one.
+     * Inside each nibble the order matches the P1 input lines
+     */
+    fn get_bit(&self) -> u8 {
+        match self {
+            Button::Right => 0b0000_0001,
+            Button::Left => 0b0000_0010,
+            Button::Up => 0b0000_0100,
+            Button::Down => 0b0000_1000,
+            Button::A => 0b0001_0000,
+            Button::B => 0b0010_0000,
+            Button::Select => 0b0100_0000,
+            Button::Start => 0b1000_0000,
+        }
+    }
+}
+
+/**
+ * P1/JOYP register at 0xFF00. Bits 4 and 5 select the directions and the action buttons, a pressed
+ * button pulls its input line low on the lower 4 bits for every selected group
+ */
+pub struct Joypad {
+    select: u8,
+    pressed: u8,
+}
+
+impl Joypad {
+    pub fn new() -> Self {
+        Joypad {
+            select: SELECT_DIRECTIONS | SELECT_ACTIONS,
+            pressed: 0,
+        }
+    }
+
+    pub fn press(&mut self, button: Button, interrupts: &mut InterruptController) {
+        self.update(|joypad| joypad.pressed |= button.get_bit(), interrupts);
+    }
+
+    pub fn release(&mut self, button: Button, interrupts: &mut InterruptController) {
+        self.update(|joypad| joypad.pressed &= !button.get_bit(), interrupts);
+    }
+
+    pub fn is_pressed(&self, button: Button) -> bool {
+        self.pressed & button.get_bit() != 0
+    }
+
+    /**
+     * Input lines as seen on the lower 4 bits, active low
+     */
+    fn get_lines(&self) -> u8 {
+        let mut lines = 0x0F;
+        if self.select & SELECT_DIRECTIONS == 0 {
+            lines &= !(self.pressed & 0x0F);
+        }
+        if self.select & SELECT_ACTIONS == 0 {
+            lines &= !(self.pressed >> 4);
+        }
+        lines
+    }
+
+    /**
+     * The interrupt is requested when any of the input lines goes from high to low, either because a
+     * button of a selected group was pressed or because a group with a held button got selected
+     */
+    fn update(&mut self, change: impl FnOnce(&mut Self), interrupts: &mut InterruptController) {
+        let before = self.get_lines();
+        change(self);
+        if before & !self.get_lines() != 0 {
+            interrupts.request(Interrupt::Joypad);
+        }
+    }
+
+    pub fn read_register(&self) -> u8 {
+        //Bits 6 and 7 are unused and read as 1
+        0xC0 | self.select | self.get_lines()
+    }
+
+    pub fn write_register(&mut self, value: u8, interrupts: &mut InterruptController) {
+        self.update(
+            |joypad| joypad.select = value & (SELECT_DIRECTIONS | SELECT_ACTIONS),
+            interrupts,
+        );
+    }
+}
+
+impl Default for Joypad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
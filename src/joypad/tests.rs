@@ -0,0 +1,67 @@
+#![cfg(test)]
+#![allow(clippy::bool_assert_comparison)]
+
+use crate::interrupts::{Interrupt, InterruptController};
+
+use super::{Button, Joypad};
+
+#[test]
+fn test_nothing_selected_reads_high() {
+    let mut interrupts = InterruptController::new();
+    let mut joypad = Joypad::new();
+    joypad.press(Button::A, &mut interrupts);
+    joypad.press(Button::Down, &mut interrupts);
+
+    assert_eq!(joypad.read_register(), 0xFF);
+    assert_eq!(interrupts.is_requested(Interrupt::Joypad), false);
+}
+
+#[test]
+fn test_group_selection() {
+    let mut interrupts = InterruptController::new();
+    let mut joypad = Joypad::new();
+    joypad.press(Button::Start, &mut interrupts);
+    joypad.press(Button::Left, &mut interrupts);
+
+    joypad.write_register(0x20, &mut interrupts);
+    assert_eq!(joypad.read_register(), 0xED);
+
+    joypad.write_register(0x10, &mut interrupts);
+    assert_eq!(joypad.read_register(), 0xD7);
+
+    joypad.write_register(0x00, &mut interrupts);
+    assert_eq!(joypad.read_register(), 0xC5);
+
+    joypad.release(Button::Start, &mut interrupts);
+    assert_eq!(joypad.read_register(), 0xCD);
+    assert_eq!(joypad.is_pressed(Button::Start), false);
+    assert_eq!(joypad.is_pressed(Button::Left), true);
+}
+
+#[test]
+fn test_interrupt_on_press() {
+    let mut interrupts = InterruptController::new();
+    let mut joypad = Joypad::new();
+    joypad.write_register(0x10, &mut interrupts);
+
+    joypad.press(Button::Up, &mut interrupts);
+    assert_eq!(interrupts.is_requested(Interrupt::Joypad), false);
+
+    joypad.press(Button::B, &mut interrupts);
+    assert_eq!(interrupts.is_requested(Interrupt::Joypad), true);
+
+    //Releasing is a low to high transition
+    interrupts.clear(Interrupt::Joypad);
+    joypad.release(Button::B, &mut interrupts);
+    assert_eq!(interrupts.is_requested(Interrupt::Joypad), false);
+}
+
+#[test]
+fn test_interrupt_on_selecting_held_button() {
+    let mut interrupts = InterruptController::new();
+    let mut joypad = Joypad::new();
+    joypad.press(Button::Right, &mut interrupts);
+
+    joypad.write_register(0x20, &mut interrupts);
+    assert_eq!(interrupts.is_requested(Interrupt::Joypad), true);
+}
@@ -0,0 +1,20 @@
+pub mod apu;
+pub mod audio;
+pub mod boot_rom;
+pub mod bus;
+pub mod cartridge;
+pub mod code;
+pub mod cpu;
+pub mod dma;
+pub mod header;
+pub mod interrupts;
+pub mod joypad;
+pub mod mbc;
+pub mod memory_bank;
+pub mod model;
+pub mod ppu;
+pub mod save_file;
+pub mod timer;
+
+pub use cartridge::Cartidge;
+pub use cpu::Cpu;
@@ -1,17 +1,80 @@
-use std::env;
+use std::{env, fs, process};
 
-use cartridge::Cartidge;
-use cpu::Cpu;
-
-mod bus;
-mod cartridge;
-mod code;
-mod cpu;
-mod memory_bank;
+use gbc::{
+    audio::{SampleFormat, WavSink},
+    boot_rom::BootRom,
+    model::Model,
+    ppu::Renderer,
+    Cartidge, Cpu,
+};
 
 fn main() {
-    let path = env::args().nth(1).expect("Usage gbc {path to rom}");
+    let args: Vec<String> = env::args().collect();
+    let path = args
+        .get(1)
+        .cloned()
+        .expect("Usage gbc {path to rom} [--model {dmg|mgb|sgb|cgb|agb}] [--boot {path to boot rom}] [--fifo] [--wav {path to wav}] [--frames {count}]");
     let cart = Cartidge::new(path);
-    let mut cpu = Cpu::new(cart);
-    cpu.run();
+    if let Err(error) = cart.verify_header() {
+        eprintln!("Warning: {}", error);
+    }
+    let boot_rom = get_option(&args, "--boot").map(|boot_path| {
+        fs::read(boot_path)
+            .map_err(|error| error.to_string())
+            .and_then(|content| BootRom::new(content).map_err(|error| error.to_string()))
+            .unwrap_or_else(|error| {
+                eprintln!("Couldn't load the boot rom {}: {}", boot_path, error);
+                process::exit(1);
+            })
+    });
+    let model = get_option(&args, "--model").map(|name| {
+        name.parse::<Model>().unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(1);
+        })
+    });
+    let cpu = match (boot_rom, model) {
+        (Some(boot_rom), model) => {
+            //Without a model the boot rom decides between the DMG and the CGB
+            let model = model.unwrap_or(if boot_rom.is_cgb() { Model::Cgb } else { Model::Dmg });
+            Cpu::new_with_boot_rom(cart, model, boot_rom)
+        }
+        (None, Some(model)) => Cpu::new_with_model(cart, model),
+        (None, None) => Cpu::new(cart),
+    };
+    let mut cpu = cpu.unwrap_or_else(|error| {
+        eprintln!("Couldn't load the rom: {}", error);
+        process::exit(1);
+    });
+    if args.iter().any(|arg| arg == "--fifo") {
+        cpu.set_renderer(Renderer::PixelFifo);
+    }
+    if let Some(wav_path) = get_option(&args, "--wav") {
+        match WavSink::create(wav_path, 48000, SampleFormat::I16) {
+            Ok(sink) => cpu.set_audio_sink(Box::new(sink)),
+            Err(error) => eprintln!("Couldn't create {}: {}", wav_path, error),
+        }
+    }
+    let frames = get_option(&args, "--frames").map(|frames| {
+        frames.parse::<u64>().unwrap_or_else(|_| {
+            eprintln!("Invalid frame count {}", frames);
+            process::exit(1);
+        })
+    });
+    match frames {
+        Some(frames) => {
+            cpu.run_frames(frames);
+        }
+        None => cpu.run(),
+    }
+
+    //Hands the buffered samples to the wav file and finishes it
+    cpu.flush_audio();
+    cpu.remove_audio_sink();
+}
+
+fn get_option<'a>(args: &'a [String], name: &str) -> Option<&'a String> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|position| args.get(position + 1))
 }
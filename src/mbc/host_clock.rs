@@ -0,0 +1,109 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/**
+ * Source the real time clock uses to advance
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RtcClock {
+    //Follows the emulated cycles, so it stops while the emulator is paused
+    Emulated,
+    //Follows the clock of the host machine
+    Host,
+}
+
+/**
+ * Returns the current unix time in seconds
+ */
+pub type TimeSource = Box<dyn Fn() -> u64>;
+
+/**
+ * Wall clock side of the cartridge clocks. It tracks when the counters were last brought up to date and
+ * hands out the time elapsed since then in whole units (seconds for MBC3, minutes for HuC3), the
+ * cartridge only decides how to advance its counters
+ */
+pub struct HostClock {
+    clock: RtcClock,
+    unit: u64,
+    last_sync: u64,
+    //Timestamp of a save loaded with the emulated clock, kept for a later switch to the host clock
+    saved_time: Option<u64>,
+    time_source: TimeSource,
+}
+
+impl HostClock {
+    pub fn new(unit: u64) -> Self {
+        HostClock {
+            clock: RtcClock::Emulated,
+            unit,
+            last_sync: get_host_time(),
+            saved_time: None,
+            time_source: Box::new(get_host_time),
+        }
+    }
+
+    pub fn is_emulated(&self) -> bool {
+        self.clock == RtcClock::Emulated
+    }
+
+    /**
+     * Units elapsed since the last sync, always 0 with the emulated clock. Leftover seconds are kept
+     * for the next sync
+     */
+    pub fn sync(&mut self) -> u64 {
+        if self.clock != RtcClock::Host {
+            return 0;
+        }
+        let elapsed = (self.time_source)().saturating_sub(self.last_sync) / self.unit;
+        self.last_sync += elapsed * self.unit;
+        elapsed
+    }
+
+    /**
+     * Switching to the host clock right after loading a save adds the time elapsed since it was written.
+     * Returns the units to advance, both the ones left in the previous clock and the offline ones
+     */
+    pub fn set_clock(&mut self, clock: RtcClock) -> u64 {
+        let elapsed = self.sync();
+        self.clock = clock;
+        self.last_sync = self.saved_time.take().unwrap_or_else(|| (self.time_source)());
+        elapsed + self.sync()
+    }
+
+    /**
+     * Timestamp to store along the counters. The host clock only updates them when they are accessed,
+     * so they hold the time of the last sync
+     */
+    pub fn get_save_time(&self) -> u64 {
+        match self.clock {
+            RtcClock::Emulated => (self.time_source)(),
+            RtcClock::Host => self.last_sync,
+        }
+    }
+
+    /**
+     * Restores the timestamp of a save, returns the units elapsed since it was written when the host
+     * clock is already running
+     */
+    pub fn load(&mut self, saved_time: u64) -> u64 {
+        if self.clock == RtcClock::Host {
+            self.last_sync = saved_time;
+            self.sync()
+        } else {
+            self.saved_time = Some(saved_time);
+            0
+        }
+    }
+
+    #[cfg(test)]
+    pub fn set_time_source(&mut self, time_source: TimeSource) {
+        self.last_sync = time_source();
+        self.time_source = time_source;
+    }
+}
+
+fn get_host_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0)
+}
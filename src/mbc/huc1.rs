@@ -0,0 +1,86 @@
+use crate::memory_bank::MemoryBank;
+
+use super::{copy_ram, Mbc};
+
+/**
+ * Hudson HuC1, MBC1 like banking where the RAM area can be switched to an infrared port
+ */
+pub struct HuC1 {
+    rom: MemoryBank,
+    ram: MemoryBank,
+    ir_mode: bool,
+    ir_led: bool,
+    rom_bank: u8,
+    ram_bank: u8,
+}
+
+impl HuC1 {
+    pub fn new(content: Vec<u8>, rom_banks: usize, ram_banks: usize) -> Self {
+        HuC1 {
+            rom: MemoryBank::new_from_bytes(16 * 1024, rom_banks, 0, content),
+            ram: MemoryBank::new(8 * 1024, ram_banks, 0),
+            ir_mode: false,
+            ir_led: false,
+            rom_bank: 1,
+            ram_bank: 0,
+        }
+    }
+
+    pub fn is_ir_led_on(&self) -> bool {
+        self.ir_led
+    }
+
+    fn get_ram_bank(&self) -> usize {
+        (self.ram_bank as usize) % self.ram.get_bank_ammount().max(1)
+    }
+}
+
+impl Mbc for HuC1 {
+    fn read_rom(&self, address: u16) -> Option<u8> {
+        match address {
+            0x0000..=0x3FFF => self.rom.read_from_bank(0, address),
+            _ => {
+                let bank = (self.rom_bank as usize) % self.rom.get_bank_ammount();
+                self.rom.read_from_bank(bank, address - 0x4000)
+            }
+        }
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ir_mode = value & 0x0F == 0x0E,
+            0x2000..=0x3FFF => self.rom_bank = (value & 0x3F).max(1),
+            0x4000..=0x5FFF => self.ram_bank = value & 0x03,
+            _ => {}
+        }
+    }
+
+    fn read_ram(&self, address: u16) -> Option<u8> {
+        if self.ir_mode {
+            //No other device is ever in range, so no light is received
+            return Some(0xC0);
+        }
+        Some(
+            self.ram
+                .read_from_bank(self.get_ram_bank(), address)
+                .unwrap_or(0xFF),
+        )
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        if self.ir_mode {
+            self.ir_led = value & 0x01 != 0;
+            return;
+        }
+        let bank = self.get_ram_bank();
+        self.ram.write_to_bank(bank, address, value);
+    }
+
+    fn get_save_data(&self) -> Vec<u8> {
+        self.ram.get_bytes().to_vec()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        copy_ram(self.ram.get_bytes_mut(), data);
+    }
+}
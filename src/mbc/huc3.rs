@@ -0,0 +1,205 @@
+use byteorder::{ByteOrder, LittleEndian};
+
+use crate::memory_bank::MemoryBank;
+
+use super::{copy_ram, host_clock::HostClock, Mbc, RtcClock};
+
+const CYCLES_PER_MINUTE: u64 = 4_194_304 * 60;
+const MINUTES_PER_DAY: u16 = 1440;
+const SAVE_TRAILER_SIZE: usize = 12;
+
+/**
+ * Hudson HuC3, banked ROM and RAM plus an infrared port and a real time clock that counts minutes and days.
+ * The clock is driven through nibble sized commands written while the RAM area is in command mode
+ */
+pub struct HuC3 {
+    rom: MemoryBank,
+    ram: MemoryBank,
+    mode: u8,
+    rom_bank: u8,
+    ram_bank: u8,
+    ir_led: bool,
+    rtc_memory: [u8; 256],
+    rtc_address: u8,
+    rtc_response: u8,
+    minutes: u16,
+    days: u16,
+    host_clock: HostClock,
+    cycle_accumulator: u64,
+}
+
+impl HuC3 {
+    pub fn new(content: Vec<u8>, rom_banks: usize, ram_banks: usize) -> Self {
+        HuC3 {
+            rom: MemoryBank::new_from_bytes(16 * 1024, rom_banks, 0, content),
+            ram: MemoryBank::new(8 * 1024, ram_banks, 0),
+            mode: 0,
+            rom_bank: 1,
+            ram_bank: 0,
+            ir_led: false,
+            rtc_memory: [0; 256],
+            rtc_address: 0,
+            rtc_response: 0,
+            minutes: 0,
+            days: 0,
+            host_clock: HostClock::new(60),
+            cycle_accumulator: 0,
+        }
+    }
+
+    pub fn is_ir_led_on(&self) -> bool {
+        self.ir_led
+    }
+
+    fn sync_host_time(&mut self) {
+        let minutes = self.host_clock.sync();
+        self.advance(minutes);
+    }
+
+    fn advance(&mut self, minutes: u64) {
+        let total = self.minutes as u64 + minutes;
+        self.minutes = (total % MINUTES_PER_DAY as u64) as u16;
+        self.days = ((self.days as u64 + total / MINUTES_PER_DAY as u64) & 0xFFF) as u16;
+    }
+
+    fn run_rtc_command(&mut self, value: u8) {
+        let argument = value & 0x0F;
+        match (value >> 4) & 0x07 {
+            0x1 => {
+                self.rtc_response = self.rtc_memory[self.rtc_address as usize];
+                self.rtc_address = self.rtc_address.wrapping_add(1);
+            }
+            0x3 => {
+                self.rtc_memory[self.rtc_address as usize] = argument;
+                self.rtc_address = self.rtc_address.wrapping_add(1);
+            }
+            0x4 => self.rtc_address = (self.rtc_address & 0xF0) | argument,
+            0x5 => self.rtc_address = (self.rtc_address & 0x0F) | argument << 4,
+            0x6 => match argument {
+                0x0 => {
+                    //Copies the clock into memory as two 12 bit values, minutes then days
+                    self.sync_host_time();
+                    for nibble in 0..3 {
+                        self.rtc_memory[nibble] = ((self.minutes >> (nibble * 4)) & 0x0F) as u8;
+                        self.rtc_memory[nibble + 3] = ((self.days >> (nibble * 4)) & 0x0F) as u8;
+                    }
+                }
+                0x1 => {
+                    self.sync_host_time();
+                    let mut minutes = 0;
+                    let mut days = 0;
+                    for nibble in 0..3 {
+                        minutes |= (self.rtc_memory[nibble] as u16) << (nibble * 4);
+                        days |= (self.rtc_memory[nibble + 3] as u16) << (nibble * 4);
+                    }
+                    self.minutes = minutes % MINUTES_PER_DAY;
+                    self.days = days;
+                    self.cycle_accumulator = 0;
+                }
+                0x2 => self.rtc_response = 0x01,
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    fn get_ram_bank(&self) -> usize {
+        (self.ram_bank as usize) % self.ram.get_bank_ammount().max(1)
+    }
+}
+
+impl Mbc for HuC3 {
+    fn read_rom(&self, address: u16) -> Option<u8> {
+        match address {
+            0x0000..=0x3FFF => self.rom.read_from_bank(0, address),
+            _ => {
+                let bank = (self.rom_bank as usize) % self.rom.get_bank_ammount();
+                self.rom.read_from_bank(bank, address - 0x4000)
+            }
+        }
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.mode = value & 0x0F,
+            0x2000..=0x3FFF => self.rom_bank = (value & 0x7F).max(1),
+            0x4000..=0x5FFF => self.ram_bank = value & 0x0F,
+            _ => {}
+        }
+    }
+
+    fn read_ram(&self, address: u16) -> Option<u8> {
+        let value = match self.mode {
+            0x0 | 0xA => self
+                .ram
+                .read_from_bank(self.get_ram_bank(), address)
+                .unwrap_or(0xFF),
+            0xC => 0x80 | self.rtc_response,
+            //The clock is always ready to take a new command
+            0xD => 0x01,
+            0xE => 0xC0,
+            _ => 0xFF,
+        };
+        Some(value)
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        match self.mode {
+            0xA => {
+                let bank = self.get_ram_bank();
+                self.ram.write_to_bank(bank, address, value);
+            }
+            0xB => self.run_rtc_command(value),
+            0xE => self.ir_led = value & 0x01 != 0,
+            _ => {}
+        }
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        if !self.host_clock.is_emulated() {
+            return;
+        }
+        self.cycle_accumulator += cycles;
+        let minutes = self.cycle_accumulator / CYCLES_PER_MINUTE;
+        self.cycle_accumulator %= CYCLES_PER_MINUTE;
+        self.advance(minutes);
+    }
+
+    fn set_rtc_clock(&mut self, clock: RtcClock) {
+        let minutes = self.host_clock.set_clock(clock);
+        self.advance(minutes);
+    }
+
+    /**
+     * The RAM is followed by a 12 byte trailer: a u64 unix timestamp, then the minutes and days as u16
+     */
+    fn get_save_data(&self) -> Vec<u8> {
+        let mut data = self.ram.get_bytes().to_vec();
+        let mut trailer = [0; SAVE_TRAILER_SIZE];
+        LittleEndian::write_u64(&mut trailer[0..8], self.host_clock.get_save_time());
+        LittleEndian::write_u16(&mut trailer[8..10], self.minutes);
+        LittleEndian::write_u16(&mut trailer[10..12], self.days);
+        data.extend_from_slice(&trailer);
+        data
+    }
+
+    fn get_save_contents(&self) -> Vec<u8> {
+        let mut data = self.get_save_data();
+        let trailer = data.len() - SAVE_TRAILER_SIZE;
+        data[trailer..trailer + 8].fill(0);
+        data
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        let ram_size = copy_ram(self.ram.get_bytes_mut(), data);
+
+        let trailer = &data[ram_size..];
+        if trailer.len() < SAVE_TRAILER_SIZE {
+            return;
+        }
+        self.minutes = LittleEndian::read_u16(&trailer[8..10]) % MINUTES_PER_DAY;
+        self.days = LittleEndian::read_u16(&trailer[10..12]) & 0xFFF;
+        let minutes = self.host_clock.load(LittleEndian::read_u64(&trailer[0..8]));
+        self.advance(minutes);
+    }
+}
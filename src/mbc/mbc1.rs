@@ -0,0 +1,127 @@
+use crate::memory_bank::MemoryBank;
+
+use super::{copy_ram, Mbc};
+
+/**
+ * MBC1, up to 2 MiB of ROM and 32 KiB of RAM. The MBC1M multicart variant wires only
+ * 4 bits of the ROM bank register, so the secondary register selects one of four 256 KiB games
+ */
+pub struct Mbc1 {
+    rom: MemoryBank,
+    ram: MemoryBank,
+    ram_enabled: bool,
+    rom_bank: u8,
+    secondary_bank: u8,
+    advanced_banking: bool,
+    multicart: bool,
+}
+
+impl Mbc1 {
+    pub fn new(content: Vec<u8>, rom_banks: usize, ram_banks: usize) -> Self {
+        let multicart = Self::is_multicart(&content);
+        Mbc1 {
+            rom: MemoryBank::new_from_bytes(16 * 1024, rom_banks, 0, content),
+            ram: MemoryBank::new(8 * 1024, ram_banks, 0),
+            ram_enabled: false,
+            rom_bank: 1,
+            secondary_bank: 0,
+            advanced_banking: false,
+            multicart,
+        }
+    }
+
+    /**
+     * MBC1M carts are 1 MiB and carry a second copy of the Nintendo logo in the header of the game at bank 0x10
+     */
+    fn is_multicart(content: &[u8]) -> bool {
+        const LOGO_START: usize = 0x104;
+        const LOGO_END: usize = 0x134;
+        const SECOND_GAME: usize = 0x10 * 16 * 1024;
+
+        content.len() == 1024 * 1024
+            && content[LOGO_START..LOGO_END]
+                == content[SECOND_GAME + LOGO_START..SECOND_GAME + LOGO_END]
+    }
+
+    fn get_secondary_shift(&self) -> u8 {
+        if self.multicart {
+            4
+        } else {
+            5
+        }
+    }
+
+    fn get_low_rom_bank(&self) -> usize {
+        if self.advanced_banking {
+            ((self.secondary_bank << self.get_secondary_shift()) as usize) % self.rom.get_bank_ammount()
+        } else {
+            0
+        }
+    }
+
+    fn get_high_rom_bank(&self) -> usize {
+        let rom_bank = if self.multicart {
+            self.rom_bank & 0x0F
+        } else {
+            self.rom_bank
+        };
+        let bank = (self.secondary_bank << self.get_secondary_shift()) | rom_bank;
+        (bank as usize) % self.rom.get_bank_ammount()
+    }
+
+    fn get_ram_bank(&self) -> usize {
+        if self.advanced_banking && self.ram.get_bank_ammount() > 1 {
+            self.secondary_bank as usize % self.ram.get_bank_ammount()
+        } else {
+            0
+        }
+    }
+}
+
+impl Mbc for Mbc1 {
+    fn read_rom(&self, address: u16) -> Option<u8> {
+        match address {
+            0x0000..=0x3FFF => self.rom.read_from_bank(self.get_low_rom_bank(), address),
+            _ => self
+                .rom
+                .read_from_bank(self.get_high_rom_bank(), address - 0x4000),
+        }
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => {
+                //Bank 0 can't be mapped in the switchable area, it's translated to 1
+                self.rom_bank = (value & 0x1F).max(1);
+            }
+            0x4000..=0x5FFF => self.secondary_bank = value & 0x03,
+            _ => self.advanced_banking = value & 0x01 != 0,
+        }
+    }
+
+    fn read_ram(&self, address: u16) -> Option<u8> {
+        if !self.ram_enabled {
+            return Some(0xFF);
+        }
+        Some(
+            self.ram
+                .read_from_bank(self.get_ram_bank(), address)
+                .unwrap_or(0xFF),
+        )
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        if self.ram_enabled {
+            self.ram.write_to_bank(self.get_ram_bank(), address, value);
+        }
+    }
+
+    fn get_save_data(&self) -> Vec<u8> {
+        self.ram.get_bytes().to_vec()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        copy_ram(self.ram.get_bytes_mut(), data);
+    }
+}
@@ -0,0 +1,71 @@
+use crate::memory_bank::MemoryBank;
+
+use super::Mbc;
+
+const RAM_SIZE: usize = 512;
+
+/**
+ * MBC2, up to 256 KiB of ROM and a built in RAM of 512 half bytes echoed through the whole RAM area
+ */
+pub struct Mbc2 {
+    rom: MemoryBank,
+    ram: [u8; RAM_SIZE],
+    ram_enabled: bool,
+    rom_bank: u8,
+}
+
+impl Mbc2 {
+    pub fn new(content: Vec<u8>, rom_banks: usize) -> Self {
+        Mbc2 {
+            rom: MemoryBank::new_from_bytes(16 * 1024, rom_banks, 0, content),
+            ram: [0x0F; RAM_SIZE],
+            ram_enabled: false,
+            rom_bank: 1,
+        }
+    }
+}
+
+impl Mbc for Mbc2 {
+    fn read_rom(&self, address: u16) -> Option<u8> {
+        match address {
+            0x0000..=0x3FFF => self.rom.read_from_bank(0, address),
+            _ => {
+                let bank = (self.rom_bank as usize) % self.rom.get_bank_ammount();
+                self.rom.read_from_bank(bank, address - 0x4000)
+            }
+        }
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        //Bit 8 of the address selects between the RAM enable and the ROM bank registers
+        match address {
+            0x0000..=0x3FFF if address & 0x0100 == 0 => self.ram_enabled = value & 0x0F == 0x0A,
+            0x0000..=0x3FFF => self.rom_bank = (value & 0x0F).max(1),
+            _ => {}
+        }
+    }
+
+    fn read_ram(&self, address: u16) -> Option<u8> {
+        if !self.ram_enabled {
+            return Some(0xFF);
+        }
+        //Only the lower nibble exists, the upper one reads as 1
+        Some(self.ram[address as usize % RAM_SIZE] | 0xF0)
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        if self.ram_enabled {
+            self.ram[address as usize % RAM_SIZE] = value & 0x0F;
+        }
+    }
+
+    fn get_save_data(&self) -> Vec<u8> {
+        self.ram.to_vec()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        for (cell, value) in self.ram.iter_mut().zip(data) {
+            *cell = value & 0x0F;
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::memory_bank::MemoryBank;
+
+use super::{copy_ram, rtc::Rtc, Mbc, RtcClock};
+
+/**
+ * MBC3, up to 2 MiB of ROM, 32 KiB of RAM and an optional real time clock
+ * whose registers are mapped in the RAM area
+ */
+pub struct Mbc3 {
+    rom: MemoryBank,
+    ram: MemoryBank,
+    rtc: Option<Rtc>,
+    ram_enabled: bool,
+    rom_bank: u8,
+    ram_bank: u8,
+}
+
+impl Mbc3 {
+    pub fn new(content: Vec<u8>, rom_banks: usize, ram_banks: usize, has_rtc: bool) -> Self {
+        Mbc3 {
+            rom: MemoryBank::new_from_bytes(16 * 1024, rom_banks, 0, content),
+            ram: MemoryBank::new(8 * 1024, ram_banks, 0),
+            rtc: if has_rtc { Some(Rtc::new()) } else { None },
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn set_time_source(&mut self, time_source: super::host_clock::TimeSource) {
+        if let Some(rtc) = self.rtc.as_mut() {
+            rtc.set_time_source(time_source);
+        }
+    }
+
+    fn get_rtc_register(&self) -> Option<u8> {
+        match self.ram_bank {
+            0x08..=0x0C if self.rtc.is_some() => Some(self.ram_bank),
+            _ => None,
+        }
+    }
+
+    fn get_ram_bank(&self) -> usize {
+        (self.ram_bank as usize) % self.ram.get_bank_ammount().max(1)
+    }
+}
+
+impl Mbc for Mbc3 {
+    fn read_rom(&self, address: u16) -> Option<u8> {
+        match address {
+            0x0000..=0x3FFF => self.rom.read_from_bank(0, address),
+            _ => {
+                let bank = (self.rom_bank as usize) % self.rom.get_bank_ammount();
+                self.rom.read_from_bank(bank, address - 0x4000)
+            }
+        }
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = (value & 0x7F).max(1),
+            0x4000..=0x5FFF => self.ram_bank = value & 0x0F,
+            _ => {
+                if let Some(rtc) = self.rtc.as_mut() {
+                    rtc.write_latch(value);
+                }
+            }
+        }
+    }
+
+    fn read_ram(&self, address: u16) -> Option<u8> {
+        if !self.ram_enabled {
+            return Some(0xFF);
+        }
+        if let (Some(register), Some(rtc)) = (self.get_rtc_register(), self.rtc.as_ref()) {
+            return Some(rtc.read(register));
+        }
+        Some(
+            self.ram
+                .read_from_bank(self.get_ram_bank(), address)
+                .unwrap_or(0xFF),
+        )
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        if !self.ram_enabled {
+            return;
+        }
+        if let Some(register) = self.get_rtc_register() {
+            if let Some(rtc) = self.rtc.as_mut() {
+                rtc.write(register, value);
+            }
+            return;
+        }
+        let bank = self.get_ram_bank();
+        self.ram.write_to_bank(bank, address, value);
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        if let Some(rtc) = self.rtc.as_mut() {
+            rtc.tick(cycles);
+        }
+    }
+
+    fn set_rtc_clock(&mut self, clock: RtcClock) {
+        if let Some(rtc) = self.rtc.as_mut() {
+            rtc.set_clock(clock);
+        }
+    }
+
+    fn get_save_data(&self) -> Vec<u8> {
+        let mut data = self.ram.get_bytes().to_vec();
+        if let Some(rtc) = self.rtc.as_ref() {
+            data.extend(rtc.save());
+        }
+        data
+    }
+
+    fn get_save_contents(&self) -> Vec<u8> {
+        let mut data = self.get_save_data();
+        if self.rtc.is_some() {
+            //The trailer ends with the timestamp
+            data.truncate(data.len() - 8);
+        }
+        data
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        let ram_size = copy_ram(self.ram.get_bytes_mut(), data);
+        if let Some(rtc) = self.rtc.as_mut() {
+            if data.len() >= ram_size + Rtc::get_save_size() {
+                rtc.load(&data[ram_size..]);
+            }
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use crate::memory_bank::MemoryBank;
+
+use super::{copy_ram, Mbc, RumbleCallback};
+
+/**
+ * MBC5, up to 8 MiB of ROM through a 9 bit bank number and 128 KiB of RAM.
+ * Rumble cartridges drive the motor with bit 3 of the RAM bank register
+ */
+pub struct Mbc5 {
+    rom: MemoryBank,
+    ram: MemoryBank,
+    ram_enabled: bool,
+    rom_bank: u16,
+    ram_bank: u8,
+    has_rumble: bool,
+    rumble_active: bool,
+    rumble_callback: Option<RumbleCallback>,
+}
+
+impl Mbc5 {
+    pub fn new(content: Vec<u8>, rom_banks: usize, ram_banks: usize, has_rumble: bool) -> Self {
+        Mbc5 {
+            rom: MemoryBank::new_from_bytes(16 * 1024, rom_banks, 0, content),
+            ram: MemoryBank::new(8 * 1024, ram_banks, 0),
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            has_rumble,
+            rumble_active: false,
+            rumble_callback: None,
+        }
+    }
+
+    pub fn is_rumble_active(&self) -> bool {
+        self.rumble_active
+    }
+
+    fn set_rumble(&mut self, active: bool) {
+        if self.rumble_active == active {
+            return;
+        }
+        self.rumble_active = active;
+        if let Some(callback) = self.rumble_callback.as_mut() {
+            callback(active);
+        }
+    }
+
+    fn get_ram_bank(&self) -> usize {
+        (self.ram_bank as usize) % self.ram.get_bank_ammount().max(1)
+    }
+}
+
+impl Mbc for Mbc5 {
+    fn read_rom(&self, address: u16) -> Option<u8> {
+        match address {
+            0x0000..=0x3FFF => self.rom.read_from_bank(0, address),
+            _ => {
+                //Unlike MBC1 and MBC3, bank 0 can be mapped in the switchable area
+                let bank = (self.rom_bank as usize) % self.rom.get_bank_ammount();
+                self.rom.read_from_bank(bank, address - 0x4000)
+            }
+        }
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+            0x2000..=0x2FFF => self.rom_bank = (self.rom_bank & 0x100) | value as u16,
+            0x3000..=0x3FFF => self.rom_bank = (self.rom_bank & 0xFF) | ((value as u16 & 0x01) << 8),
+            0x4000..=0x5FFF => {
+                if self.has_rumble {
+                    self.ram_bank = value & 0x07;
+                    self.set_rumble(value & 0x08 != 0);
+                } else {
+                    self.ram_bank = value & 0x0F;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn read_ram(&self, address: u16) -> Option<u8> {
+        if !self.ram_enabled {
+            return Some(0xFF);
+        }
+        Some(
+            self.ram
+                .read_from_bank(self.get_ram_bank(), address)
+                .unwrap_or(0xFF),
+        )
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        if self.ram_enabled {
+            let bank = self.get_ram_bank();
+            self.ram.write_to_bank(bank, address, value);
+        }
+    }
+
+    fn set_rumble_callback(&mut self, callback: RumbleCallback) {
+        self.rumble_callback = Some(callback);
+    }
+
+    fn get_save_data(&self) -> Vec<u8> {
+        self.ram.get_bytes().to_vec()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        copy_ram(self.ram.get_bytes_mut(), data);
+    }
+}
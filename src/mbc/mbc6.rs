@@ -0,0 +1,173 @@
+use crate::memory_bank::MemoryBank;
+
+use super::{copy_ram, Mbc};
+
+const FLASH_BANKS: usize = 128;
+const BANK_SIZE: usize = 8 * 1024;
+const RAM_BANK_SIZE: usize = 4 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FlashState {
+    Idle,
+    Unlocked,
+    Command,
+    Program,
+    EraseUnlock,
+    EraseUnlocked,
+    EraseCommand,
+}
+
+/**
+ * MBC6, splits both the ROM and RAM areas into two independently banked halves.
+ * Each ROM half can map either the ROM or the 1 MiB flash chip
+ */
+pub struct Mbc6 {
+    rom: MemoryBank,
+    flash: MemoryBank,
+    ram: MemoryBank,
+    ram_enabled: bool,
+    flash_enabled: bool,
+    flash_write_enabled: bool,
+    flash_state: FlashState,
+    rom_banks: [u8; 2],
+    flash_selected: [bool; 2],
+    ram_banks: [u8; 2],
+}
+
+impl Mbc6 {
+    pub fn new(content: Vec<u8>, rom_banks: usize, ram_banks: usize) -> Self {
+        let mut flash = MemoryBank::new(BANK_SIZE, FLASH_BANKS, 0);
+        flash.get_bytes_mut().fill(0xFF);
+
+        Mbc6 {
+            rom: MemoryBank::new_from_bytes(BANK_SIZE, rom_banks * 2, 0, content),
+            flash,
+            ram: MemoryBank::new(RAM_BANK_SIZE, ram_banks * 2, 0),
+            ram_enabled: false,
+            flash_enabled: false,
+            flash_write_enabled: false,
+            flash_state: FlashState::Idle,
+            rom_banks: [0, 0],
+            flash_selected: [false, false],
+            ram_banks: [0, 0],
+        }
+    }
+
+    fn get_window(address: u16) -> usize {
+        (address as usize >> 13) & 0x01
+    }
+
+    /**
+     * Flash commands follow the usual unlock sequence (0xAA, 0x55) before the command byte
+     */
+    fn write_flash(&mut self, bank: usize, address: u16, value: u8) {
+        if value == 0xF0 {
+            self.flash_state = FlashState::Idle;
+            return;
+        }
+
+        self.flash_state = match (self.flash_state, value) {
+            (FlashState::Idle, 0xAA) => FlashState::Unlocked,
+            (FlashState::Unlocked, 0x55) => FlashState::Command,
+            (FlashState::Command, 0xA0) => FlashState::Program,
+            (FlashState::Command, 0x80) => FlashState::EraseUnlock,
+            (FlashState::Program, _) => {
+                //Programming can only clear bits, erasing sets them back
+                let current = self.flash.read_from_bank(bank, address).unwrap_or(0xFF);
+                self.flash.write_to_bank(bank, address, current & value);
+                FlashState::Idle
+            }
+            (FlashState::EraseUnlock, 0xAA) => FlashState::EraseUnlocked,
+            (FlashState::EraseUnlocked, 0x55) => FlashState::EraseCommand,
+            (FlashState::EraseCommand, 0x30) => {
+                let start = bank * BANK_SIZE;
+                self.flash.get_bytes_mut()[start..start + BANK_SIZE].fill(0xFF);
+                FlashState::Idle
+            }
+            (FlashState::EraseCommand, 0x10) => {
+                self.flash.get_bytes_mut().fill(0xFF);
+                FlashState::Idle
+            }
+            _ => FlashState::Idle,
+        };
+    }
+}
+
+impl Mbc for Mbc6 {
+    fn read_rom(&self, address: u16) -> Option<u8> {
+        match address {
+            0x0000..=0x3FFF => self.rom.read_bankless(address),
+            _ => {
+                let window = Self::get_window(address - 0x4000);
+                let offset = address & 0x1FFF;
+                if self.flash_selected[window] {
+                    if !self.flash_enabled {
+                        return Some(0xFF);
+                    }
+                    let bank = self.rom_banks[window] as usize % FLASH_BANKS;
+                    self.flash.read_from_bank(bank, offset)
+                } else {
+                    let bank = self.rom_banks[window] as usize % self.rom.get_bank_ammount();
+                    self.rom.read_from_bank(bank, offset)
+                }
+            }
+        }
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x03FF => self.ram_enabled = value & 0x0F == 0x0A,
+            0x0400..=0x07FF => self.ram_banks[0] = value & 0x07,
+            0x0800..=0x0BFF => self.ram_banks[1] = value & 0x07,
+            0x0C00..=0x0FFF => self.flash_enabled = value & 0x01 != 0,
+            0x1000 => self.flash_write_enabled = value & 0x01 != 0,
+            0x2000..=0x27FF => self.rom_banks[0] = value & 0x7F,
+            0x2800..=0x2FFF => self.flash_selected[0] = value == 0x08,
+            0x3000..=0x37FF => self.rom_banks[1] = value & 0x7F,
+            0x3800..=0x3FFF => self.flash_selected[1] = value == 0x08,
+            0x4000..=0x7FFF => {
+                let window = Self::get_window(address - 0x4000);
+                if self.flash_selected[window] && self.flash_enabled && self.flash_write_enabled {
+                    let bank = self.rom_banks[window] as usize % FLASH_BANKS;
+                    self.write_flash(bank, address & 0x1FFF, value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn read_ram(&self, address: u16) -> Option<u8> {
+        if !self.ram_enabled {
+            return Some(0xFF);
+        }
+        let window = (address as usize >> 12) & 0x01;
+        let bank = self.ram_banks[window] as usize % self.ram.get_bank_ammount().max(1);
+        Some(
+            self.ram
+                .read_from_bank(bank, address & 0x0FFF)
+                .unwrap_or(0xFF),
+        )
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        if !self.ram_enabled {
+            return;
+        }
+        let window = (address as usize >> 12) & 0x01;
+        let bank = self.ram_banks[window] as usize % self.ram.get_bank_ammount().max(1);
+        self.ram.write_to_bank(bank, address & 0x0FFF, value);
+    }
+
+    fn get_save_data(&self) -> Vec<u8> {
+        let mut data = self.ram.get_bytes().to_vec();
+        data.extend_from_slice(self.flash.get_bytes());
+        data
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        let ram_size = copy_ram(self.ram.get_bytes_mut(), data);
+
+        let flash_data = &data[ram_size..];
+        copy_ram(self.flash.get_bytes_mut(), flash_data);
+    }
+}
@@ -0,0 +1,266 @@
+use byteorder::{ByteOrder, LittleEndian};
+
+use crate::memory_bank::MemoryBank;
+
+use super::Mbc;
+
+const EEPROM_WORDS: usize = 128;
+const ACCELEROMETER_CENTER: f32 = 0x81D0 as f32;
+const ACCELEROMETER_ONE_G: f32 = 0x70 as f32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EepromState {
+    Idle,
+    Command,
+    Read(u16, u8),
+    Write(Option<u8>),
+}
+
+/**
+ * 93LC56 serial EEPROM organized as 128 words of 16 bits. Commands are a start bit, a 2 bit opcode
+ * and an 8 bit address shifted in on the rising edges of the clock while chip select is high
+ */
+struct Eeprom {
+    words: [u16; EEPROM_WORDS],
+    chip_select: bool,
+    clock: bool,
+    data_in: bool,
+    data_out: bool,
+    state: EepromState,
+    shift: u32,
+    bits: u8,
+    write_enabled: bool,
+}
+
+impl Eeprom {
+    fn new() -> Self {
+        Eeprom {
+            words: [0xFFFF; EEPROM_WORDS],
+            chip_select: false,
+            clock: false,
+            data_in: false,
+            data_out: true,
+            state: EepromState::Idle,
+            shift: 0,
+            bits: 0,
+            write_enabled: false,
+        }
+    }
+
+    fn read(&self) -> u8 {
+        (self.chip_select as u8) << 7
+            | (self.clock as u8) << 6
+            | (self.data_in as u8) << 1
+            | self.data_out as u8
+    }
+
+    fn write(&mut self, value: u8) {
+        let chip_select = value & 0x80 != 0;
+        let clock = value & 0x40 != 0;
+        self.data_in = value & 0x02 != 0;
+
+        if !chip_select {
+            self.state = EepromState::Idle;
+        } else if clock && !self.clock {
+            self.clock_rising_edge();
+        }
+
+        self.chip_select = chip_select;
+        self.clock = clock;
+    }
+
+    fn shift_in(&mut self) {
+        self.shift = (self.shift << 1) | self.data_in as u32;
+        self.bits += 1;
+    }
+
+    fn clock_rising_edge(&mut self) {
+        match self.state {
+            EepromState::Idle => {
+                if self.data_in {
+                    self.state = EepromState::Command;
+                    self.shift = 0;
+                    self.bits = 0;
+                }
+            }
+            EepromState::Command => {
+                self.shift_in();
+                if self.bits == 10 {
+                    self.run_command();
+                }
+            }
+            EepromState::Read(value, remaining) => {
+                self.data_out = value & (1 << (remaining - 1)) != 0;
+                self.state = if remaining == 1 {
+                    EepromState::Idle
+                } else {
+                    EepromState::Read(value, remaining - 1)
+                };
+            }
+            EepromState::Write(address) => {
+                self.shift_in();
+                if self.bits == 16 {
+                    let value = self.shift as u16;
+                    if self.write_enabled {
+                        match address {
+                            Some(address) => self.words[address as usize] = value,
+                            None => self.words.fill(value),
+                        }
+                    }
+                    //Ready status
+                    self.data_out = true;
+                    self.state = EepromState::Idle;
+                }
+            }
+        }
+    }
+
+    fn run_command(&mut self) {
+        let opcode = (self.shift >> 8) & 0b11;
+        let address = (self.shift & 0x7F) as u8;
+        self.state = EepromState::Idle;
+
+        match opcode {
+            0b10 => {
+                //A dummy zero precedes the data
+                self.data_out = false;
+                self.state = EepromState::Read(self.words[address as usize], 16);
+            }
+            0b01 => self.begin_write(Some(address)),
+            0b11 => {
+                if self.write_enabled {
+                    self.words[address as usize] = 0xFFFF;
+                }
+                self.data_out = true;
+            }
+            _ => match (self.shift >> 6) & 0b11 {
+                0b00 => self.write_enabled = false,
+                0b11 => self.write_enabled = true,
+                0b10 => {
+                    if self.write_enabled {
+                        self.words.fill(0xFFFF);
+                    }
+                    self.data_out = true;
+                }
+                _ => self.begin_write(None),
+            },
+        }
+    }
+
+    fn begin_write(&mut self, address: Option<u8>) {
+        self.shift = 0;
+        self.bits = 0;
+        self.state = EepromState::Write(address);
+    }
+}
+
+/**
+ * MBC7, pairs the ROM banking with a two axis accelerometer and a serial EEPROM in place of RAM
+ */
+pub struct Mbc7 {
+    rom: MemoryBank,
+    eeprom: Eeprom,
+    ram_enabled: bool,
+    ram_unlocked: bool,
+    rom_bank: u8,
+    acceleration: (f32, f32),
+    latched: (u16, u16),
+    latch_erased: bool,
+}
+
+impl Mbc7 {
+    pub fn new(content: Vec<u8>, rom_banks: usize) -> Self {
+        Mbc7 {
+            rom: MemoryBank::new_from_bytes(16 * 1024, rom_banks, 0, content),
+            eeprom: Eeprom::new(),
+            ram_enabled: false,
+            ram_unlocked: false,
+            rom_bank: 1,
+            acceleration: (0.0, 0.0),
+            latched: (0x8000, 0x8000),
+            latch_erased: false,
+        }
+    }
+
+    fn to_sensor_value(acceleration: f32) -> u16 {
+        (ACCELEROMETER_CENTER + acceleration * ACCELEROMETER_ONE_G).clamp(0.0, u16::MAX as f32) as u16
+    }
+
+    fn is_ram_accessible(&self, address: u16) -> bool {
+        self.ram_enabled && self.ram_unlocked && address < 0x1000
+    }
+}
+
+impl Mbc for Mbc7 {
+    fn read_rom(&self, address: u16) -> Option<u8> {
+        match address {
+            0x0000..=0x3FFF => self.rom.read_from_bank(0, address),
+            _ => {
+                let bank = (self.rom_bank as usize) % self.rom.get_bank_ammount();
+                self.rom.read_from_bank(bank, address - 0x4000)
+            }
+        }
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = value == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = value & 0x7F,
+            0x4000..=0x5FFF => self.ram_unlocked = value == 0x40,
+            _ => {}
+        }
+    }
+
+    fn read_ram(&self, address: u16) -> Option<u8> {
+        if !self.is_ram_accessible(address) {
+            return Some(0xFF);
+        }
+        let value = match (address >> 4) & 0x0F {
+            0x2 => self.latched.0 as u8,
+            0x3 => (self.latched.0 >> 8) as u8,
+            0x4 => self.latched.1 as u8,
+            0x5 => (self.latched.1 >> 8) as u8,
+            0x6 => 0x00,
+            0x8 => self.eeprom.read(),
+            _ => 0xFF,
+        };
+        Some(value)
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        if !self.is_ram_accessible(address) {
+            return;
+        }
+        match (address >> 4) & 0x0F {
+            0x0 if value == 0x55 => {
+                self.latched = (0x8000, 0x8000);
+                self.latch_erased = true;
+            }
+            0x1 if value == 0xAA && self.latch_erased => {
+                self.latched = (
+                    Self::to_sensor_value(self.acceleration.0),
+                    Self::to_sensor_value(self.acceleration.1),
+                );
+                self.latch_erased = false;
+            }
+            0x8 => self.eeprom.write(value),
+            _ => {}
+        }
+    }
+
+    fn set_accelerometer(&mut self, x: f32, y: f32) {
+        self.acceleration = (x, y);
+    }
+
+    fn get_save_data(&self) -> Vec<u8> {
+        let mut data = vec![0; EEPROM_WORDS * 2];
+        LittleEndian::write_u16_into(&self.eeprom.words, &mut data);
+        data
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        if data.len() >= EEPROM_WORDS * 2 {
+            LittleEndian::read_u16_into(&data[..EEPROM_WORDS * 2], &mut self.eeprom.words);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use crate::memory_bank::MemoryBank;
+
+use super::{copy_ram, Mbc};
+
+/**
+ * MMM01 multicart controller. It boots with the last 32 KiB of the ROM mapped so the menu can run,
+ * the menu then selects the base bank of a game and locks the configuration, after which
+ * the controller behaves like an MBC1 restricted to that game
+ */
+pub struct Mmm01 {
+    rom: MemoryBank,
+    ram: MemoryBank,
+    mapped: bool,
+    ram_enabled: bool,
+    rom_bank_low: u8,
+    rom_bank_mid: u8,
+    rom_bank_high: u8,
+    rom_bank_mask: u8,
+    ram_bank_low: u8,
+    ram_bank_high: u8,
+    advanced_banking: bool,
+}
+
+impl Mmm01 {
+    pub fn new(content: Vec<u8>, rom_banks: usize, ram_banks: usize) -> Self {
+        Mmm01 {
+            rom: MemoryBank::new_from_bytes(16 * 1024, rom_banks, 0, content),
+            ram: MemoryBank::new(8 * 1024, ram_banks, 0),
+            mapped: false,
+            ram_enabled: false,
+            rom_bank_low: 0,
+            rom_bank_mid: 0,
+            rom_bank_high: 0,
+            rom_bank_mask: 0,
+            ram_bank_low: 0,
+            ram_bank_high: 0,
+            advanced_banking: false,
+        }
+    }
+
+    /**
+     * Bits of the 5 bit bank register that stay under control of the game once mapped
+     */
+    fn get_game_bits(&self) -> u8 {
+        !(self.rom_bank_mask << 1) & 0x1F
+    }
+
+    fn get_base_bank(&self) -> usize {
+        (self.rom_bank_high as usize) << 7 | (self.rom_bank_mid as usize) << 5
+    }
+
+    fn get_low_rom_bank(&self) -> usize {
+        let bank = if self.mapped {
+            self.get_base_bank() | (self.rom_bank_low & !self.get_game_bits()) as usize
+        } else {
+            //The menu lives at the end of the ROM
+            0x1FE
+        };
+        bank % self.rom.get_bank_ammount()
+    }
+
+    fn get_high_rom_bank(&self) -> usize {
+        let bank = if self.mapped {
+            let mut low = self.rom_bank_low;
+            if low & self.get_game_bits() == 0 {
+                low |= 1;
+            }
+            self.get_base_bank() | low as usize
+        } else {
+            0x1FF
+        };
+        bank % self.rom.get_bank_ammount()
+    }
+
+    fn get_ram_bank(&self) -> usize {
+        let bank = (self.ram_bank_high << 2) | if self.advanced_banking || !self.mapped {
+            self.ram_bank_low
+        } else {
+            0
+        };
+        bank as usize % self.ram.get_bank_ammount().max(1)
+    }
+}
+
+impl Mbc for Mmm01 {
+    fn read_rom(&self, address: u16) -> Option<u8> {
+        match address {
+            0x0000..=0x3FFF => self.rom.read_from_bank(self.get_low_rom_bank(), address),
+            _ => self
+                .rom
+                .read_from_bank(self.get_high_rom_bank(), address - 0x4000),
+        }
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => {
+                self.ram_enabled = value & 0x0F == 0x0A;
+                if !self.mapped {
+                    self.ram_bank_high = (value >> 4) & 0x03;
+                    self.mapped = value & 0x40 != 0;
+                }
+            }
+            0x2000..=0x3FFF => {
+                let game_bits = if self.mapped { self.get_game_bits() } else { 0x1F };
+                self.rom_bank_low = (self.rom_bank_low & !game_bits) | (value & game_bits);
+                if !self.mapped {
+                    self.rom_bank_mid = (value >> 5) & 0x03;
+                }
+            }
+            0x4000..=0x5FFF => {
+                self.ram_bank_low = value & 0x03;
+                if !self.mapped {
+                    self.ram_bank_high = (value >> 2) & 0x03;
+                    self.rom_bank_high = (value >> 4) & 0x03;
+                }
+            }
+            _ => {
+                self.advanced_banking = value & 0x01 != 0;
+                if !self.mapped {
+                    self.rom_bank_mask = (value >> 2) & 0x0F;
+                }
+            }
+        }
+    }
+
+    fn read_ram(&self, address: u16) -> Option<u8> {
+        if !self.ram_enabled {
+            return Some(0xFF);
+        }
+        Some(
+            self.ram
+                .read_from_bank(self.get_ram_bank(), address)
+                .unwrap_or(0xFF),
+        )
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        if self.ram_enabled {
+            let bank = self.get_ram_bank();
+            self.ram.write_to_bank(bank, address, value);
+        }
+    }
+
+    fn get_save_data(&self) -> Vec<u8> {
+        self.ram.get_bytes().to_vec()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        copy_ram(self.ram.get_bytes_mut(), data);
+    }
+}
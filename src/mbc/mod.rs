@@ -0,0 +1,113 @@
+mod host_clock;
+mod huc1;
+mod huc3;
+mod mbc1;
+mod mbc2;
+mod mbc3;
+mod mbc5;
+mod mbc6;
+mod mbc7;
+mod mmm01;
+mod rom_only;
+mod rtc;
+mod tests;
+
+pub use huc1::HuC1;
+pub use huc3::HuC3;
+pub use mbc1::Mbc1;
+pub use mbc2::Mbc2;
+pub use mbc3::Mbc3;
+pub use mbc5::Mbc5;
+pub use mbc6::Mbc6;
+pub use mbc7::Mbc7;
+pub use mmm01::Mmm01;
+pub use rom_only::RomOnly;
+pub use host_clock::RtcClock;
+pub use rtc::Rtc;
+
+use crate::cartridge::{Cartidge, CartridgeError, Mapper};
+
+/**
+ * Called by rumble cartridges with the new motor state every time it changes
+ */
+pub type RumbleCallback = Box<dyn FnMut(bool)>;
+
+/**
+ * A memory bank controller owns the cartridge ROM and RAM and decides which banks are visible.
+ * ROM addresses are absolute (0x0000-0x7FFF) since writes to them drive the controller registers,
+ * RAM addresses are relative to 0xA000
+ */
+pub trait Mbc {
+    fn read_rom(&self, address: u16) -> Option<u8>;
+    fn write_rom(&mut self, address: u16, value: u8);
+    fn read_ram(&self, address: u16) -> Option<u8>;
+    fn write_ram(&mut self, address: u16, value: u8);
+
+    /**
+     * Advances the hardware inside the cartridge, such as a real time clock
+     */
+    fn tick(&mut self, _cycles: u64) {}
+
+    fn set_rtc_clock(&mut self, _clock: RtcClock) {}
+
+    fn set_rumble_callback(&mut self, _callback: RumbleCallback) {}
+
+    /**
+     * Feeds the accelerometer of MBC7 cartridges, in g along each axis
+     */
+    fn set_accelerometer(&mut self, _x: f32, _y: f32) {}
+
+    /**
+     * Contents kept alive by the cartridge battery, the RAM followed by any controller specific trailer
+     */
+    fn get_save_data(&self) -> Vec<u8> {
+        Vec::new()
+    }
+
+    /**
+     * Save data without the parts that change on their own, like the host timestamp of a clock trailer.
+     * Only a change in these contents is worth writing to disk
+     */
+    fn get_save_contents(&self) -> Vec<u8> {
+        self.get_save_data()
+    }
+
+    fn load_save_data(&mut self, _data: &[u8]) {}
+}
+
+/**
+ * Copies a save file into cartridge memory, as much as both sides have. Returns the amount of bytes
+ * copied so the controller can find the trailer that follows the RAM
+ */
+pub(crate) fn copy_ram(destination: &mut [u8], source: &[u8]) -> usize {
+    let size = destination.len().min(source.len());
+    destination[..size].copy_from_slice(&source[..size]);
+    size
+}
+
+/**
+ * Builds the controller matching the cartridge type byte (0x147) of the header
+ */
+pub fn new_mbc(cart: Cartidge) -> Result<Box<dyn Mbc>, CartridgeError> {
+    let header = cart.get_header()?;
+    let rom_banks = header.rom_banks;
+    let ram_banks = header.ram_banks;
+    let content = cart.content;
+
+    let mbc: Box<dyn Mbc> = match header.mapper {
+        Mapper::RomOnly => Box::new(RomOnly::new(content, ram_banks)),
+        Mapper::Mbc1 => Box::new(Mbc1::new(content, rom_banks, ram_banks)),
+        Mapper::Mbc2 => Box::new(Mbc2::new(content, rom_banks)),
+        Mapper::Mbc3 => Box::new(Mbc3::new(content, rom_banks, ram_banks, header.has_rtc())),
+        Mapper::Mbc5 => Box::new(Mbc5::new(content, rom_banks, ram_banks, header.has_rumble())),
+        Mapper::Mbc6 => Box::new(Mbc6::new(content, rom_banks, ram_banks)),
+        Mapper::Mbc7 => Box::new(Mbc7::new(content, rom_banks)),
+        Mapper::Mmm01 => Box::new(Mmm01::new(content, rom_banks, ram_banks)),
+        Mapper::HuC1 => Box::new(HuC1::new(content, rom_banks, ram_banks)),
+        Mapper::HuC3 => Box::new(HuC3::new(content, rom_banks, ram_banks)),
+        Mapper::PocketCamera | Mapper::BandaiTama5 => {
+            return Err(CartridgeError::UnsupportedMapper(header.mapper));
+        }
+    };
+    Ok(mbc)
+}
@@ -0,0 +1,46 @@
+use crate::memory_bank::MemoryBank;
+
+use super::{copy_ram, Mbc};
+
+/**
+ * Cartridges without controller, 32 KiB of ROM and optionally a single RAM bank
+ */
+pub struct RomOnly {
+    rom: MemoryBank,
+    ram: MemoryBank,
+}
+
+impl RomOnly {
+    pub fn new(content: Vec<u8>, ram_banks: usize) -> Self {
+        RomOnly {
+            rom: MemoryBank::new_from_bytes(16 * 1024, 2, 0, content),
+            ram: MemoryBank::new(8 * 1024, ram_banks.min(1), 0),
+        }
+    }
+}
+
+impl Mbc for RomOnly {
+    fn read_rom(&self, address: u16) -> Option<u8> {
+        self.rom.read_bankless(address)
+    }
+
+    fn write_rom(&mut self, _address: u16, _value: u8) {
+        //There are no registers to write to, ROM is read only
+    }
+
+    fn read_ram(&self, address: u16) -> Option<u8> {
+        Some(self.ram.read_from_bank(0, address).unwrap_or(0xFF))
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        self.ram.write_to_bank(0, address, value);
+    }
+
+    fn get_save_data(&self) -> Vec<u8> {
+        self.ram.get_bytes().to_vec()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        copy_ram(self.ram.get_bytes_mut(), data);
+    }
+}
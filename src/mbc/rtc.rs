@@ -0,0 +1,199 @@
+use byteorder::{ByteOrder, LittleEndian};
+
+use super::host_clock::{HostClock, RtcClock};
+
+const CYCLES_PER_SECOND: u64 = 4_194_304;
+const SAVE_TRAILER_SIZE: usize = 48;
+
+/**
+ * Real time clock found in MBC3 cartridges. Reads return the latched copy of the registers,
+ * writes go to the live counter
+ */
+pub struct Rtc {
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    days: u16,
+    halted: bool,
+    day_carry: bool,
+    latched: [u8; 5],
+    latch_armed: bool,
+    host_clock: HostClock,
+    cycle_accumulator: u64,
+}
+
+impl Rtc {
+    pub fn new() -> Self {
+        Rtc {
+            seconds: 0,
+            minutes: 0,
+            hours: 0,
+            days: 0,
+            halted: false,
+            day_carry: false,
+            latched: [0; 5],
+            latch_armed: false,
+            host_clock: HostClock::new(1),
+            cycle_accumulator: 0,
+        }
+    }
+
+    pub fn set_clock(&mut self, clock: RtcClock) {
+        let elapsed = self.host_clock.set_clock(clock);
+        self.advance_unless_halted(elapsed);
+    }
+
+    pub fn tick(&mut self, cycles: u64) {
+        if !self.host_clock.is_emulated() || self.halted {
+            return;
+        }
+        self.cycle_accumulator += cycles;
+        let seconds = self.cycle_accumulator / CYCLES_PER_SECOND;
+        self.cycle_accumulator %= CYCLES_PER_SECOND;
+        self.advance(seconds);
+    }
+
+    fn sync_host_time(&mut self) {
+        let elapsed = self.host_clock.sync();
+        self.advance_unless_halted(elapsed);
+    }
+
+    fn advance_unless_halted(&mut self, seconds: u64) {
+        if !self.halted {
+            self.advance(seconds);
+        }
+    }
+
+    fn advance(&mut self, seconds: u64) {
+        if seconds == 0 {
+            return;
+        }
+        let total = self.seconds as u64
+            + self.minutes as u64 * 60
+            + self.hours as u64 * 3600
+            + self.days as u64 * 86400
+            + seconds;
+
+        self.seconds = (total % 60) as u8;
+        self.minutes = (total / 60 % 60) as u8;
+        self.hours = (total / 3600 % 24) as u8;
+        let days = total / 86400;
+        if days > 0x1FF {
+            //The carry stays set until the game clears it
+            self.day_carry = true;
+        }
+        self.days = (days % 0x200) as u16;
+    }
+
+    fn get_day_high(&self) -> u8 {
+        ((self.days >> 8) as u8 & 0x01) | (self.halted as u8) << 6 | (self.day_carry as u8) << 7
+    }
+
+    /**
+     * Latching happens when 0x00 and then 0x01 are written to 0x6000-0x7FFF
+     */
+    pub fn write_latch(&mut self, value: u8) {
+        if self.latch_armed && value == 0x01 {
+            self.sync_host_time();
+            self.latched = [
+                self.seconds,
+                self.minutes,
+                self.hours,
+                self.days as u8,
+                self.get_day_high(),
+            ];
+        }
+        self.latch_armed = value == 0x00;
+    }
+
+    /**
+     * Reads one of the latched registers, selected with 0x08-0x0C
+     */
+    pub fn read(&self, register: u8) -> u8 {
+        match register {
+            0x08 => self.latched[0] & 0x3F,
+            0x09 => self.latched[1] & 0x3F,
+            0x0A => self.latched[2] & 0x1F,
+            0x0B => self.latched[3],
+            _ => self.latched[4] & 0xC1,
+        }
+    }
+
+    pub fn write(&mut self, register: u8, value: u8) {
+        self.sync_host_time();
+        match register {
+            0x08 => {
+                self.seconds = value & 0x3F;
+                self.cycle_accumulator = 0;
+            }
+            0x09 => self.minutes = value & 0x3F,
+            0x0A => self.hours = value & 0x1F,
+            0x0B => self.days = (self.days & 0x100) | value as u16,
+            _ => {
+                self.days = (self.days & 0xFF) | ((value as u16 & 0x01) << 8);
+                self.halted = value & 0x40 != 0;
+                self.day_carry = value & 0x80 != 0;
+            }
+        }
+    }
+
+    /**
+     * Serializes the clock in the 48 byte trailer appended to save files by other emulators:
+     * the live and latched registers as five u32 each, followed by a u64 unix timestamp
+     */
+    pub fn save(&self) -> Vec<u8> {
+        let live = [
+            self.seconds,
+            self.minutes,
+            self.hours,
+            self.days as u8,
+            self.get_day_high(),
+        ];
+
+        let mut data = vec![0; SAVE_TRAILER_SIZE];
+        for (index, value) in live.iter().chain(self.latched.iter()).enumerate() {
+            LittleEndian::write_u32(&mut data[index * 4..index * 4 + 4], *value as u32);
+        }
+        LittleEndian::write_u64(&mut data[40..48], self.host_clock.get_save_time());
+        data
+    }
+
+    /**
+     * Restores a trailer written by save. With the host clock the time elapsed since the save is added
+     */
+    pub fn load(&mut self, data: &[u8]) {
+        if data.len() < SAVE_TRAILER_SIZE {
+            return;
+        }
+
+        let registers: Vec<u8> = (0..10)
+            .map(|index| LittleEndian::read_u32(&data[index * 4..index * 4 + 4]) as u8)
+            .collect();
+
+        self.seconds = registers[0];
+        self.minutes = registers[1];
+        self.hours = registers[2];
+        self.days = registers[3] as u16 | ((registers[4] as u16 & 0x01) << 8);
+        self.halted = registers[4] & 0x40 != 0;
+        self.day_carry = registers[4] & 0x80 != 0;
+        self.latched.copy_from_slice(&registers[5..10]);
+
+        let elapsed = self.host_clock.load(LittleEndian::read_u64(&data[40..48]));
+        self.advance_unless_halted(elapsed);
+    }
+
+    pub fn get_save_size() -> usize {
+        SAVE_TRAILER_SIZE
+    }
+
+    #[cfg(test)]
+    pub fn set_time_source(&mut self, time_source: super::host_clock::TimeSource) {
+        self.host_clock.set_time_source(time_source);
+    }
+}
+
+impl Default for Rtc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,587 @@
+#![cfg(test)]
+#![allow(clippy::bool_assert_comparison)]
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use crate::{
+    cartridge::{Cartidge, CartridgeError, Mapper},
+    header::HeaderError,
+    save_file::SaveFile,
+};
+
+use super::{
+    host_clock::{HostClock, TimeSource},
+    HuC3, Mbc, Mbc1, Mbc2, Mbc3, Mbc5, Mbc6, Mbc7, Mmm01, Rtc, RtcClock,
+};
+
+/**
+ * Builds a rom where the first byte of every bank holds the bank number
+ */
+fn build_banked_rom(banks: usize) -> Vec<u8> {
+    let mut content = vec![0; banks * 16 * 1024];
+    for bank in 0..banks {
+        content[bank * 16 * 1024] = bank as u8;
+    }
+    content
+}
+
+#[test]
+fn test_mbc1_rom_bank_switch() {
+    let mut mbc = Mbc1::new(build_banked_rom(32), 32, 0);
+
+    assert_eq!(mbc.read_rom(0x4000), Some(1));
+
+    mbc.write_rom(0x2000, 0x05);
+    assert_eq!(mbc.read_rom(0x4000), Some(5));
+
+    //Bank 0 is translated to bank 1
+    mbc.write_rom(0x2000, 0x00);
+    assert_eq!(mbc.read_rom(0x4000), Some(1));
+}
+
+#[test]
+fn test_mbc1_rom_is_not_writable() {
+    let mut mbc = Mbc1::new(build_banked_rom(4), 4, 0);
+
+    mbc.write_rom(0x0100, 0x55);
+
+    assert_eq!(mbc.read_rom(0x0100), Some(0x00));
+}
+
+#[test]
+fn test_mbc1_secondary_bank() {
+    let mut mbc = Mbc1::new(build_banked_rom(128), 128, 0);
+
+    mbc.write_rom(0x2000, 0x02);
+    mbc.write_rom(0x4000, 0x01);
+    assert_eq!(mbc.read_rom(0x4000), Some(0x22));
+
+    //Mode 0 keeps bank 0 mapped at 0x0000
+    assert_eq!(mbc.read_rom(0x0000), Some(0x00));
+
+    mbc.write_rom(0x6000, 0x01);
+    assert_eq!(mbc.read_rom(0x0000), Some(0x20));
+}
+
+#[test]
+fn test_mbc1_ram_enable_and_banking() {
+    let mut mbc = Mbc1::new(build_banked_rom(4), 4, 4);
+
+    mbc.write_ram(0x0000, 0x12);
+    assert_eq!(mbc.read_ram(0x0000), Some(0xFF));
+
+    mbc.write_rom(0x0000, 0x0A);
+    mbc.write_ram(0x0000, 0x12);
+    assert_eq!(mbc.read_ram(0x0000), Some(0x12));
+
+    mbc.write_rom(0x6000, 0x01);
+    mbc.write_rom(0x4000, 0x02);
+    assert_eq!(mbc.read_ram(0x0000), Some(0x00));
+    mbc.write_ram(0x0000, 0x34);
+
+    mbc.write_rom(0x4000, 0x00);
+    assert_eq!(mbc.read_ram(0x0000), Some(0x12));
+}
+
+#[test]
+fn test_mbc1_multicart() {
+    let mut content = build_banked_rom(64);
+    for game in 0..4 {
+        let header = game * 0x10 * 16 * 1024 + 0x104;
+        content[header..header + 0x30].copy_from_slice(&[0xCE; 0x30]);
+    }
+    let mut mbc = Mbc1::new(content, 64, 0);
+
+    mbc.write_rom(0x2000, 0x12);
+    mbc.write_rom(0x4000, 0x01);
+
+    assert_eq!(mbc.read_rom(0x4000), Some(0x12));
+
+    mbc.write_rom(0x6000, 0x01);
+    assert_eq!(mbc.read_rom(0x0000), Some(0x10));
+}
+
+fn latch(mbc: &mut Mbc3) {
+    mbc.write_rom(0x6000, 0x00);
+    mbc.write_rom(0x6000, 0x01);
+}
+
+#[test]
+fn test_mbc3_rom_bank_switch() {
+    let mut mbc = Mbc3::new(build_banked_rom(128), 128, 0, false);
+
+    mbc.write_rom(0x2000, 0x45);
+    assert_eq!(mbc.read_rom(0x4000), Some(0x45));
+
+    mbc.write_rom(0x2000, 0x00);
+    assert_eq!(mbc.read_rom(0x4000), Some(0x01));
+}
+
+#[test]
+fn test_mbc3_rtc_latch() {
+    let mut mbc = Mbc3::new(build_banked_rom(4), 4, 1, true);
+    mbc.write_rom(0x0000, 0x0A);
+
+    mbc.tick(4_194_304 * 61);
+
+    mbc.write_rom(0x4000, 0x08);
+    assert_eq!(mbc.read_ram(0x0000), Some(0));
+
+    latch(&mut mbc);
+    assert_eq!(mbc.read_ram(0x0000), Some(1));
+    mbc.write_rom(0x4000, 0x09);
+    assert_eq!(mbc.read_ram(0x0000), Some(1));
+
+    //The latched value doesn't change until the next latch
+    mbc.tick(4_194_304);
+    mbc.write_rom(0x4000, 0x08);
+    assert_eq!(mbc.read_ram(0x0000), Some(1));
+}
+
+#[test]
+fn test_mbc3_rtc_halt_and_day_carry() {
+    let mut mbc = Mbc3::new(build_banked_rom(4), 4, 1, true);
+    mbc.write_rom(0x0000, 0x0A);
+
+    mbc.write_rom(0x4000, 0x0B);
+    mbc.write_ram(0x0000, 0xFF);
+    mbc.write_rom(0x4000, 0x0C);
+    mbc.write_ram(0x0000, 0x41);
+
+    //Halted clocks don't advance
+    mbc.tick(4_194_304 * 86400);
+    latch(&mut mbc);
+    assert_eq!(mbc.read_ram(0x0000), Some(0x41));
+
+    mbc.write_ram(0x0000, 0x01);
+    mbc.tick(4_194_304 * 86400);
+    latch(&mut mbc);
+
+    assert_eq!(mbc.read_ram(0x0000), Some(0x80));
+    mbc.write_rom(0x4000, 0x0B);
+    assert_eq!(mbc.read_ram(0x0000), Some(0x00));
+}
+
+#[test]
+fn test_mbc3_save_trailer() {
+    let mut mbc = Mbc3::new(build_banked_rom(4), 4, 1, true);
+    mbc.write_rom(0x0000, 0x0A);
+    mbc.write_ram(0x0010, 0x99);
+    mbc.write_rom(0x4000, 0x0A);
+    mbc.write_ram(0x0000, 0x05);
+
+    let data = mbc.get_save_data();
+    assert_eq!(data.len(), 8 * 1024 + Rtc::get_save_size());
+
+    let mut restored = Mbc3::new(build_banked_rom(4), 4, 1, true);
+    restored.load_save_data(&data);
+    restored.write_rom(0x0000, 0x0A);
+    assert_eq!(restored.read_ram(0x0010), Some(0x99));
+
+    latch(&mut restored);
+    restored.write_rom(0x4000, 0x0A);
+    assert_eq!(restored.read_ram(0x0000), Some(0x05));
+}
+
+#[test]
+fn test_mbc5_nine_bit_rom_bank() {
+    let mut content = build_banked_rom(512);
+    content[0x1FF * 16 * 1024 + 1] = 0xAB;
+    let mut mbc = Mbc5::new(content, 512, 0, false);
+
+    mbc.write_rom(0x2000, 0xFF);
+    mbc.write_rom(0x3000, 0x01);
+    assert_eq!(mbc.read_rom(0x4001), Some(0xAB));
+
+    //Bank 0 is allowed in the switchable area
+    mbc.write_rom(0x2000, 0x00);
+    mbc.write_rom(0x3000, 0x00);
+    assert_eq!(mbc.read_rom(0x4000), Some(0x00));
+}
+
+#[test]
+fn test_mbc5_ram_banks() {
+    let mut mbc = Mbc5::new(build_banked_rom(4), 4, 16, false);
+    mbc.write_rom(0x0000, 0x0A);
+
+    mbc.write_rom(0x4000, 0x0F);
+    mbc.write_ram(0x0000, 0x0F);
+    mbc.write_rom(0x4000, 0x00);
+    mbc.write_ram(0x0000, 0x01);
+
+    mbc.write_rom(0x4000, 0x0F);
+    assert_eq!(mbc.read_ram(0x0000), Some(0x0F));
+}
+
+#[test]
+fn test_mbc5_rumble_callback() {
+    let states = Rc::new(RefCell::new(Vec::new()));
+    let recorded = states.clone();
+
+    let mut mbc = Mbc5::new(build_banked_rom(4), 4, 1, true);
+    mbc.set_rumble_callback(Box::new(move |active| recorded.borrow_mut().push(active)));
+
+    mbc.write_rom(0x4000, 0x08);
+    mbc.write_rom(0x4000, 0x08);
+    assert_eq!(mbc.is_rumble_active(), true);
+    mbc.write_rom(0x4000, 0x00);
+
+    assert_eq!(*states.borrow(), vec![true, false]);
+}
+
+#[test]
+fn test_unsupported_mapper_error() {
+    let mut content = build_banked_rom(2);
+    content[0x147] = 0xFC;
+
+    let result = super::new_mbc(Cartidge::new_from_bytes(content));
+
+    assert_eq!(result.err(), Some(CartridgeError::UnsupportedMapper(Mapper::PocketCamera)));
+}
+
+#[test]
+fn test_unknown_cartridge_type_error() {
+    let mut content = build_banked_rom(2);
+    content[0x147] = 0x42;
+
+    let result = super::new_mbc(Cartidge::new_from_bytes(content));
+
+    assert_eq!(
+        result.err(),
+        Some(CartridgeError::InvalidHeader(HeaderError::UnknownCartridgeType(0x42)))
+    );
+}
+
+#[test]
+fn test_mbc2_registers_and_ram() {
+    let mut mbc = Mbc2::new(build_banked_rom(16), 16);
+
+    //Address bit 8 set selects the ROM bank register
+    mbc.write_rom(0x2100, 0x03);
+    assert_eq!(mbc.read_rom(0x4000), Some(0x03));
+
+    mbc.write_rom(0x0000, 0x0A);
+    mbc.write_ram(0x0000, 0x5A);
+    assert_eq!(mbc.read_ram(0x0000), Some(0xFA));
+    //The 512 half bytes are echoed through the RAM area
+    assert_eq!(mbc.read_ram(0x0200), Some(0xFA));
+}
+
+#[test]
+fn test_mbc6_split_banks_and_flash() {
+    let mut mbc = Mbc6::new(build_banked_rom(8), 8, 4);
+
+    mbc.write_rom(0x2000, 0x04);
+    mbc.write_rom(0x3000, 0x07);
+    assert_eq!(mbc.read_rom(0x4000), Some(0x02));
+    assert_eq!(mbc.read_rom(0x6001), Some(0x00));
+
+    mbc.write_rom(0x0C00, 0x01);
+    mbc.write_rom(0x1000, 0x01);
+    mbc.write_rom(0x2800, 0x08);
+    mbc.write_rom(0x2000, 0x01);
+    assert_eq!(mbc.read_rom(0x4010), Some(0xFF));
+
+    for value in [0xAA, 0x55, 0xA0, 0x3C] {
+        mbc.write_rom(0x4010, value);
+    }
+    assert_eq!(mbc.read_rom(0x4010), Some(0x3C));
+
+    for value in [0xAA, 0x55, 0x80, 0xAA, 0x55, 0x30] {
+        mbc.write_rom(0x4010, value);
+    }
+    assert_eq!(mbc.read_rom(0x4010), Some(0xFF));
+}
+
+fn write_eeprom_bits(mbc: &mut Mbc7, bits: &[u8]) {
+    for bit in bits {
+        mbc.write_ram(0x0080, 0x80 | bit << 1);
+        mbc.write_ram(0x0080, 0xC0 | bit << 1);
+    }
+}
+
+fn read_eeprom_word(mbc: &mut Mbc7) -> u16 {
+    let mut value = 0;
+    for _ in 0..16 {
+        mbc.write_ram(0x0080, 0x80);
+        mbc.write_ram(0x0080, 0xC0);
+        value = (value << 1) | (mbc.read_ram(0x0080).unwrap() & 0x01) as u16;
+    }
+    value
+}
+
+#[test]
+fn test_mbc7_accelerometer() {
+    let mut mbc = Mbc7::new(build_banked_rom(4), 4);
+    mbc.write_rom(0x0000, 0x0A);
+    mbc.write_rom(0x4000, 0x40);
+
+    mbc.set_accelerometer(1.0, -1.0);
+    mbc.write_ram(0x0000, 0x55);
+    mbc.write_ram(0x0010, 0xAA);
+
+    assert_eq!(mbc.read_ram(0x0020), Some(0x40));
+    assert_eq!(mbc.read_ram(0x0030), Some(0x82));
+    assert_eq!(mbc.read_ram(0x0040), Some(0x60));
+    assert_eq!(mbc.read_ram(0x0050), Some(0x81));
+}
+
+#[test]
+fn test_mbc7_eeprom_write_and_read() {
+    let mut mbc = Mbc7::new(build_banked_rom(4), 4);
+    mbc.write_rom(0x0000, 0x0A);
+    mbc.write_rom(0x4000, 0x40);
+
+    //EWEN
+    write_eeprom_bits(&mut mbc, &[1, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0]);
+    mbc.write_ram(0x0080, 0x00);
+
+    //WRITE 0x1234 to word 5
+    write_eeprom_bits(&mut mbc, &[1, 0, 1, 0, 0, 0, 0, 0, 1, 0, 1]);
+    write_eeprom_bits(&mut mbc, &[0, 0, 0, 1, 0, 0, 1, 0, 0, 0, 1, 1, 0, 1, 0, 0]);
+    mbc.write_ram(0x0080, 0x00);
+
+    //READ word 5
+    write_eeprom_bits(&mut mbc, &[1, 1, 0, 0, 0, 0, 0, 0, 1, 0, 1]);
+    assert_eq!(mbc.read_ram(0x0080).unwrap() & 0x01, 0);
+    assert_eq!(read_eeprom_word(&mut mbc), 0x1234);
+
+    assert_eq!(&mbc.get_save_data()[10..12], &[0x34, 0x12]);
+}
+
+#[test]
+fn test_huc3_clock_commands() {
+    let mut mbc = HuC3::new(build_banked_rom(4), 4, 1);
+    mbc.tick(4_194_304 * 60 * 1441);
+
+    assert_eq!(read_huc3_clock(&mut mbc), vec![1, 0, 0, 1, 0, 0]);
+}
+
+#[test]
+fn test_huc3_host_clock_adds_offline_time() {
+    let mbc = HuC3::new(build_banked_rom(4), 4, 1);
+    let mut data = mbc.get_save_data();
+    let ram_size = data.len() - 12;
+    let saved_time = LittleEndian::read_u64(&data[ram_size..ram_size + 8]);
+    LittleEndian::write_u64(&mut data[ram_size..ram_size + 8], saved_time - 125 * 60 - 30);
+
+    //The save is loaded before the clock source is picked
+    let mut restored = HuC3::new(build_banked_rom(4), 4, 1);
+    restored.load_save_data(&data);
+    restored.set_rtc_clock(RtcClock::Host);
+
+    assert_eq!(read_huc3_clock(&mut restored), vec![0xD, 0x7, 0, 0, 0, 0]);
+}
+
+#[test]
+fn test_huc3_host_clock_save_keeps_unsynced_time() {
+    let mut mbc = HuC3::new(build_banked_rom(4), 4, 1);
+    let mut data = mbc.get_save_data();
+    let ram_size = data.len() - 12;
+    let saved_time = LittleEndian::read_u64(&data[ram_size..ram_size + 8]);
+    LittleEndian::write_u64(&mut data[ram_size..ram_size + 8], saved_time - 90);
+
+    //The minute is counted on load, the leftover seconds wait for the next sync
+    mbc.set_rtc_clock(RtcClock::Host);
+    mbc.load_save_data(&data);
+    let saved = mbc.get_save_data();
+    assert_eq!(LittleEndian::read_u64(&saved[ram_size..ram_size + 8]), saved_time - 30);
+    assert_eq!(&saved[ram_size + 8..ram_size + 12], &[1, 0, 0, 0]);
+}
+
+/**
+ * Reads the minutes and days nibbles through the clock command interface
+ */
+fn read_huc3_clock(mbc: &mut HuC3) -> Vec<u8> {
+    mbc.write_rom(0x0000, 0x0B);
+    mbc.write_ram(0x0000, 0x60);
+    mbc.write_ram(0x0000, 0x40);
+    mbc.write_ram(0x0000, 0x50);
+
+    let mut nibbles = Vec::new();
+    for _ in 0..6 {
+        mbc.write_rom(0x0000, 0x0B);
+        mbc.write_ram(0x0000, 0x10);
+        mbc.write_rom(0x0000, 0x0C);
+        nibbles.push(mbc.read_ram(0x0000).unwrap() & 0x0F);
+    }
+    nibbles
+}
+
+#[test]
+fn test_mmm01_menu_and_mapping() {
+    let mut mbc = Mmm01::new(build_banked_rom(64), 64, 0);
+
+    assert_eq!(mbc.read_rom(0x0000), Some(62));
+    assert_eq!(mbc.read_rom(0x4000), Some(63));
+
+    //Select the game at bank 0x20 and map it
+    mbc.write_rom(0x2000, 0x20);
+    mbc.write_rom(0x0000, 0x40);
+
+    assert_eq!(mbc.read_rom(0x0000), Some(0x20));
+    assert_eq!(mbc.read_rom(0x4000), Some(0x21));
+
+    mbc.write_rom(0x2000, 0x03);
+    assert_eq!(mbc.read_rom(0x4000), Some(0x23));
+}
+
+#[test]
+fn test_mbc1_save_data_round_trip() {
+    let mut mbc = Mbc1::new(build_banked_rom(4), 4, 1);
+    mbc.write_rom(0x0000, 0x0A);
+    mbc.write_ram(0x0010, 0x42);
+
+    let data = mbc.get_save_data();
+    assert_eq!(data.len(), 8 * 1024);
+
+    let mut restored = Mbc1::new(build_banked_rom(4), 4, 1);
+    restored.load_save_data(&data);
+    restored.write_rom(0x0000, 0x0A);
+    assert_eq!(restored.read_ram(0x0010), Some(0x42));
+}
+
+#[test]
+fn test_save_file_only_flushes_changes() {
+    let path = std::env::temp_dir().join(format!("gbc-save-test-{}.sav", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut mbc = Mbc5::new(build_banked_rom(4), 4, 1, false);
+    let mut save_file = SaveFile::new(path.clone());
+    assert_eq!(save_file.read(), None);
+
+    mbc.write_rom(0x0000, 0x0A);
+    mbc.write_ram(0x0000, 0x99);
+
+    //Not enough cycles for a periodic flush yet
+    save_file.tick(1000, &mbc);
+    assert_eq!(path.exists(), false);
+
+    save_file.tick(4_194_304, &mbc);
+    let written = std::fs::read(&path).unwrap();
+    assert_eq!(written[0], 0x99);
+
+    let mut reloaded = SaveFile::new(path.clone());
+    assert_eq!(reloaded.read(), Some(written));
+
+    std::fs::remove_file(&path).unwrap();
+    //Unchanged data doesn't touch the disk
+    reloaded.flush(&mbc).unwrap();
+    assert_eq!(path.exists(), false);
+}
+
+#[test]
+fn test_save_file_ignores_clock_timestamp() {
+    let path = std::env::temp_dir().join(format!("gbc-rtc-save-test-{}.sav", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let time = Rc::new(Cell::new(1_000_000));
+    let mut mbc = Mbc3::new(build_banked_rom(4), 4, 1, true);
+    mbc.set_time_source(fake_time_source(&time));
+    mbc.write_rom(0x0000, 0x0A);
+    mbc.write_ram(0x0000, 0x99);
+    let mut save_file = SaveFile::new(path.clone());
+    save_file.flush(&mbc).unwrap();
+    assert_eq!(path.exists(), true);
+
+    //Only the host timestamp of the trailer moves on
+    std::fs::remove_file(&path).unwrap();
+    time.set(time.get() + 1);
+    save_file.flush(&mbc).unwrap();
+    assert_eq!(path.exists(), false);
+}
+
+#[test]
+fn test_mbc3_host_clock_adds_offline_time() {
+    let mbc = Mbc3::new(build_banked_rom(4), 4, 1, true);
+    let mut data = mbc.get_save_data();
+    let ram_size = data.len() - Rtc::get_save_size();
+    let saved_time = LittleEndian::read_u64(&data[ram_size + 40..ram_size + 48]);
+    LittleEndian::write_u64(&mut data[ram_size + 40..ram_size + 48], saved_time - 125);
+
+    //The save is loaded before the clock source is picked
+    let mut restored = Mbc3::new(build_banked_rom(4), 4, 1, true);
+    restored.load_save_data(&data);
+    restored.set_rtc_clock(RtcClock::Host);
+
+    restored.write_rom(0x0000, 0x0A);
+    latch(&mut restored);
+    restored.write_rom(0x4000, 0x09);
+    assert_eq!(restored.read_ram(0x0000), Some(2));
+    restored.write_rom(0x4000, 0x08);
+    let seconds = restored.read_ram(0x0000).unwrap();
+    assert!((5..=7).contains(&seconds));
+}
+
+#[test]
+fn test_mbc3_host_clock_save_keeps_unsynced_time() {
+    let time = Rc::new(Cell::new(1_000_000));
+    let mut mbc = Mbc3::new(build_banked_rom(4), 4, 1, true);
+    mbc.set_time_source(fake_time_source(&time));
+    mbc.set_rtc_clock(RtcClock::Host);
+
+    //Nothing reads the clock before saving, so the registers haven't caught up with the host yet
+    time.set(time.get() + 5);
+    let data = mbc.get_save_data();
+    let ram_size = data.len() - Rtc::get_save_size();
+    assert_eq!(LittleEndian::read_u64(&data[ram_size + 40..ram_size + 48]), 1_000_000);
+
+    let mut restored = Mbc3::new(build_banked_rom(4), 4, 1, true);
+    restored.set_time_source(fake_time_source(&time));
+    restored.set_rtc_clock(RtcClock::Host);
+    restored.load_save_data(&data);
+
+    restored.write_rom(0x0000, 0x0A);
+    latch(&mut restored);
+    restored.write_rom(0x4000, 0x08);
+    assert_eq!(restored.read_ram(0x0000), Some(5));
+}
+
+/**
+ * Host clock driven by a fake unix time the test moves by hand
+ */
+fn fake_host_clock(unit: u64) -> (HostClock, Rc<Cell<u64>>) {
+    let time = Rc::new(Cell::new(1_000_000));
+    let mut clock = HostClock::new(unit);
+    clock.set_time_source(fake_time_source(&time));
+    (clock, time)
+}
+
+fn fake_time_source(time: &Rc<Cell<u64>>) -> TimeSource {
+    let time = time.clone();
+    Box::new(move || time.get())
+}
+
+#[test]
+fn test_host_clock_units() {
+    let (mut clock, time) = fake_host_clock(60);
+    assert_eq!(clock.set_clock(RtcClock::Host), 0);
+
+    //Leftover seconds are kept for the next sync
+    time.set(time.get() + 150);
+    assert_eq!(clock.sync(), 2);
+    assert_eq!(clock.get_save_time(), 1_000_120);
+    time.set(time.get() + 30);
+    assert_eq!(clock.sync(), 1);
+
+    //The emulated clock never reports host time
+    clock.set_clock(RtcClock::Emulated);
+    time.set(time.get() + 600);
+    assert_eq!(clock.sync(), 0);
+    assert_eq!(clock.get_save_time(), time.get());
+}
+
+#[test]
+fn test_host_clock_switch_after_load() {
+    let (mut clock, time) = fake_host_clock(1);
+    assert_eq!(clock.load(time.get() - 125), 0);
+
+    time.set(time.get() + 5);
+    assert_eq!(clock.set_clock(RtcClock::Host), 130);
+    assert_eq!(clock.sync(), 0);
+}
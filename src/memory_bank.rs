@@ -32,14 +32,44 @@ impl MemoryBank {
     }
 
     pub fn write(&mut self, address: u16, value: u8) {
-        let actual_address = self.current_bank * self.bank_size;
+        let actual_address = address as usize + self.current_bank * self.bank_size;
         assert!(
-            actual_address < self.mem.len(),
+            actual_address < self.bank_size * self.bank_ammount,
             "Writing to memory out of bounds"
         );
         self.mem[actual_address] = value;
     }
 
+    pub fn get_current_bank(&self) -> usize {
+        self.current_bank
+    }
+
+    pub fn set_current_bank(&mut self, bank: usize) {
+        self.current_bank = bank;
+    }
+
+    pub fn get_bank_ammount(&self) -> usize {
+        self.bank_ammount
+    }
+
+    pub fn read_from_bank(&self, bank: usize, address: u16) -> Option<u8> {
+        self.mem.get(address as usize + bank * self.bank_size).copied()
+    }
+
+    pub fn write_to_bank(&mut self, bank: usize, address: u16, value: u8) {
+        if let Some(byte) = self.mem.get_mut(address as usize + bank * self.bank_size) {
+            *byte = value;
+        }
+    }
+
+    pub fn get_bytes(&self) -> &[u8] {
+        &self.mem
+    }
+
+    pub fn get_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.mem
+    }
+
     pub fn read_bankless(&self, address: u16) -> Option<u8> {
         self.mem.get(address as usize).copied()
     }
@@ -0,0 +1,67 @@
+use std::{fmt, str::FromStr};
+
+use crate::header::{CartridgeHeader, Licensee};
+
+/**
+ * Hardware the emulator behaves as. Besides the initial register values it decides whether CGB
+ * games run with their color features and whether DMG games run in the CGB compatibility mode
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Model {
+    Dmg,
+    Mgb,
+    Sgb,
+    Cgb,
+    Agb,
+}
+
+impl Model {
+    /**
+     * The model a game is meant for: CGB for games that know about its features, SGB for the ones with SGB
+     * support (the SGB only enables it with the new licensee code) and DMG otherwise
+     */
+    pub fn detect(header: &CartridgeHeader) -> Self {
+        if header.is_cgb() {
+            Model::Cgb
+        } else if header.sgb_support && matches!(header.licensee, Licensee::New(_)) {
+            Model::Sgb
+        } else {
+            Model::Dmg
+        }
+    }
+
+    /**
+     * Whether the model has the CGB hardware, which runs DMG games in compatibility mode
+     */
+    pub fn is_cgb_hardware(&self) -> bool {
+        matches!(self, Model::Cgb | Model::Agb)
+    }
+}
+
+impl fmt::Display for Model {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Model::Dmg => "DMG",
+            Model::Mgb => "MGB",
+            Model::Sgb => "SGB",
+            Model::Cgb => "CGB",
+            Model::Agb => "AGB",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Model {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "dmg" => Ok(Model::Dmg),
+            "mgb" => Ok(Model::Mgb),
+            "sgb" => Ok(Model::Sgb),
+            "cgb" => Ok(Model::Cgb),
+            "agb" => Ok(Model::Agb),
+            _ => Err(format!("Unknown model {}, expected dmg, mgb, sgb, cgb or agb", name)),
+        }
+    }
+}
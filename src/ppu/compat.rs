@@ -0,0 +1,133 @@
+/**
+ * Palettes the CGB boot rom gives DMG games. Licensed Nintendo games are recognized by the sum of their
+ * title bytes, the rest get the default combination
+ */
+pub struct CompatPalettes {
+    pub bg: [u16; 4],
+    pub obj0: [u16; 4],
+    pub obj1: [u16; 4],
+}
+
+//Nintendo as old licensee, or as new licensee when the old one is 0x33
+const NINTENDO_LICENSEE: u8 = 0x01;
+const NEW_LICENSEE_MARKER: u8 = 0x33;
+
+//Title sums known to the boot rom, the last 14 are shared by several games
+const TITLE_CHECKSUMS: [u8; 79] = [
+    0x00, 0x88, 0x16, 0x36, 0xD1, 0xDB, 0xF2, 0x3C, 0x8C, 0x92, 0x3D, 0x5C, 0x58,
+    0xC9, 0x3E, 0x70, 0x1D, 0x59, 0x69, 0x19, 0x35, 0xA8, 0x14, 0xAA, 0x75, 0x95,
+    0x99, 0x34, 0x6F, 0x15, 0xFF, 0x97, 0x4B, 0x90, 0x17, 0x10, 0x39, 0xF7, 0xF6,
+    0xA2, 0x49, 0x4E, 0x43, 0x68, 0xE0, 0x8B, 0xF0, 0xCE, 0x0C, 0x29, 0xE8, 0xB7,
+    0x86, 0x9A, 0x52, 0x01, 0x9D, 0x71, 0x9C, 0xBD, 0x5D, 0x6D, 0x67, 0x3F, 0x6B,
+    0xB3, 0x46, 0x28, 0xA5, 0xC6, 0xD3, 0x27, 0x61, 0x18, 0x66, 0x6A, 0xBF, 0x0D,
+    0xF4,
+];
+const FIRST_SHARED_CHECKSUM: usize = 65;
+const SHARED_CHECKSUMS: usize = 14;
+
+//The 4th title letter tells apart the games sharing a sum. Each row of 14 letters has one entry per shared
+//sum, the games matched in later rows come after the earlier ones in GAME_COMBINATIONS
+const FOURTH_LETTERS: &[u8; 29] = b"BEFAARBEKEK R-URAR INAILICE R";
+
+//Palette combination of every recognized game, index 0 is the default
+const GAME_COMBINATIONS: [u8; 94] = [
+    0, 4, 5, 35, 34, 3, 31, 15, 10, 5, 19, 36, 7, 37, 30, 44,
+    21, 32, 31, 20, 5, 33, 13, 14, 5, 29, 5, 18, 9, 3, 2, 26,
+    25, 25, 41, 42, 26, 45, 42, 45, 36, 38, 26, 42, 30, 41, 34, 34,
+    5, 42, 6, 5, 33, 25, 42, 42, 40, 2, 16, 25, 42, 42, 5, 0,
+    39, 36, 22, 25, 6, 32, 12, 36, 11, 39, 18, 39, 24, 31, 50, 17,
+    46, 6, 27, 0, 47, 41, 41, 0, 0, 19, 34, 23, 18, 29,
+];
+
+//OBJ0, OBJ1 and BG as offsets in PALETTE_COLORS. A few combinations start in the middle of a palette
+const COMBINATIONS: [(usize, usize, usize); 51] = [
+    (16, 16, 116), (72, 72, 72), (80, 80, 80), (96, 96, 96), (36, 36, 36), (0, 0, 0),
+    (108, 108, 108), (20, 20, 20), (48, 48, 48), (104, 104, 104), (64, 32, 32), (16, 112, 112),
+    (16, 8, 8), (12, 16, 16), (16, 116, 116), (112, 16, 112), (8, 68, 8), (64, 64, 32),
+    (16, 16, 28), (16, 16, 72), (16, 16, 80), (76, 76, 36), (15, 15, 44), (68, 68, 8),
+    (16, 16, 8), (16, 16, 12), (112, 112, 0), (12, 12, 0), (0, 0, 4), (72, 88, 72),
+    (80, 88, 80), (96, 88, 96), (64, 88, 32), (68, 16, 52), (111, 0, 56), (111, 16, 60),
+    (76, 88, 36), (64, 112, 40), (16, 92, 112), (68, 88, 8), (16, 0, 8), (16, 112, 12),
+    (112, 12, 0), (12, 112, 16), (84, 112, 16), (12, 112, 0), (100, 12, 112), (0, 112, 32),
+    (16, 12, 112), (112, 12, 24), (16, 112, 116),
+];
+
+//BGR555 colors, four per palette
+const PALETTE_COLORS: [u16; 120] = [
+    0x7FFF, 0x32BF, 0x00D0, 0x0000,
+    0x639F, 0x4279, 0x15B0, 0x04CB,
+    0x7FFF, 0x6E31, 0x454A, 0x0000,
+    0x7FFF, 0x1BEF, 0x0200, 0x0000,
+    0x7FFF, 0x421F, 0x1CF2, 0x0000,
+    0x7FFF, 0x5294, 0x294A, 0x0000,
+    0x7FFF, 0x03FF, 0x012F, 0x0000,
+    0x7FFF, 0x03EF, 0x01D6, 0x0000,
+    0x7FFF, 0x42B5, 0x3DC8, 0x0000,
+    0x7E74, 0x03FF, 0x0180, 0x0000,
+    0x67FF, 0x77AC, 0x1A13, 0x2D6B,
+    0x7ED6, 0x4BFF, 0x2175, 0x0000,
+    0x53FF, 0x4A5F, 0x7E52, 0x0000,
+    0x4FFF, 0x7ED2, 0x3A4C, 0x1CE0,
+    0x03ED, 0x7FFF, 0x255F, 0x0000,
+    0x036A, 0x021F, 0x03FF, 0x7FFF,
+    0x7FFF, 0x01DF, 0x0112, 0x0000,
+    0x231F, 0x035F, 0x00F2, 0x0009,
+    0x7FFF, 0x03EA, 0x011F, 0x0000,
+    0x299F, 0x001A, 0x000C, 0x0000,
+    0x7FFF, 0x027F, 0x001F, 0x0000,
+    0x7FFF, 0x03E0, 0x0206, 0x0120,
+    0x7FFF, 0x7EEB, 0x001F, 0x7C00,
+    0x7FFF, 0x3FFF, 0x7E00, 0x001F,
+    0x7FFF, 0x03FF, 0x001F, 0x0000,
+    0x03FF, 0x001F, 0x000C, 0x0000,
+    0x7FFF, 0x033F, 0x0193, 0x0000,
+    0x0000, 0x4200, 0x037F, 0x7FFF,
+    0x7FFF, 0x7E8C, 0x7C00, 0x0000,
+    0x7FFF, 0x1BEF, 0x6180, 0x0000,
+];
+
+impl CompatPalettes {
+    /**
+     * Picks the palettes from the title (0x134-0x143) and the old and new licensee codes (0x14B, 0x144-0x145)
+     */
+    pub fn from_header(title: &[u8; 16], old_licensee: u8, new_licensee: [u8; 2]) -> Self {
+        let is_nintendo = match old_licensee {
+            NINTENDO_LICENSEE => true,
+            NEW_LICENSEE_MARKER => new_licensee == *b"01",
+            _ => false,
+        };
+        let game = if is_nintendo { find_game(title) } else { 0 };
+
+        let (obj0, obj1, bg) = COMBINATIONS[GAME_COMBINATIONS[game] as usize];
+        CompatPalettes {
+            bg: get_palette(bg),
+            obj0: get_palette(obj0),
+            obj1: get_palette(obj1),
+        }
+    }
+}
+
+/**
+ * Index of the game in GAME_COMBINATIONS, 0 when the title isn't known
+ */
+fn find_game(title: &[u8; 16]) -> usize {
+    let checksum = title.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    let index = match TITLE_CHECKSUMS.iter().position(|known| *known == checksum) {
+        Some(index) => index,
+        None => return 0,
+    };
+    if index < FIRST_SHARED_CHECKSUM {
+        return index;
+    }
+
+    (index - FIRST_SHARED_CHECKSUM..FOURTH_LETTERS.len())
+        .step_by(SHARED_CHECKSUMS)
+        .find(|letter| FOURTH_LETTERS[*letter] == title[3])
+        .map_or(0, |letter| FIRST_SHARED_CHECKSUM + letter)
+}
+
+fn get_palette(offset: usize) -> [u16; 4] {
+    let mut palette = [0; 4];
+    palette.copy_from_slice(&PALETTE_COLORS[offset..offset + 4]);
+    palette
+}
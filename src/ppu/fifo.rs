@@ -0,0 +1,303 @@
+use std::collections::VecDeque;
+
+use super::{
+    render::{
+        BgPixel, Sprite, SpritePixel, ATTRIBUTE_BANK, ATTRIBUTE_CGB_PALETTE, ATTRIBUTE_PRIORITY,
+        ATTRIBUTE_X_FLIP, ATTRIBUTE_Y_FLIP, LCDC_BG_ENABLE, LCDC_BG_MAP, LCDC_OBJ_ENABLE,
+        LCDC_WINDOW_ENABLE, LCDC_WINDOW_MAP,
+    },
+    Ppu, SCREEN_WIDTH,
+};
+
+//The first tile fetch of every line is thrown away
+const INITIAL_FETCH_DOTS: u8 = 6;
+const SPRITE_FETCH_DOTS: u8 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FetcherStep {
+    Tile,
+    DataLow,
+    DataHigh,
+    Push,
+}
+
+#[derive(Clone, Copy)]
+struct ObjFifoPixel {
+    pixel: SpritePixel,
+    index: usize,
+}
+
+/**
+ * State of the pixel FIFO renderer for the line being drawn. The background fetcher and the sprite
+ * fetches run dot by dot, so register writes in the middle of mode 3 show up where they happen on
+ * hardware and the length of the mode depends on SCX, the window and the sprites on the line
+ */
+pub(super) struct PixelFifo {
+    bg_fifo: VecDeque<BgPixel>,
+    obj_fifo: VecDeque<Option<ObjFifoPixel>>,
+    sprites: VecDeque<Sprite>,
+    step: FetcherStep,
+    step_dots: u8,
+    fetcher_x: u8,
+    tile: u8,
+    attributes: u8,
+    low: u8,
+    high: u8,
+    fetching_window: bool,
+    discard: u8,
+    stall: u8,
+    x: u8,
+}
+
+impl PixelFifo {
+    pub fn new() -> Self {
+        PixelFifo {
+            bg_fifo: VecDeque::with_capacity(16),
+            obj_fifo: VecDeque::with_capacity(8),
+            sprites: VecDeque::new(),
+            step: FetcherStep::Tile,
+            step_dots: 0,
+            fetcher_x: 0,
+            tile: 0,
+            attributes: 0,
+            low: 0,
+            high: 0,
+            fetching_window: false,
+            discard: 0,
+            stall: 0,
+            x: 0,
+        }
+    }
+
+    /**
+     * Clears the state left by the previous line, keeping the allocations around
+     */
+    fn reset(&mut self) {
+        self.bg_fifo.clear();
+        self.obj_fifo.clear();
+        self.sprites.clear();
+        self.restart_fetcher();
+        self.tile = 0;
+        self.attributes = 0;
+        self.low = 0;
+        self.high = 0;
+        self.fetching_window = false;
+        self.discard = 0;
+        self.stall = 0;
+        self.x = 0;
+    }
+
+    fn restart_fetcher(&mut self) {
+        self.step = FetcherStep::Tile;
+        self.step_dots = 0;
+        self.fetcher_x = 0;
+    }
+}
+
+impl Default for PixelFifo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ppu {
+    /**
+     * Prepares the FIFO for a new line at the start of mode 3
+     */
+    pub(super) fn start_fifo_line(&mut self) {
+        let mut sprites = self.scan_oam();
+        sprites.sort_by_key(|sprite| (sprite.x, sprite.index));
+
+        self.fifo.reset();
+        self.fifo.sprites.extend(sprites);
+        self.fifo.discard = self.scx % 8;
+        self.fifo.stall = INITIAL_FETCH_DOTS;
+    }
+
+    /**
+     * Runs the FIFO for one dot, returns true once the 160 pixels of the line have been pushed out
+     */
+    pub(super) fn step_fifo(&mut self) -> bool {
+        if self.fifo.stall > 0 {
+            self.fifo.stall -= 1;
+            return false;
+        }
+
+        if !self.fifo.fetching_window && self.is_window_triggered() {
+            self.fifo.bg_fifo.clear();
+            self.fifo.restart_fetcher();
+            self.fifo.fetching_window = true;
+            //With WX below 7 the window starts left of the screen, its first pixels are dropped
+            self.fifo.discard = if self.fifo.x == 0 { 7u8.saturating_sub(self.wx) } else { 0 };
+        }
+
+        if self.fetch_next_sprite() {
+            self.fifo.stall = SPRITE_FETCH_DOTS - 1;
+            return false;
+        }
+
+        self.step_fetcher();
+        self.shift_pixel();
+
+        if self.fifo.x as usize == SCREEN_WIDTH {
+            if self.fifo.fetching_window {
+                self.window_line += 1;
+            }
+            return true;
+        }
+        false
+    }
+
+    fn is_window_triggered(&self) -> bool {
+        self.lcdc & LCDC_WINDOW_ENABLE != 0
+            && (self.is_cgb || self.lcdc & LCDC_BG_ENABLE != 0)
+            && self.ly >= self.wy
+            && self.wx <= 166
+            && self.fifo.x as u16 + 7 >= self.wx as u16
+    }
+
+    /**
+     * Fetching a sprite stalls the pixel output. The sprite is mixed into the OBJ FIFO over the pixels
+     * that are already there unless they are transparent or, on CGB, belong to a later OAM entry
+     */
+    fn fetch_next_sprite(&mut self) -> bool {
+        if self.lcdc & LCDC_OBJ_ENABLE == 0 {
+            return false;
+        }
+
+        let x = self.fifo.x as i16;
+        let sprite = match self.fifo.sprites.front() {
+            Some(sprite) if sprite.x as i16 - 8 <= x => *sprite,
+            _ => return false,
+        };
+        self.fifo.sprites.pop_front();
+
+        while self.fifo.obj_fifo.len() < 8 {
+            self.fifo.obj_fifo.push_back(None);
+        }
+
+        let oam_priority = self.has_oam_priority();
+        for (column, pixel) in self.get_sprite_row(&sprite).iter().enumerate() {
+            let slot = sprite.x as i16 - 8 + column as i16 - x;
+            let pixel = match pixel {
+                Some(pixel) if slot >= 0 => *pixel,
+                _ => continue,
+            };
+
+            let entry = &mut self.fifo.obj_fifo[slot as usize];
+            let replace = match entry {
+                None => true,
+                Some(existing) => oam_priority && existing.index > sprite.index,
+            };
+            if replace {
+                *entry = Some(ObjFifoPixel {
+                    pixel,
+                    index: sprite.index,
+                });
+            }
+        }
+
+        true
+    }
+
+    /**
+     * Every step but the push takes two dots, the push waits until the background FIFO is empty
+     */
+    fn step_fetcher(&mut self) {
+        if self.fifo.step != FetcherStep::Push {
+            self.fifo.step_dots += 1;
+            if self.fifo.step_dots < 2 {
+                return;
+            }
+            self.fifo.step_dots = 0;
+        }
+
+        match self.fifo.step {
+            FetcherStep::Tile => {
+                let map_address = self.get_fetcher_map_address();
+                let (tile, attributes) = self.get_map_entry(map_address);
+                self.fifo.tile = tile;
+                self.fifo.attributes = attributes;
+                self.fifo.step = FetcherStep::DataLow;
+            }
+            FetcherStep::DataLow => {
+                self.fifo.low = self.read_fetcher_tile_data(0);
+                self.fifo.step = FetcherStep::DataHigh;
+            }
+            FetcherStep::DataHigh => {
+                self.fifo.high = self.read_fetcher_tile_data(1);
+                self.fifo.step = FetcherStep::Push;
+            }
+            FetcherStep::Push => {
+                if self.fifo.bg_fifo.is_empty() {
+                    self.push_tile_row();
+                    self.fifo.fetcher_x = self.fifo.fetcher_x.wrapping_add(1);
+                    self.fifo.step = FetcherStep::Tile;
+                }
+            }
+        }
+    }
+
+    fn get_fetcher_map_address(&self) -> u16 {
+        if self.fifo.fetching_window {
+            let map = if self.lcdc & LCDC_WINDOW_MAP != 0 { 0x1C00 } else { 0x1800 };
+            map + (self.window_line as u16 / 8) * 32 + (self.fifo.fetcher_x as u16 & 0x1F)
+        } else {
+            let map = if self.lcdc & LCDC_BG_MAP != 0 { 0x1C00 } else { 0x1800 };
+            let y = self.ly.wrapping_add(self.scy);
+            let x = (self.scx / 8).wrapping_add(self.fifo.fetcher_x) & 0x1F;
+            map + (y as u16 / 8) * 32 + x as u16
+        }
+    }
+
+    fn read_fetcher_tile_data(&self, offset: u16) -> u8 {
+        let y = if self.fifo.fetching_window {
+            self.window_line
+        } else {
+            self.ly.wrapping_add(self.scy)
+        } % 8;
+        let y = if self.fifo.attributes & ATTRIBUTE_Y_FLIP != 0 { 7 - y } else { y };
+        let bank = (self.fifo.attributes & ATTRIBUTE_BANK != 0) as usize;
+
+        let address = self.get_bg_tile_address(self.fifo.tile) + y as u16 * 2 + offset;
+        self.vram.read_from_bank(bank, address).unwrap_or(0)
+    }
+
+    fn push_tile_row(&mut self) {
+        let attributes = self.fifo.attributes;
+        for column in 0..8 {
+            let bit = if attributes & ATTRIBUTE_X_FLIP != 0 { column } else { 7 - column };
+            self.fifo.bg_fifo.push_back(BgPixel {
+                color: ((self.fifo.high >> bit) & 1) << 1 | ((self.fifo.low >> bit) & 1),
+                palette: attributes & ATTRIBUTE_CGB_PALETTE,
+                priority: attributes & ATTRIBUTE_PRIORITY != 0,
+            });
+        }
+    }
+
+    /**
+     * Pops a pixel from each FIFO and mixes them, the first SCX % 8 background pixels are discarded
+     */
+    fn shift_pixel(&mut self) {
+        let bg = match self.fifo.bg_fifo.pop_front() {
+            Some(bg) => bg,
+            None => return,
+        };
+
+        if self.fifo.discard > 0 {
+            self.fifo.discard -= 1;
+            return;
+        }
+
+        let sprite = self.fifo.obj_fifo.pop_front().flatten().map(|sprite| sprite.pixel);
+        let bg = if self.is_cgb || self.lcdc & LCDC_BG_ENABLE != 0 {
+            bg
+        } else {
+            BgPixel::default()
+        };
+        let sprite = if self.lcdc & LCDC_OBJ_ENABLE != 0 { sprite } else { None };
+
+        self.output_pixel(self.fifo.x as usize, bg, sprite);
+        self.fifo.x += 1;
+    }
+}
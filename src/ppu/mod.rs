@@ -0,0 +1,449 @@
+mod compat;
+mod fifo;
+mod palette;
+mod render;
+mod tests;
+
+use fifo::PixelFifo;
+
+use crate::{
+    interrupts::{Interrupt, InterruptController},
+    memory_bank::MemoryBank,
+};
+
+pub use compat::CompatPalettes;
+pub use palette::{color_to_rgb, ColorPalettes};
+pub use render::{shade_to_rgb, DMG_SHADES};
+
+pub const SCREEN_WIDTH: usize = 160;
+pub const SCREEN_HEIGHT: usize = 144;
+
+const DOTS_PER_LINE: u64 = 456;
+const OAM_SCAN_DOTS: u64 = 80;
+const DRAWING_DOTS: u64 = 172;
+const LINES_PER_FRAME: u8 = 154;
+
+const LCDC_ENABLE: u8 = 0b1000_0000;
+
+const STAT_LYC_INTERRUPT: u8 = 0b0100_0000;
+const STAT_OAM_INTERRUPT: u8 = 0b0010_0000;
+const STAT_VBLANK_INTERRUPT: u8 = 0b0001_0000;
+const STAT_HBLANK_INTERRUPT: u8 = 0b0000_1000;
+const STAT_COINCIDENCE: u8 = 0b0000_0100;
+
+/**
+ * Ppu modes, the discriminant is the value reported in the lower bits of STAT
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    HBlank = 0,
+    VBlank = 1,
+    OamScan = 2,
+    Drawing = 3,
+}
+
+/**
+ * The scanline renderer draws a whole line at once with a fixed mode 3 length, the pixel FIFO
+ * follows the hardware dot by dot at a higher cost
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Renderer {
+    Scanline,
+    PixelFifo,
+}
+
+pub struct Ppu {
+    is_cgb: bool,
+    compat_mode: bool,
+    vram: MemoryBank,
+    oam: [u8; 0xA0],
+    lcdc: u8,
+    stat: u8,
+    scy: u8,
+    scx: u8,
+    ly: u8,
+    lyc: u8,
+    bgp: u8,
+    obp0: u8,
+    obp1: u8,
+    wy: u8,
+    wx: u8,
+    opri: u8,
+    bg_palettes: ColorPalettes,
+    obj_palettes: ColorPalettes,
+    mode: Mode,
+    renderer: Renderer,
+    line_renderer: Renderer,
+    fifo: PixelFifo,
+    dot: u64,
+    window_line: u8,
+    stat_line: bool,
+    framebuffer: Vec<u8>,
+    color_framebuffer: Vec<u16>,
+    frame_ready: bool,
+    hblank_started: bool,
+}
+
+impl Ppu {
+    pub fn new(is_cgb: bool) -> Self {
+        let vram_banks = if is_cgb { 2 } else { 1 };
+
+        Ppu {
+            is_cgb,
+            compat_mode: false,
+            vram: MemoryBank::new(8 * 1024, vram_banks, 0),
+            oam: [0; 0xA0],
+            lcdc: 0,
+            stat: 0,
+            scy: 0,
+            scx: 0,
+            ly: 0,
+            lyc: 0,
+            bgp: 0,
+            obp0: 0,
+            obp1: 0,
+            wy: 0,
+            wx: 0,
+            opri: 0,
+            bg_palettes: ColorPalettes::new(),
+            obj_palettes: ColorPalettes::new(),
+            mode: Mode::HBlank,
+            renderer: Renderer::Scanline,
+            line_renderer: Renderer::Scanline,
+            fifo: PixelFifo::new(),
+            dot: 0,
+            window_line: 0,
+            stat_line: false,
+            framebuffer: vec![0; SCREEN_WIDTH * SCREEN_HEIGHT],
+            color_framebuffer: vec![0; SCREEN_WIDTH * SCREEN_HEIGHT],
+            frame_ready: false,
+            hblank_started: false,
+        }
+    }
+
+    /**
+     * DMG games on CGB hardware: the CGB features are turned off but the DMG shades still go through
+     * background palette 0 and object palettes 0 and 1
+     */
+    pub fn set_compat_mode(&mut self) {
+        self.is_cgb = false;
+        self.compat_mode = true;
+        self.vram.set_current_bank(0);
+        self.opri = 0x01;
+    }
+
+    pub fn is_compat_mode(&self) -> bool {
+        self.compat_mode
+    }
+
+    /**
+     * Loads the colors the compatibility mode maps the BGP, OBP0 and OBP1 shades to
+     */
+    pub fn set_compat_palettes(&mut self, bg: [u16; 4], obj0: [u16; 4], obj1: [u16; 4]) {
+        self.bg_palettes.write_spec(0x80);
+        for color in bg {
+            color.to_le_bytes().iter().for_each(|byte| self.bg_palettes.write_data(*byte));
+        }
+        self.obj_palettes.write_spec(0x80);
+        for color in obj0.iter().chain(obj1.iter()) {
+            color.to_le_bytes().iter().for_each(|byte| self.obj_palettes.write_data(*byte));
+        }
+    }
+
+    pub fn get_mode(&self) -> Mode {
+        self.mode
+    }
+
+    /**
+     * Takes effect from the next line on
+     */
+    pub fn set_renderer(&mut self, renderer: Renderer) {
+        self.renderer = renderer;
+    }
+
+    pub fn get_renderer(&self) -> Renderer {
+        self.renderer
+    }
+
+    pub fn get_ly(&self) -> u8 {
+        self.ly
+    }
+
+    /**
+     * Framebuffer of 2-bit shades, 0 being the lightest. In CGB mode it holds the color index
+     * within the palette instead, see get_color_framebuffer
+     */
+    pub fn get_framebuffer(&self) -> &[u8] {
+        &self.framebuffer
+    }
+
+    /**
+     * BGR555 colors of the last frame, only drawn in CGB and compatibility mode
+     */
+    pub fn get_color_framebuffer(&self) -> &[u16] {
+        &self.color_framebuffer
+    }
+
+    /**
+     * Framebuffer converted to packed 8-bit RGB triplets
+     */
+    pub fn get_rgb_framebuffer(&self) -> Vec<u8> {
+        if self.is_cgb || self.compat_mode {
+            self.color_framebuffer
+                .iter()
+                .flat_map(|color| color_to_rgb(*color))
+                .collect()
+        } else {
+            self.framebuffer
+                .iter()
+                .flat_map(|shade| shade_to_rgb(*shade))
+                .collect()
+        }
+    }
+
+    /**
+     * Returns the framebuffer once per finished frame, at the start of VBlank
+     */
+    pub fn take_frame(&mut self) -> Option<&[u8]> {
+        if self.frame_ready {
+            self.frame_ready = false;
+            Some(&self.framebuffer)
+        } else {
+            None
+        }
+    }
+
+    /**
+     * Whether a visible line entered HBlank since the last call, HBlank DMA copies a block each time
+     */
+    pub fn take_hblank_started(&mut self) -> bool {
+        std::mem::take(&mut self.hblank_started)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.lcdc & LCDC_ENABLE != 0
+    }
+
+    /**
+     * Advances the ppu by the given amount of dots
+     */
+    pub fn tick(&mut self, cycles: u64, interrupts: &mut InterruptController) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        for _ in 0..cycles {
+            self.step_dot(interrupts);
+        }
+    }
+
+    fn step_dot(&mut self, interrupts: &mut InterruptController) {
+        self.dot += 1;
+
+        if self.dot == DOTS_PER_LINE {
+            self.dot = 0;
+            self.ly = (self.ly + 1) % LINES_PER_FRAME;
+
+            if self.ly == SCREEN_HEIGHT as u8 {
+                self.set_mode(Mode::VBlank);
+                self.window_line = 0;
+                self.frame_ready = true;
+                interrupts.request(Interrupt::VBlank);
+            } else if self.ly < SCREEN_HEIGHT as u8 {
+                self.set_mode(Mode::OamScan);
+            }
+        } else if self.ly < SCREEN_HEIGHT as u8 {
+            if self.dot == OAM_SCAN_DOTS {
+                self.start_drawing();
+            } else if self.mode == Mode::Drawing && self.step_drawing() {
+                self.set_mode(Mode::HBlank);
+                self.hblank_started = true;
+            }
+        }
+
+        self.update_stat_line(interrupts);
+    }
+
+    fn start_drawing(&mut self) {
+        self.line_renderer = self.renderer;
+        if self.line_renderer == Renderer::PixelFifo {
+            self.start_fifo_line();
+        }
+        self.set_mode(Mode::Drawing);
+    }
+
+    /**
+     * Returns true once the line is finished and the ppu can move on to HBlank
+     */
+    fn step_drawing(&mut self) -> bool {
+        match self.line_renderer {
+            Renderer::Scanline => {
+                if self.dot == OAM_SCAN_DOTS + DRAWING_DOTS {
+                    self.render_scanline();
+                    true
+                } else {
+                    false
+                }
+            }
+            Renderer::PixelFifo => self.step_fifo(),
+        }
+    }
+
+    fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+
+    /**
+     * All STAT sources are ORed into a single line and the interrupt fires on its rising edge,
+     * so a source becoming active while another one already is doesn't trigger a second interrupt
+     */
+    fn update_stat_line(&mut self, interrupts: &mut InterruptController) {
+        let line = (self.stat & STAT_LYC_INTERRUPT != 0 && self.ly == self.lyc)
+            || match self.mode {
+                Mode::HBlank => self.stat & STAT_HBLANK_INTERRUPT != 0,
+                Mode::VBlank => self.stat & STAT_VBLANK_INTERRUPT != 0,
+                Mode::OamScan => self.stat & STAT_OAM_INTERRUPT != 0,
+                Mode::Drawing => false,
+            };
+
+        if line && !self.stat_line {
+            interrupts.request(Interrupt::Stat);
+        }
+        self.stat_line = line;
+    }
+
+    fn set_lcdc(&mut self, value: u8) {
+        let was_enabled = self.is_enabled();
+        self.lcdc = value;
+
+        if was_enabled && !self.is_enabled() {
+            //Turning the lcd off resets the scanline and leaves the ppu in HBlank
+            self.ly = 0;
+            self.dot = 0;
+            self.window_line = 0;
+            self.set_mode(Mode::HBlank);
+            self.stat_line = false;
+        } else if !was_enabled && self.is_enabled() {
+            self.set_mode(Mode::OamScan);
+        }
+    }
+
+    /**
+     * VRAM is inaccessible to the cpu while the ppu is drawing
+     */
+    pub fn read_vram(&self, address: u16) -> Option<u8> {
+        if self.mode == Mode::Drawing {
+            return Some(0xFF);
+        }
+        self.vram.read(address)
+    }
+
+    pub fn write_vram(&mut self, address: u16, value: u8) {
+        if self.mode != Mode::Drawing {
+            self.vram.write(address, value);
+        }
+    }
+
+    /**
+     * VRAM DMA writes to the selected bank regardless of the mode
+     */
+    pub fn write_vram_dma(&mut self, address: u16, value: u8) {
+        self.vram.write(address & 0x1FFF, value);
+    }
+
+    /**
+     * OAM is inaccessible to the cpu during OAM scan and drawing
+     */
+    pub fn read_oam(&self, address: u16) -> Option<u8> {
+        if matches!(self.mode, Mode::OamScan | Mode::Drawing) {
+            return Some(0xFF);
+        }
+        self.oam.get(address as usize).copied()
+    }
+
+    /**
+     * OAM DMA writes go through regardless of the mode
+     */
+    pub fn write_oam_dma(&mut self, address: u8, value: u8) {
+        if let Some(byte) = self.oam.get_mut(address as usize) {
+            *byte = value;
+        }
+    }
+
+    pub fn write_oam(&mut self, address: u16, value: u8) {
+        if !matches!(self.mode, Mode::OamScan | Mode::Drawing) {
+            if let Some(byte) = self.oam.get_mut(address as usize) {
+                *byte = value;
+            }
+        }
+    }
+
+    /**
+     * Palette memory is locked while drawing, like VRAM
+     */
+    fn read_palette_data(&self, palettes: &ColorPalettes) -> u8 {
+        if self.mode == Mode::Drawing {
+            0xFF
+        } else {
+            palettes.read_data()
+        }
+    }
+
+    pub fn read_register(&self, address: u16) -> Option<u8> {
+        match address {
+            0xFF40 => Some(self.lcdc),
+            0xFF41 => {
+                let coincidence = if self.ly == self.lyc { STAT_COINCIDENCE } else { 0 };
+                Some(0x80 | self.stat | coincidence | self.mode as u8)
+            }
+            0xFF42 => Some(self.scy),
+            0xFF43 => Some(self.scx),
+            0xFF44 => Some(self.ly),
+            0xFF45 => Some(self.lyc),
+            0xFF47 => Some(self.bgp),
+            0xFF48 => Some(self.obp0),
+            0xFF49 => Some(self.obp1),
+            0xFF4A => Some(self.wy),
+            0xFF4B => Some(self.wx),
+            0xFF4F if self.is_cgb => Some(0xFE | self.vram.get_current_bank() as u8),
+            0xFF68 if self.is_cgb => Some(self.bg_palettes.read_spec()),
+            0xFF69 if self.is_cgb => Some(self.read_palette_data(&self.bg_palettes)),
+            0xFF6A if self.is_cgb => Some(self.obj_palettes.read_spec()),
+            0xFF6B if self.is_cgb => Some(self.read_palette_data(&self.obj_palettes)),
+            0xFF6C if self.is_cgb => Some(0xFE | self.opri),
+            0xFF4F | 0xFF68..=0xFF6C => Some(0xFF),
+            _ => None,
+        }
+    }
+
+    pub fn write_register(&mut self, address: u16, value: u8, interrupts: &mut InterruptController) {
+        match address {
+            0xFF40 => self.set_lcdc(value),
+            0xFF41 => {
+                //Only the interrupt enable bits are writable
+                self.stat = value & 0b0111_1000;
+            }
+            0xFF42 => self.scy = value,
+            0xFF43 => self.scx = value,
+            //LY is read only
+            0xFF44 => {}
+            0xFF45 => self.lyc = value,
+            0xFF47 => self.bgp = value,
+            0xFF48 => self.obp0 = value,
+            0xFF49 => self.obp1 = value,
+            0xFF4A => self.wy = value,
+            0xFF4B => self.wx = value,
+            0xFF4F if self.is_cgb => self.vram.set_current_bank((value & 0x01) as usize),
+            0xFF68 if self.is_cgb => self.bg_palettes.write_spec(value),
+            0xFF69 if self.is_cgb && self.mode != Mode::Drawing => self.bg_palettes.write_data(value),
+            0xFF6A if self.is_cgb => self.obj_palettes.write_spec(value),
+            0xFF6B if self.is_cgb && self.mode != Mode::Drawing => self.obj_palettes.write_data(value),
+            0xFF6C if self.is_cgb => self.opri = value & 0x01,
+            _ => return,
+        }
+
+        if self.is_enabled() {
+            self.update_stat_line(interrupts);
+        }
+    }
+}
@@ -0,0 +1,67 @@
+/**
+ * CGB color palette memory, eight palettes of four 15-bit colors accessed through an index register
+ * (BCPS/OCPS) and a data register (BCPD/OCPD)
+ */
+pub struct ColorPalettes {
+    data: [u8; 64],
+    index: u8,
+    auto_increment: bool,
+}
+
+impl ColorPalettes {
+    pub fn new() -> Self {
+        ColorPalettes {
+            data: [0; 64],
+            index: 0,
+            auto_increment: false,
+        }
+    }
+
+    pub fn read_spec(&self) -> u8 {
+        (self.auto_increment as u8) << 7 | 0x40 | self.index
+    }
+
+    pub fn write_spec(&mut self, value: u8) {
+        self.auto_increment = value & 0x80 != 0;
+        self.index = value & 0x3F;
+    }
+
+    pub fn read_data(&self) -> u8 {
+        self.data[self.index as usize]
+    }
+
+    /**
+     * Writing the data register advances the index when auto increment is set, reading it doesn't
+     */
+    pub fn write_data(&mut self, value: u8) {
+        self.data[self.index as usize] = value;
+        if self.auto_increment {
+            self.index = (self.index + 1) & 0x3F;
+        }
+    }
+
+    /**
+     * Little endian BGR555 color
+     */
+    pub fn get_color(&self, palette: u8, color: u8) -> u16 {
+        let address = (palette as usize & 0x07) * 8 + (color as usize & 0x03) * 2;
+        u16::from_le_bytes([self.data[address], self.data[address + 1]])
+    }
+}
+
+impl Default for ColorPalettes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/**
+ * Expands a BGR555 color to 8 bits per channel
+ */
+pub fn color_to_rgb(color: u16) -> [u8; 3] {
+    let expand = |channel: u16| {
+        let channel = (channel & 0x1F) as u8;
+        channel << 3 | channel >> 2
+    };
+    [expand(color), expand(color >> 5), expand(color >> 10)]
+}
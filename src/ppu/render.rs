@@ -0,0 +1,325 @@
+use super::{Ppu, SCREEN_WIDTH};
+
+pub(super) const LCDC_BG_ENABLE: u8 = 0b0000_0001;
+pub(super) const LCDC_OBJ_ENABLE: u8 = 0b0000_0010;
+const LCDC_OBJ_SIZE: u8 = 0b0000_0100;
+pub(super) const LCDC_BG_MAP: u8 = 0b0000_1000;
+const LCDC_TILE_DATA: u8 = 0b0001_0000;
+pub(super) const LCDC_WINDOW_ENABLE: u8 = 0b0010_0000;
+pub(super) const LCDC_WINDOW_MAP: u8 = 0b0100_0000;
+
+//Shared by the OBJ attributes and the CGB background map attributes
+pub(super) const ATTRIBUTE_PRIORITY: u8 = 0b1000_0000;
+pub(super) const ATTRIBUTE_Y_FLIP: u8 = 0b0100_0000;
+pub(super) const ATTRIBUTE_X_FLIP: u8 = 0b0010_0000;
+const OBJ_DMG_PALETTE: u8 = 0b0001_0000;
+pub(super) const ATTRIBUTE_BANK: u8 = 0b0000_1000;
+pub(super) const ATTRIBUTE_CGB_PALETTE: u8 = 0b0000_0111;
+
+const MAX_SPRITES_PER_LINE: usize = 10;
+
+/**
+ * Grey levels used to turn the 2-bit shades into RGB
+ */
+pub const DMG_SHADES: [[u8; 3]; 4] = [
+    [0xFF, 0xFF, 0xFF],
+    [0xAA, 0xAA, 0xAA],
+    [0x55, 0x55, 0x55],
+    [0x00, 0x00, 0x00],
+];
+
+#[derive(Clone, Copy, Default)]
+pub(super) struct BgPixel {
+    pub color: u8,
+    pub palette: u8,
+    pub priority: bool,
+}
+
+/**
+ * Sprite pixel that won the priority check for a given column. The palette is the OBP register
+ * number on DMG and the color palette number on CGB
+ */
+#[derive(Clone, Copy)]
+pub(super) struct SpritePixel {
+    pub color: u8,
+    pub palette: u8,
+    pub behind_bg: bool,
+}
+
+#[derive(Clone, Copy)]
+pub(super) struct Sprite {
+    pub index: usize,
+    pub y: u8,
+    pub x: u8,
+    pub tile: u8,
+    pub attributes: u8,
+}
+
+pub fn shade_to_rgb(shade: u8) -> [u8; 3] {
+    DMG_SHADES[(shade & 0b11) as usize]
+}
+
+/**
+ * Maps a 2-bit color index through a BGP/OBP style palette register
+ */
+fn apply_palette(palette: u8, color: u8) -> u8 {
+    (palette >> (color * 2)) & 0b11
+}
+
+impl Ppu {
+    /**
+     * Draws the current line into the framebuffer, called once the drawing mode of the line ends
+     */
+    pub(super) fn render_scanline(&mut self) {
+        let mut bg = [BgPixel::default(); SCREEN_WIDTH];
+
+        //On CGB the bit doesn't hide the background, it takes away its priority over sprites
+        if self.is_cgb || self.lcdc & LCDC_BG_ENABLE != 0 {
+            self.render_background(&mut bg);
+            self.render_window(&mut bg);
+        }
+
+        let sprites = if self.lcdc & LCDC_OBJ_ENABLE != 0 {
+            self.render_sprites()
+        } else {
+            [None; SCREEN_WIDTH]
+        };
+
+        for (x, (bg, sprite)) in bg.iter().zip(sprites.iter()).enumerate() {
+            self.output_pixel(x, *bg, *sprite);
+        }
+    }
+
+    /**
+     * Resolves the priority between the background and the winning sprite of a column and writes the result
+     */
+    pub(super) fn output_pixel(&mut self, x: usize, bg: BgPixel, sprite: Option<SpritePixel>) {
+        let sprite = sprite.filter(|sprite| self.is_sprite_visible(sprite, &bg));
+        let position = self.ly as usize * SCREEN_WIDTH + x;
+
+        if self.is_cgb {
+            let (color, rgb) = match sprite {
+                Some(sprite) => (
+                    sprite.color,
+                    self.obj_palettes.get_color(sprite.palette, sprite.color),
+                ),
+                None => (bg.color, self.bg_palettes.get_color(bg.palette, bg.color)),
+            };
+            self.framebuffer[position] = color;
+            self.color_framebuffer[position] = rgb;
+        } else {
+            //With LCDC bit 0 clear the background is plain white, BGP doesn't apply
+            let shade = match sprite {
+                Some(sprite) => self.get_sprite_shade(&sprite),
+                None if self.lcdc & LCDC_BG_ENABLE == 0 => 0,
+                None => apply_palette(self.bgp, bg.color),
+            };
+            self.framebuffer[position] = shade;
+
+            if self.compat_mode {
+                self.color_framebuffer[position] = match sprite {
+                    Some(sprite) => self.obj_palettes.get_color(sprite.palette, shade),
+                    None => self.bg_palettes.get_color(0, shade),
+                };
+            }
+        }
+    }
+
+    /**
+     * Color 0 of the background never hides a sprite. Otherwise on CGB LCDC bit 0 clear gives sprites
+     * priority over everything, and either the map attribute or the OBJ attribute can push them behind
+     */
+    fn is_sprite_visible(&self, sprite: &SpritePixel, bg: &BgPixel) -> bool {
+        if bg.color == 0 {
+            return true;
+        }
+        if self.is_cgb && self.lcdc & LCDC_BG_ENABLE == 0 {
+            return true;
+        }
+        !(sprite.behind_bg || bg.priority)
+    }
+
+    fn get_sprite_shade(&self, sprite: &SpritePixel) -> u8 {
+        let palette = if sprite.palette == 1 { self.obp1 } else { self.obp0 };
+        apply_palette(palette, sprite.color)
+    }
+
+    fn render_background(&self, bg: &mut [BgPixel; SCREEN_WIDTH]) {
+        let map = if self.lcdc & LCDC_BG_MAP != 0 { 0x1C00 } else { 0x1800 };
+        let y = self.ly.wrapping_add(self.scy);
+
+        for (x, pixel) in bg.iter_mut().enumerate() {
+            let x = (x as u8).wrapping_add(self.scx);
+            *pixel = self.get_map_pixel(map, x, y);
+        }
+    }
+
+    /**
+     * The window keeps its own line counter, which only advances on lines where it was actually drawn
+     */
+    fn render_window(&mut self, bg: &mut [BgPixel; SCREEN_WIDTH]) {
+        if self.lcdc & LCDC_WINDOW_ENABLE == 0 || self.ly < self.wy || self.wx > 166 {
+            return;
+        }
+
+        let map = if self.lcdc & LCDC_WINDOW_MAP != 0 { 0x1C00 } else { 0x1800 };
+        let start = self.wx as i16 - 7;
+
+        for (x, pixel) in bg.iter_mut().enumerate() {
+            let window_x = x as i16 - start;
+            if window_x >= 0 {
+                *pixel = self.get_map_pixel(map, window_x as u8, self.window_line);
+            }
+        }
+
+        self.window_line += 1;
+    }
+
+    /**
+     * On CGB the map entry in VRAM bank 1 holds the attributes of the tile stored in bank 0
+     */
+    fn get_map_pixel(&self, map: u16, x: u8, y: u8) -> BgPixel {
+        let (tile, attributes) = self.get_map_entry(map + (y as u16 / 8) * 32 + x as u16 / 8);
+
+        let tile_x = if attributes & ATTRIBUTE_X_FLIP != 0 { 7 - x % 8 } else { x % 8 };
+        let tile_y = if attributes & ATTRIBUTE_Y_FLIP != 0 { 7 - y % 8 } else { y % 8 };
+        let bank = (attributes & ATTRIBUTE_BANK != 0) as usize;
+
+        BgPixel {
+            color: self.get_tile_pixel(bank, self.get_bg_tile_address(tile), tile_x, tile_y),
+            palette: attributes & ATTRIBUTE_CGB_PALETTE,
+            priority: attributes & ATTRIBUTE_PRIORITY != 0,
+        }
+    }
+
+    /**
+     * Tile number and, on CGB, the attributes stored at the same position of VRAM bank 1
+     */
+    pub(super) fn get_map_entry(&self, map_address: u16) -> (u8, u8) {
+        let tile = self.vram.read_from_bank(0, map_address).unwrap_or(0);
+        let attributes = if self.is_cgb {
+            self.vram.read_from_bank(1, map_address).unwrap_or(0)
+        } else {
+            0
+        };
+        (tile, attributes)
+    }
+
+    /**
+     * With LCDC bit 4 clear tiles are indexed with a signed number relative to 0x9000
+     */
+    pub(super) fn get_bg_tile_address(&self, tile: u8) -> u16 {
+        if self.lcdc & LCDC_TILE_DATA != 0 {
+            tile as u16 * 16
+        } else {
+            (0x1000 + (tile as i8 as i16) * 16) as u16
+        }
+    }
+
+    fn get_tile_pixel(&self, bank: usize, tile_address: u16, x: u8, y: u8) -> u8 {
+        let row_address = tile_address + y as u16 * 2;
+        let low = self.vram.read_from_bank(bank, row_address).unwrap_or(0);
+        let high = self.vram.read_from_bank(bank, row_address + 1).unwrap_or(0);
+        let bit = 7 - x;
+        ((high >> bit) & 1) << 1 | ((low >> bit) & 1)
+    }
+
+    /**
+     * OAM scan picks the first 10 sprites in OAM order that overlap the line
+     */
+    pub(super) fn scan_oam(&self) -> Vec<Sprite> {
+        let height = self.get_sprite_height();
+
+        self.oam
+            .chunks(4)
+            .enumerate()
+            .map(|(index, entry)| Sprite {
+                index,
+                y: entry[0],
+                x: entry[1],
+                tile: entry[2],
+                attributes: entry[3],
+            })
+            .filter(|sprite| {
+                let line = self.ly as u16 + 16;
+                line >= sprite.y as u16 && line < sprite.y as u16 + height as u16
+            })
+            .take(MAX_SPRITES_PER_LINE)
+            .collect()
+    }
+
+    fn get_sprite_height(&self) -> u8 {
+        if self.lcdc & LCDC_OBJ_SIZE != 0 {
+            16
+        } else {
+            8
+        }
+    }
+
+    /**
+     * On DMG the sprite with the smaller X wins, ties go to the one earlier in OAM. CGB only looks at
+     * the OAM position unless OPRI asks for the DMG behaviour
+     */
+    pub(super) fn has_oam_priority(&self) -> bool {
+        self.is_cgb && self.opri & 0x01 == 0
+    }
+
+    /**
+     * A winning sprite hides the ones below it even when it's itself hidden behind the background
+     */
+    fn render_sprites(&self) -> [Option<SpritePixel>; SCREEN_WIDTH] {
+        let mut sprites = self.scan_oam();
+        if !self.has_oam_priority() {
+            sprites.sort_by_key(|sprite| (sprite.x, sprite.index));
+        }
+
+        let mut pixels = [None; SCREEN_WIDTH];
+
+        for sprite in sprites {
+            for (column, pixel) in self.get_sprite_row(&sprite).iter().enumerate() {
+                let x = sprite.x as i16 - 8 + column as i16;
+                if (0..SCREEN_WIDTH as i16).contains(&x) && pixels[x as usize].is_none() {
+                    pixels[x as usize] = *pixel;
+                }
+            }
+        }
+
+        pixels
+    }
+
+    /**
+     * Pixels of the sprite on the current line from left to right, None where it's transparent
+     */
+    pub(super) fn get_sprite_row(&self, sprite: &Sprite) -> [Option<SpritePixel>; 8] {
+        let height = self.get_sprite_height();
+        let mut row = (self.ly + 16 - sprite.y) % height;
+        if sprite.attributes & ATTRIBUTE_Y_FLIP != 0 {
+            row = height - 1 - row;
+        }
+
+        let tile = if height == 16 { sprite.tile & 0xFE } else { sprite.tile };
+        let (bank, palette) = if self.is_cgb {
+            (
+                (sprite.attributes & ATTRIBUTE_BANK != 0) as usize,
+                sprite.attributes & ATTRIBUTE_CGB_PALETTE,
+            )
+        } else {
+            (0, (sprite.attributes & OBJ_DMG_PALETTE != 0) as u8)
+        };
+
+        let mut pixels = [None; 8];
+        for (column, pixel) in pixels.iter_mut().enumerate() {
+            let column = column as u8;
+            let tile_x = if sprite.attributes & ATTRIBUTE_X_FLIP != 0 { 7 - column } else { column };
+            let color = self.get_tile_pixel(bank, tile as u16 * 16, tile_x, row);
+            if color != 0 {
+                *pixel = Some(SpritePixel {
+                    color,
+                    palette,
+                    behind_bg: sprite.attributes & ATTRIBUTE_PRIORITY != 0,
+                });
+            }
+        }
+        pixels
+    }
+}
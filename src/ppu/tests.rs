@@ -0,0 +1,625 @@
+#![cfg(test)]
+#![allow(clippy::bool_assert_comparison)]
+
+use crate::interrupts::{Interrupt, InterruptController};
+
+use super::{CompatPalettes, Mode, Ppu, Renderer};
+
+fn enabled_ppu(interrupts: &mut InterruptController) -> Ppu {
+    let mut ppu = Ppu::new(false);
+    ppu.write_register(0xFF40, 0x80, interrupts);
+    ppu
+}
+
+#[test]
+fn test_scanline_modes() {
+    let mut interrupts = InterruptController::new();
+    let mut ppu = enabled_ppu(&mut interrupts);
+
+    assert_eq!(ppu.get_mode(), Mode::OamScan);
+
+    ppu.tick(80, &mut interrupts);
+    assert_eq!(ppu.get_mode(), Mode::Drawing);
+
+    ppu.tick(172, &mut interrupts);
+    assert_eq!(ppu.get_mode(), Mode::HBlank);
+    //LY and LYC are both 0, so the coincidence bit is set
+    assert_eq!(ppu.read_register(0xFF41), Some(0x84));
+
+    ppu.tick(204, &mut interrupts);
+    assert_eq!(ppu.get_mode(), Mode::OamScan);
+    assert_eq!(ppu.get_ly(), 1);
+}
+
+#[test]
+fn test_vblank_interrupt_and_frame() {
+    let mut interrupts = InterruptController::new();
+    let mut ppu = enabled_ppu(&mut interrupts);
+
+    ppu.tick(456 * 144 - 1, &mut interrupts);
+    assert_eq!(interrupts.is_requested(Interrupt::VBlank), false);
+    assert!(ppu.take_frame().is_none());
+
+    ppu.tick(1, &mut interrupts);
+    assert_eq!(ppu.get_mode(), Mode::VBlank);
+    assert_eq!(ppu.read_register(0xFF44), Some(144));
+    assert_eq!(interrupts.is_requested(Interrupt::VBlank), true);
+    assert_eq!(ppu.take_frame().map(|frame| frame.len()), Some(160 * 144));
+    assert!(ppu.take_frame().is_none());
+
+    //The frame wraps around after 154 lines
+    ppu.tick(456 * 10, &mut interrupts);
+    assert_eq!(ppu.get_ly(), 0);
+    assert_eq!(ppu.get_mode(), Mode::OamScan);
+}
+
+#[test]
+fn test_lyc_stat_interrupt() {
+    let mut interrupts = InterruptController::new();
+    let mut ppu = enabled_ppu(&mut interrupts);
+    ppu.write_register(0xFF45, 2, &mut interrupts);
+    ppu.write_register(0xFF41, 0x40, &mut interrupts);
+
+    ppu.tick(456, &mut interrupts);
+    assert_eq!(interrupts.is_requested(Interrupt::Stat), false);
+
+    ppu.tick(456, &mut interrupts);
+    assert_eq!(interrupts.is_requested(Interrupt::Stat), true);
+    assert_eq!(ppu.read_register(0xFF41).unwrap() & 0x04, 0x04);
+}
+
+#[test]
+fn test_stat_interrupt_blocking() {
+    let mut interrupts = InterruptController::new();
+    let mut ppu = enabled_ppu(&mut interrupts);
+    ppu.write_register(0xFF41, 0x28, &mut interrupts);
+    interrupts.clear(Interrupt::Stat);
+
+    //HBlank raises the line
+    ppu.tick(252, &mut interrupts);
+    assert_eq!(interrupts.is_requested(Interrupt::Stat), true);
+    interrupts.clear(Interrupt::Stat);
+
+    //OAM scan right after HBlank keeps the line high, so there is no new edge
+    ppu.tick(204, &mut interrupts);
+    assert_eq!(ppu.get_mode(), Mode::OamScan);
+    assert_eq!(interrupts.is_requested(Interrupt::Stat), false);
+}
+
+#[test]
+fn test_lcd_off_resets_ly() {
+    let mut interrupts = InterruptController::new();
+    let mut ppu = enabled_ppu(&mut interrupts);
+
+    ppu.tick(456 * 5 + 100, &mut interrupts);
+    assert_eq!(ppu.get_ly(), 5);
+
+    ppu.write_register(0xFF40, 0x00, &mut interrupts);
+    assert_eq!(ppu.get_ly(), 0);
+    assert_eq!(ppu.get_mode(), Mode::HBlank);
+
+    ppu.tick(1000, &mut interrupts);
+    assert_eq!(ppu.get_ly(), 0);
+}
+
+#[test]
+fn test_vram_and_oam_blocking() {
+    let mut interrupts = InterruptController::new();
+    let mut ppu = Ppu::new(false);
+
+    ppu.write_vram(0x0000, 0x12);
+    ppu.write_oam(0x0000, 0x34);
+    ppu.write_register(0xFF40, 0x80, &mut interrupts);
+
+    assert_eq!(ppu.read_oam(0x0000), Some(0xFF));
+    assert_eq!(ppu.read_vram(0x0000), Some(0x12));
+
+    ppu.tick(80, &mut interrupts);
+    assert_eq!(ppu.read_vram(0x0000), Some(0xFF));
+    ppu.write_vram(0x0000, 0x56);
+
+    ppu.tick(172, &mut interrupts);
+    assert_eq!(ppu.read_vram(0x0000), Some(0x12));
+    assert_eq!(ppu.read_oam(0x0000), Some(0x34));
+}
+
+/**
+ * Writes a tile whose pixels all have the given color
+ */
+fn write_solid_tile(ppu: &mut Ppu, tile_address: u16, color: u8) {
+    let low = if color & 1 != 0 { 0xFF } else { 0x00 };
+    let high = if color & 2 != 0 { 0xFF } else { 0x00 };
+    for row in 0..8 {
+        ppu.write_vram(tile_address + row * 2, low);
+        ppu.write_vram(tile_address + row * 2 + 1, high);
+    }
+}
+
+fn write_sprite(ppu: &mut Ppu, index: u16, y: u8, x: u8, tile: u8, attributes: u8) {
+    for (offset, value) in [y, x, tile, attributes].iter().enumerate() {
+        ppu.write_oam(index * 4 + offset as u16, *value);
+    }
+}
+
+/**
+ * Turns the lcd on with the given LCDC and runs until the first line has been drawn
+ */
+fn render_first_line(ppu: &mut Ppu, lcdc: u8) -> Vec<u8> {
+    let mut interrupts = InterruptController::new();
+    ppu.write_register(0xFF47, 0b11_10_01_00, &mut interrupts);
+    ppu.write_register(0xFF48, 0b11_10_01_00, &mut interrupts);
+    ppu.write_register(0xFF40, lcdc, &mut interrupts);
+    ppu.tick(456, &mut interrupts);
+    ppu.get_framebuffer()[..160].to_vec()
+}
+
+#[test]
+fn test_background_unsigned_tile_data() {
+    let mut ppu = Ppu::new(false);
+    write_solid_tile(&mut ppu, 0x0010, 2);
+    //Second column of the map points to tile 1
+    ppu.write_vram(0x1801, 1);
+
+    let line = render_first_line(&mut ppu, 0x91);
+
+    assert_eq!(line[0..8], [0; 8]);
+    assert_eq!(line[8..16], [2; 8]);
+}
+
+#[test]
+fn test_background_signed_tile_data_and_scroll() {
+    let mut interrupts = InterruptController::new();
+    let mut ppu = Ppu::new(false);
+    //Tile -1 lives right below 0x9000
+    write_solid_tile(&mut ppu, 0x0FF0, 3);
+    ppu.write_vram(0x1C01, 0xFF);
+    ppu.write_register(0xFF43, 4, &mut interrupts);
+
+    let line = render_first_line(&mut ppu, 0x89);
+
+    assert_eq!(line[0..4], [0; 4]);
+    assert_eq!(line[4..12], [3; 8]);
+    assert_eq!(line[12], 0);
+}
+
+#[test]
+fn test_bg_palette_and_disable() {
+    let mut interrupts = InterruptController::new();
+    let mut ppu = Ppu::new(false);
+    write_solid_tile(&mut ppu, 0x0000, 1);
+    ppu.write_register(0xFF47, 0b00_00_11_00, &mut interrupts);
+    ppu.write_register(0xFF40, 0x91, &mut interrupts);
+    ppu.tick(456, &mut interrupts);
+    assert_eq!(ppu.get_framebuffer()[0], 3);
+    assert_eq!(ppu.get_rgb_framebuffer()[0..3], [0x00, 0x00, 0x00]);
+
+    //With the background disabled the line is blank
+    let mut ppu = Ppu::new(false);
+    write_solid_tile(&mut ppu, 0x0000, 1);
+    let line = render_first_line(&mut ppu, 0x90);
+    assert_eq!(line, vec![0; 160]);
+}
+
+#[test]
+fn test_bg_disable_ignores_palette() {
+    for renderer in [Renderer::Scanline, Renderer::PixelFifo] {
+        let mut interrupts = InterruptController::new();
+        let mut ppu = Ppu::new(false);
+        ppu.set_renderer(renderer);
+        write_solid_tile(&mut ppu, 0x0000, 3);
+        write_solid_tile(&mut ppu, 0x0010, 1);
+        //Behind the background, but the disabled background counts as color 0
+        write_sprite(&mut ppu, 0, 16, 8, 1, 0x80);
+        ppu.write_register(0xFF47, 0xFF, &mut interrupts);
+        ppu.write_register(0xFF48, 0b11_10_01_00, &mut interrupts);
+        ppu.write_register(0xFF40, 0x92, &mut interrupts);
+        ppu.tick(456, &mut interrupts);
+
+        let line = &ppu.get_framebuffer()[..160];
+        assert_eq!(line[0..8], [1; 8]);
+        assert_eq!(line[8..160], [0; 152]);
+    }
+}
+
+#[test]
+fn test_window_line_counter() {
+    let mut interrupts = InterruptController::new();
+    let mut ppu = Ppu::new(false);
+    write_solid_tile(&mut ppu, 0x0010, 1);
+    write_solid_tile(&mut ppu, 0x0020, 2);
+    //Window map at 0x9C00, first row uses tile 1 and the second row tile 2
+    ppu.write_vram(0x1C00, 1);
+    ppu.write_vram(0x1C20, 2);
+    ppu.write_register(0xFF4A, 10, &mut interrupts);
+    ppu.write_register(0xFF4B, 7 + 80, &mut interrupts);
+    ppu.write_register(0xFF47, 0b11_10_01_00, &mut interrupts);
+    ppu.write_register(0xFF40, 0xF1, &mut interrupts);
+
+    ppu.tick(456 * 11, &mut interrupts);
+    let line = &ppu.get_framebuffer()[10 * 160..11 * 160];
+    assert_eq!(line[79], 0);
+    assert_eq!(line[80], 1);
+
+    //Disabling the window for some lines doesn't advance its line counter
+    ppu.write_register(0xFF40, 0xD1, &mut interrupts);
+    ppu.tick(456 * 20, &mut interrupts);
+    ppu.write_register(0xFF40, 0xF1, &mut interrupts);
+    ppu.tick(456 * 8, &mut interrupts);
+    assert_eq!(ppu.get_framebuffer()[38 * 160 + 80], 2);
+}
+
+#[test]
+fn test_sprite_priority_and_limit() {
+    let mut interrupts = InterruptController::new();
+    let mut ppu = Ppu::new(false);
+    write_solid_tile(&mut ppu, 0x0010, 1);
+    write_solid_tile(&mut ppu, 0x0020, 2);
+    ppu.write_register(0xFF49, 0b00_00_11_00, &mut interrupts);
+
+    //Lower X wins even when it comes later in OAM
+    write_sprite(&mut ppu, 0, 16, 12, 1, 0x00);
+    write_sprite(&mut ppu, 1, 16, 8, 2, 0x00);
+    //Uses OBP1
+    write_sprite(&mut ppu, 2, 16, 40, 1, 0x10);
+    //10 more sprites on the line, only the first 7 of them fit
+    for index in 3..13 {
+        write_sprite(&mut ppu, index, 16, 48 + index as u8 * 8, 2, 0x00);
+    }
+
+    let line = render_first_line(&mut ppu, 0x93);
+
+    assert_eq!(line[0..8], [2; 8]);
+    assert_eq!(line[8..12], [1; 4]);
+    assert_eq!(line[32], 3);
+    assert_eq!(line[119], 2);
+    assert_eq!(line[120], 0);
+}
+
+#[test]
+fn test_sprite_behind_background() {
+    let mut ppu = Ppu::new(false);
+    write_solid_tile(&mut ppu, 0x0010, 2);
+    //Left half of the tile is color 0, right half color 1
+    for row in 0..8 {
+        ppu.write_vram(0x0020 + row * 2, 0x0F);
+    }
+    ppu.write_vram(0x1800, 2);
+    write_sprite(&mut ppu, 0, 16, 8, 1, 0x80);
+
+    let line = render_first_line(&mut ppu, 0x93);
+
+    assert_eq!(line[0..4], [2; 4]);
+    assert_eq!(line[4..8], [1; 4]);
+}
+
+#[test]
+fn test_tall_flipped_sprite() {
+    let mut ppu = Ppu::new(false);
+    //Tile 2 is the top half, tile 3 the bottom one
+    write_solid_tile(&mut ppu, 0x0030, 3);
+    for row in 0..8 {
+        ppu.write_vram(0x0020 + row * 2, 0x80);
+    }
+    //Odd tile numbers are rounded down in 8x16 mode
+    write_sprite(&mut ppu, 0, 16, 8, 3, 0x20);
+
+    let line = render_first_line(&mut ppu, 0x97);
+    assert_eq!(line[0..8], [0, 0, 0, 0, 0, 0, 0, 1]);
+
+    let mut ppu = Ppu::new(false);
+    write_solid_tile(&mut ppu, 0x0030, 3);
+    write_sprite(&mut ppu, 0, 16, 8, 2, 0x40);
+
+    let line = render_first_line(&mut ppu, 0x97);
+    assert_eq!(line[0..8], [3; 8]);
+}
+
+/**
+ * Fills color palette memory through the auto incrementing data register
+ */
+fn write_palette(ppu: &mut Ppu, spec_register: u16, palette: u8, colors: [u16; 4]) {
+    let mut interrupts = InterruptController::new();
+    ppu.write_register(spec_register, 0x80 | (palette * 8), &mut interrupts);
+    for color in colors {
+        for byte in color.to_le_bytes() {
+            ppu.write_register(spec_register + 1, byte, &mut interrupts);
+        }
+    }
+}
+
+#[test]
+fn test_cgb_palette_registers() {
+    let mut interrupts = InterruptController::new();
+    let mut ppu = Ppu::new(true);
+
+    ppu.write_register(0xFF68, 0x80 | 0x3F, &mut interrupts);
+    ppu.write_register(0xFF69, 0x12, &mut interrupts);
+    //The index wraps around after the last byte
+    assert_eq!(ppu.read_register(0xFF68), Some(0xC0));
+
+    ppu.write_register(0xFF68, 0x3F, &mut interrupts);
+    assert_eq!(ppu.read_register(0xFF69), Some(0x12));
+    ppu.write_register(0xFF69, 0x34, &mut interrupts);
+    //No auto increment
+    assert_eq!(ppu.read_register(0xFF68), Some(0x7F));
+    assert_eq!(ppu.read_register(0xFF69), Some(0x34));
+
+    write_palette(&mut ppu, 0xFF6A, 7, [0x0000, 0x001F, 0x03E0, 0x7C00]);
+    ppu.write_register(0xFF6A, 7 * 8 + 2, &mut interrupts);
+    assert_eq!(ppu.read_register(0xFF6B), Some(0x1F));
+
+    let dmg = Ppu::new(false);
+    assert_eq!(dmg.read_register(0xFF69), Some(0xFF));
+    assert_eq!(dmg.read_register(0xFF4F), Some(0xFF));
+}
+
+#[test]
+fn test_cgb_vram_bank_select() {
+    let mut interrupts = InterruptController::new();
+    let mut ppu = Ppu::new(true);
+
+    ppu.write_vram(0x0000, 0x11);
+    ppu.write_register(0xFF4F, 0x01, &mut interrupts);
+    assert_eq!(ppu.read_register(0xFF4F), Some(0xFF));
+    assert_eq!(ppu.read_vram(0x0000), Some(0x00));
+    ppu.write_vram(0x0000, 0x22);
+
+    ppu.write_register(0xFF4F, 0x00, &mut interrupts);
+    assert_eq!(ppu.read_register(0xFF4F), Some(0xFE));
+    assert_eq!(ppu.read_vram(0x0000), Some(0x11));
+}
+
+#[test]
+fn test_cgb_background_attributes() {
+    let mut interrupts = InterruptController::new();
+    let mut ppu = Ppu::new(true);
+    write_palette(&mut ppu, 0xFF68, 3, [0x7FFF, 0x001F, 0x03E0, 0x7C00]);
+
+    //Tile 1 in bank 1 has its leftmost column set to color 2
+    ppu.write_register(0xFF4F, 0x01, &mut interrupts);
+    for row in 0..8 {
+        ppu.write_vram(0x0010 + row * 2 + 1, 0x80);
+    }
+    //First map entry uses palette 3, bank 1 and is flipped horizontally
+    ppu.write_vram(0x1800, 0b0010_1011);
+    ppu.write_register(0xFF4F, 0x00, &mut interrupts);
+    ppu.write_vram(0x1800, 1);
+
+    ppu.write_register(0xFF40, 0x91, &mut interrupts);
+    ppu.tick(456, &mut interrupts);
+
+    assert_eq!(ppu.get_framebuffer()[0..8], [0, 0, 0, 0, 0, 0, 0, 2]);
+    assert_eq!(ppu.get_color_framebuffer()[7], 0x03E0);
+    assert_eq!(ppu.get_rgb_framebuffer()[21..24], [0x00, 0xFF, 0x00]);
+}
+
+#[test]
+fn test_cgb_sprite_priority() {
+    let mut interrupts = InterruptController::new();
+    let mut ppu = Ppu::new(true);
+    write_palette(&mut ppu, 0xFF6A, 0, [0, 0x0001, 0x0002, 0x0003]);
+    write_palette(&mut ppu, 0xFF6A, 1, [0, 0x0011, 0x0012, 0x0013]);
+    write_palette(&mut ppu, 0xFF68, 0, [0, 0x0021, 0x0022, 0x0023]);
+    write_solid_tile(&mut ppu, 0x0010, 1);
+    write_solid_tile(&mut ppu, 0x0020, 1);
+
+    //Background tile 2 with the priority attribute on the second column of the map
+    ppu.write_vram(0x1801, 2);
+    ppu.write_register(0xFF4F, 0x01, &mut interrupts);
+    ppu.write_vram(0x1801, 0x80);
+    ppu.write_register(0xFF4F, 0x00, &mut interrupts);
+
+    //OAM order wins over X on CGB
+    write_sprite(&mut ppu, 0, 16, 12, 1, 0x00);
+    write_sprite(&mut ppu, 1, 16, 8, 1, 0x01);
+
+    ppu.write_register(0xFF40, 0x93, &mut interrupts);
+    ppu.tick(456, &mut interrupts);
+
+    assert_eq!(ppu.get_color_framebuffer()[0..4], [0x0011; 4]);
+    assert_eq!(ppu.get_color_framebuffer()[4..8], [0x0001; 4]);
+    //The map attribute keeps the background on top
+    assert_eq!(ppu.get_color_framebuffer()[8..12], [0x0021; 4]);
+
+    //Clearing LCDC bit 0 gives sprites priority again
+    ppu.write_register(0xFF40, 0x92, &mut interrupts);
+    ppu.tick(456, &mut interrupts);
+    assert_eq!(ppu.get_color_framebuffer()[160 + 8..160 + 12], [0x0001; 4]);
+}
+
+/**
+ * Scene with scrolling, the window, flipped CGB tiles and overlapping sprites
+ */
+fn build_scene(is_cgb: bool, renderer: Renderer) -> Ppu {
+    let mut interrupts = InterruptController::new();
+    let mut ppu = Ppu::new(is_cgb);
+    ppu.set_renderer(renderer);
+
+    for tile in 0..4u16 {
+        for row in 0..8u16 {
+            ppu.write_vram(tile * 16 + row * 2, (tile * 37 + row * 11) as u8);
+            ppu.write_vram(tile * 16 + row * 2 + 1, (tile * 59 + row * 23) as u8);
+        }
+    }
+    for entry in 0..0x800u16 {
+        ppu.write_vram(0x1800 + entry, (entry % 4) as u8);
+    }
+    if is_cgb {
+        ppu.write_register(0xFF4F, 0x01, &mut interrupts);
+        for entry in 0..0x800u16 {
+            ppu.write_vram(0x1800 + entry, (entry % 7) as u8 | ((entry % 3) as u8) << 5);
+        }
+        ppu.write_register(0xFF4F, 0x00, &mut interrupts);
+        for palette in 0..8 {
+            let base = palette as u16 * 0x0421;
+            write_palette(&mut ppu, 0xFF68, palette, [base, base + 1, base + 2, base + 3]);
+            write_palette(&mut ppu, 0xFF6A, palette, [0, base + 0x11, base + 0x12, base + 0x13]);
+        }
+    }
+
+    write_sprite(&mut ppu, 0, 20, 30, 1, 0x20);
+    write_sprite(&mut ppu, 1, 24, 26, 2, 0x91);
+    write_sprite(&mut ppu, 2, 30, 100, 3, 0x40);
+    write_sprite(&mut ppu, 3, 16, 4, 1, 0x00);
+
+    ppu.write_register(0xFF42, 3, &mut interrupts);
+    ppu.write_register(0xFF43, 13, &mut interrupts);
+    ppu.write_register(0xFF4A, 40, &mut interrupts);
+    ppu.write_register(0xFF4B, 90, &mut interrupts);
+    ppu.write_register(0xFF47, 0b00_01_10_11, &mut interrupts);
+    ppu.write_register(0xFF48, 0b11_10_01_00, &mut interrupts);
+    ppu.write_register(0xFF49, 0b01_11_10_00, &mut interrupts);
+    ppu.write_register(0xFF40, 0xF3, &mut interrupts);
+    ppu.tick(456 * 154, &mut interrupts);
+    ppu
+}
+
+#[test]
+fn test_pixel_fifo_matches_scanline_renderer() {
+    for is_cgb in [false, true] {
+        let scanline = build_scene(is_cgb, Renderer::Scanline);
+        let fifo = build_scene(is_cgb, Renderer::PixelFifo);
+
+        assert_eq!(scanline.get_framebuffer(), fifo.get_framebuffer());
+        assert_eq!(scanline.get_color_framebuffer(), fifo.get_color_framebuffer());
+    }
+}
+
+#[test]
+fn test_pixel_fifo_window_left_of_screen() {
+    for wx in 0..7 {
+        let [scanline, fifo] = [Renderer::Scanline, Renderer::PixelFifo].map(|renderer| {
+            let mut interrupts = InterruptController::new();
+            let mut ppu = Ppu::new(false);
+            ppu.set_renderer(renderer);
+            write_solid_tile(&mut ppu, 0x0010, 1);
+            write_solid_tile(&mut ppu, 0x0020, 2);
+            //Window tiles alternate so a wrong offset shows up
+            for entry in 0..32u16 {
+                ppu.write_vram(0x1C00 + entry, 1 + (entry % 2) as u8);
+            }
+            ppu.write_register(0xFF4B, wx, &mut interrupts);
+            ppu.write_register(0xFF47, 0b11_10_01_00, &mut interrupts);
+            ppu.write_register(0xFF40, 0xF1, &mut interrupts);
+            ppu.tick(456, &mut interrupts);
+            ppu.get_framebuffer()[..160].to_vec()
+        });
+
+        let wx = wx as usize;
+        assert_eq!(scanline, fifo);
+        assert_eq!(fifo[..=wx], vec![1; wx + 1][..]);
+        assert_eq!(fifo[wx + 1], 2);
+    }
+}
+
+/**
+ * Turns the lcd on and counts the dots the first line spends in mode 3
+ */
+fn measure_drawing_dots(ppu: &mut Ppu, lcdc: u8) -> u64 {
+    let mut interrupts = InterruptController::new();
+    ppu.set_renderer(Renderer::PixelFifo);
+    ppu.write_register(0xFF40, lcdc, &mut interrupts);
+    ppu.tick(80, &mut interrupts);
+
+    let mut dots = 0;
+    while ppu.get_mode() == Mode::Drawing {
+        ppu.tick(1, &mut interrupts);
+        dots += 1;
+    }
+    dots
+}
+
+#[test]
+fn test_pixel_fifo_mode_3_length() {
+    let mut interrupts = InterruptController::new();
+
+    assert_eq!(measure_drawing_dots(&mut Ppu::new(false), 0x93), 172);
+
+    let mut ppu = Ppu::new(false);
+    ppu.write_register(0xFF43, 5, &mut interrupts);
+    assert_eq!(measure_drawing_dots(&mut ppu, 0x93), 177);
+
+    let mut ppu = Ppu::new(false);
+    write_sprite(&mut ppu, 0, 16, 50, 0, 0x00);
+    assert!(measure_drawing_dots(&mut ppu, 0x93) > 172);
+
+    let mut ppu = Ppu::new(false);
+    ppu.write_register(0xFF4B, 7 + 40, &mut interrupts);
+    assert!(measure_drawing_dots(&mut ppu, 0xB3) > 172);
+}
+
+#[test]
+fn test_pixel_fifo_mid_scanline_palette_change() {
+    let mut interrupts = InterruptController::new();
+    let mut ppu = Ppu::new(false);
+    ppu.set_renderer(Renderer::PixelFifo);
+    write_solid_tile(&mut ppu, 0x0000, 1);
+    ppu.write_register(0xFF47, 0b00_00_01_00, &mut interrupts);
+    ppu.write_register(0xFF40, 0x91, &mut interrupts);
+
+    //Halfway through mode 3 the palette changes, only the remaining pixels use it
+    ppu.tick(80 + 12 + 80, &mut interrupts);
+    ppu.write_register(0xFF47, 0b00_00_11_00, &mut interrupts);
+    ppu.tick(456 - 80 - 12 - 80, &mut interrupts);
+
+    let line = &ppu.get_framebuffer()[..160];
+    assert_eq!(line[0], 1);
+    assert_eq!(line[159], 3);
+    assert_eq!(line.iter().filter(|shade| **shade == 1).count(), 80);
+}
+
+#[test]
+fn test_compat_mode_palettes() {
+    let mut ppu = Ppu::new(true);
+    ppu.set_compat_mode();
+    ppu.set_compat_palettes(
+        [0x7FFF, 0x001F, 0x03E0, 0x7C00],
+        [0x0000; 4],
+        [0x0000; 4],
+    );
+    write_solid_tile(&mut ppu, 0x0000, 1);
+    write_solid_tile(&mut ppu, 0x0010, 3);
+    ppu.write_vram(0x1801, 1);
+
+    //BGP maps color 1 to shade 1 and color 3 to shade 3, the shades pick the colors of palette 0
+    let line = render_first_line(&mut ppu, 0x91);
+    assert_eq!(line[0], 1);
+    assert_eq!(ppu.get_color_framebuffer()[0], 0x001F);
+    assert_eq!(ppu.get_color_framebuffer()[8], 0x7C00);
+    assert_eq!(ppu.get_rgb_framebuffer()[0..3], [0xFF, 0x00, 0x00]);
+
+    //The CGB registers are locked
+    assert_eq!(ppu.read_register(0xFF68), Some(0xFF));
+}
+
+fn compat_title(title: &str) -> [u8; 16] {
+    let mut bytes = [0; 16];
+    bytes[..title.len()].copy_from_slice(title.as_bytes());
+    bytes
+}
+
+#[test]
+fn test_compat_palettes_from_header() {
+    let default = CompatPalettes::from_header(&compat_title("POKEMON RED"), 0x08, *b"00");
+    assert_eq!(default.bg, [0x7FFF, 0x1BEF, 0x6180, 0x0000]);
+    assert_eq!(default.obj0, [0x7FFF, 0x421F, 0x1CF2, 0x0000]);
+    assert_eq!(default.obj1, default.obj0);
+
+    let red = CompatPalettes::from_header(&compat_title("POKEMON RED"), 0x01, *b"00");
+    assert_eq!(red.bg, [0x7FFF, 0x421F, 0x1CF2, 0x0000]);
+    assert_eq!(red.obj0, [0x7FFF, 0x1BEF, 0x0200, 0x0000]);
+
+    //Shares its title sum with SUPER MARIOLAND, the 4th letter tells them apart
+    let blue = CompatPalettes::from_header(&compat_title("POKEMON BLUE"), 0x33, *b"01");
+    assert_eq!(blue.bg, [0x7FFF, 0x7E8C, 0x7C00, 0x0000]);
+    assert_eq!(blue.obj0, [0x7FFF, 0x421F, 0x1CF2, 0x0000]);
+
+    //Matched on the second row of letters
+    let moguranya = CompatPalettes::from_header(&compat_title("MOGURANYA"), 0x01, *b"00");
+    assert_eq!(moguranya.bg, [0x7FFF, 0x42B5, 0x3DC8, 0x0000]);
+    assert_eq!(moguranya.obj0, [0x7FFF, 0x01DF, 0x0112, 0x0000]);
+
+    //Known sum but unknown letter
+    let unknown = CompatPalettes::from_header(&compat_title("MOGXRANYA"), 0x01, *b"00");
+    assert_eq!(unknown.bg, default.bg);
+}
@@ -0,0 +1,73 @@
+use std::{fs, io, path::PathBuf};
+
+use crate::mbc::Mbc;
+
+//Roughly one second of emulated time between flushes
+const FLUSH_INTERVAL: u64 = 4_194_304;
+
+/**
+ * Keeps the battery backed contents of the cartridge in sync with a .sav file on disk.
+ * The file is flushed periodically while running, so a crash loses at most a second of progress
+ */
+pub struct SaveFile {
+    path: PathBuf,
+    cycles_since_flush: u64,
+    last_saved: Vec<u8>,
+}
+
+impl SaveFile {
+    pub fn new(path: PathBuf) -> Self {
+        SaveFile {
+            path,
+            cycles_since_flush: 0,
+            last_saved: Vec::new(),
+        }
+    }
+
+    /**
+     * Restores the previous contents of the save file into the cartridge, if there is one
+     */
+    pub fn load(&mut self, mbc: &mut dyn Mbc) {
+        if let Some(data) = self.read() {
+            mbc.load_save_data(&data);
+            self.last_saved = mbc.get_save_contents();
+        }
+    }
+
+    /**
+     * Returns the previous contents of the save file, if there is one
+     */
+    pub fn read(&mut self) -> Option<Vec<u8>> {
+        let data = fs::read(&self.path).ok()?;
+        self.last_saved = data.clone();
+        Some(data)
+    }
+
+    pub fn tick(&mut self, cycles: u64, mbc: &dyn Mbc) {
+        self.cycles_since_flush += cycles;
+        if self.cycles_since_flush >= FLUSH_INTERVAL {
+            self.cycles_since_flush = 0;
+            if let Err(error) = self.flush(mbc) {
+                eprintln!("Couldn't write save file {}: {}", self.path.display(), error);
+            }
+        }
+    }
+
+    /**
+     * Writes the save data if it changed since the last flush. The clock timestamp is left out of the
+     * comparison, otherwise cartridges with a clock would be rewritten every second. The data goes to a
+     * temporary file first so an interrupted write never leaves a truncated save behind
+     */
+    pub fn flush(&mut self, mbc: &dyn Mbc) -> io::Result<()> {
+        let contents = mbc.get_save_contents();
+        if contents == self.last_saved {
+            return Ok(());
+        }
+
+        let temporary_path = self.path.with_extension("sav.tmp");
+        fs::write(&temporary_path, mbc.get_save_data())?;
+        fs::rename(&temporary_path, &self.path)?;
+        self.last_saved = contents;
+        Ok(())
+    }
+}
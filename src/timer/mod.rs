@@ -0,0 +1,189 @@
+mod tests;
+
+use crate::interrupts::{Interrupt, InterruptController};
+
+//The APU frame sequencer follows this bit of the system counter, the next one up in double speed
+const FRAME_SEQUENCER_BIT: u16 = 1 << 12;
+const DOUBLE_SPEED_FRAME_SEQUENCER_BIT: u16 = 1 << 13;
+
+/**
+ * TIMA overflow handling: TIMA reads 0 for a M-cycle before TMA is loaded and the interrupt is requested,
+ * and the M-cycle after the reload still belongs to it
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TimaState {
+    Running,
+    Overflowed(u8),
+    Reloading(u8),
+}
+
+/**
+ * DIV, TIMA, TMA and TAC. Everything is driven by the 16-bit system counter, DIV being its upper byte
+ * and TIMA counting the falling edges of the counter bit selected by TAC
+ */
+pub struct Timer {
+    counter: u16,
+    tima: u8,
+    tma: u8,
+    tac: u8,
+    state: TimaState,
+    frame_sequencer_clocks: u32,
+    double_speed: bool,
+}
+
+impl Timer {
+    pub fn new() -> Self {
+        Timer {
+            counter: 0,
+            tima: 0,
+            tma: 0,
+            tac: 0,
+            state: TimaState::Running,
+            frame_sequencer_clocks: 0,
+            double_speed: false,
+        }
+    }
+
+    pub fn get_counter(&self) -> u16 {
+        self.counter
+    }
+
+    /**
+     * Sets the counter without the falling edge checks, used for the state left by the boot rom
+     */
+    pub fn set_initial_counter(&mut self, counter: u16) {
+        self.counter = counter;
+    }
+
+    /**
+     * Number of times the APU frame sequencer has to step since the last call
+     */
+    pub fn take_frame_sequencer_clocks(&mut self) -> u32 {
+        std::mem::take(&mut self.frame_sequencer_clocks)
+    }
+
+    /**
+     * The counter runs at the cpu clock, so in double speed the frame sequencer has to follow a higher
+     * bit to keep its 512 Hz rate
+     */
+    pub fn set_double_speed(&mut self, double_speed: bool) {
+        self.double_speed = double_speed;
+    }
+
+    fn get_frame_sequencer_bit(&self) -> u16 {
+        if self.double_speed {
+            DOUBLE_SPEED_FRAME_SEQUENCER_BIT
+        } else {
+            FRAME_SEQUENCER_BIT
+        }
+    }
+
+    fn get_selected_bit(&self) -> u16 {
+        match self.tac & 0x03 {
+            0 => 1 << 9,
+            1 => 1 << 3,
+            2 => 1 << 5,
+            _ => 1 << 7,
+        }
+    }
+
+    /**
+     * The AND of the enable bit and the selected counter bit, TIMA counts the falling edges of this signal
+     */
+    fn get_timer_signal(&self) -> bool {
+        self.tac & 0x04 != 0 && self.counter & self.get_selected_bit() != 0
+    }
+
+    pub fn tick(&mut self, cycles: u64, interrupts: &mut InterruptController) {
+        for _ in 0..cycles {
+            self.state = match self.state {
+                TimaState::Overflowed(1) => {
+                    self.tima = self.tma;
+                    interrupts.request(Interrupt::Timer);
+                    TimaState::Reloading(4)
+                }
+                TimaState::Overflowed(cycles) => TimaState::Overflowed(cycles - 1),
+                TimaState::Reloading(1) => TimaState::Running,
+                TimaState::Reloading(cycles) => TimaState::Reloading(cycles - 1),
+                TimaState::Running => TimaState::Running,
+            };
+
+            self.set_counter(self.counter.wrapping_add(1));
+        }
+    }
+
+    /**
+     * Every change of the counter goes through here so the falling edges caused by resetting DIV
+     * are seen by TIMA and the frame sequencer too
+     */
+    fn set_counter(&mut self, counter: u16) {
+        let old_signal = self.get_timer_signal();
+        let frame_sequencer_bit = self.get_frame_sequencer_bit();
+        let old_frame_sequencer = self.counter & frame_sequencer_bit != 0;
+
+        self.counter = counter;
+
+        if old_signal && !self.get_timer_signal() {
+            self.increment_tima();
+        }
+        if old_frame_sequencer && self.counter & frame_sequencer_bit == 0 {
+            self.frame_sequencer_clocks += 1;
+        }
+    }
+
+    fn increment_tima(&mut self) {
+        let (tima, overflow) = self.tima.overflowing_add(1);
+        self.tima = tima;
+        if overflow {
+            self.state = TimaState::Overflowed(4);
+        }
+    }
+
+    pub fn read_register(&self, address: u16) -> Option<u8> {
+        match address {
+            0xFF04 => Some((self.counter >> 8) as u8),
+            0xFF05 => Some(self.tima),
+            0xFF06 => Some(self.tma),
+            0xFF07 => Some(0xF8 | self.tac),
+            _ => None,
+        }
+    }
+
+    pub fn write_register(&mut self, address: u16, value: u8) {
+        match address {
+            //Any write resets the whole counter
+            0xFF04 => self.set_counter(0),
+            0xFF05 => match self.state {
+                //Writing during the overflow cycle cancels the reload and the interrupt
+                TimaState::Overflowed(_) => {
+                    self.tima = value;
+                    self.state = TimaState::Running;
+                }
+                //The reload wins over a write on the same cycle
+                TimaState::Reloading(_) => {}
+                TimaState::Running => self.tima = value,
+            },
+            0xFF06 => {
+                self.tma = value;
+                if let TimaState::Reloading(_) = self.state {
+                    self.tima = value;
+                }
+            }
+            0xFF07 => {
+                //Changing the selected bit or disabling the timer can produce a falling edge
+                let old_signal = self.get_timer_signal();
+                self.tac = value & 0x07;
+                if old_signal && !self.get_timer_signal() {
+                    self.increment_tima();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
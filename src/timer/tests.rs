@@ -0,0 +1,162 @@
+#![cfg(test)]
+#![allow(clippy::bool_assert_comparison)]
+
+use crate::interrupts::{Interrupt, InterruptController};
+
+use super::Timer;
+
+#[test]
+fn test_div_increments_and_resets() {
+    let mut interrupts = InterruptController::new();
+    let mut timer = Timer::new();
+
+    timer.tick(255, &mut interrupts);
+    assert_eq!(timer.read_register(0xFF04), Some(0));
+    timer.tick(1, &mut interrupts);
+    assert_eq!(timer.read_register(0xFF04), Some(1));
+
+    timer.tick(256 * 10, &mut interrupts);
+    assert_eq!(timer.read_register(0xFF04), Some(11));
+
+    timer.write_register(0xFF04, 0x55);
+    assert_eq!(timer.read_register(0xFF04), Some(0));
+    assert_eq!(timer.get_counter(), 0);
+}
+
+#[test]
+fn test_tac_frequencies() {
+    for (tac, period) in [(0x04, 1024), (0x05, 16), (0x06, 64), (0x07, 256)] {
+        let mut interrupts = InterruptController::new();
+        let mut timer = Timer::new();
+        timer.write_register(0xFF07, tac);
+
+        timer.tick(period * 3 - 1, &mut interrupts);
+        assert_eq!(timer.read_register(0xFF05), Some(2));
+        timer.tick(1, &mut interrupts);
+        assert_eq!(timer.read_register(0xFF05), Some(3));
+    }
+
+    //Disabled timer
+    let mut interrupts = InterruptController::new();
+    let mut timer = Timer::new();
+    timer.write_register(0xFF07, 0x01);
+    timer.tick(1000, &mut interrupts);
+    assert_eq!(timer.read_register(0xFF05), Some(0));
+    assert_eq!(timer.read_register(0xFF07), Some(0xF9));
+}
+
+#[test]
+fn test_delayed_reload_and_interrupt() {
+    let mut interrupts = InterruptController::new();
+    let mut timer = Timer::new();
+    timer.write_register(0xFF06, 0xAB);
+    timer.write_register(0xFF05, 0xFF);
+    timer.write_register(0xFF07, 0x05);
+
+    timer.tick(16, &mut interrupts);
+    //TIMA stays at 0 for a M-cycle before TMA is loaded
+    assert_eq!(timer.read_register(0xFF05), Some(0x00));
+    assert_eq!(interrupts.is_requested(Interrupt::Timer), false);
+
+    timer.tick(4, &mut interrupts);
+    assert_eq!(timer.read_register(0xFF05), Some(0xAB));
+    assert_eq!(interrupts.is_requested(Interrupt::Timer), true);
+}
+
+#[test]
+fn test_tima_write_cancels_reload() {
+    let mut interrupts = InterruptController::new();
+    let mut timer = Timer::new();
+    timer.write_register(0xFF06, 0xAB);
+    timer.write_register(0xFF05, 0xFF);
+    timer.write_register(0xFF07, 0x05);
+
+    timer.tick(16, &mut interrupts);
+    timer.write_register(0xFF05, 0x10);
+    timer.tick(4, &mut interrupts);
+    assert_eq!(timer.read_register(0xFF05), Some(0x10));
+    assert_eq!(interrupts.is_requested(Interrupt::Timer), false);
+}
+
+#[test]
+fn test_writes_during_reload_cycle() {
+    let mut interrupts = InterruptController::new();
+    let mut timer = Timer::new();
+    timer.write_register(0xFF06, 0xAB);
+    timer.write_register(0xFF05, 0xFF);
+    timer.write_register(0xFF07, 0x05);
+    timer.tick(20, &mut interrupts);
+
+    //TIMA writes are ignored while the reload happens, TMA writes go through to TIMA
+    timer.write_register(0xFF05, 0x10);
+    assert_eq!(timer.read_register(0xFF05), Some(0xAB));
+    timer.write_register(0xFF06, 0xCD);
+    assert_eq!(timer.read_register(0xFF05), Some(0xCD));
+
+    //Once the cycle is over TMA writes don't touch TIMA anymore
+    timer.tick(4, &mut interrupts);
+    timer.write_register(0xFF06, 0x01);
+    assert_eq!(timer.read_register(0xFF05), Some(0xCD));
+}
+
+#[test]
+fn test_div_write_glitch() {
+    let mut interrupts = InterruptController::new();
+    let mut timer = Timer::new();
+    timer.write_register(0xFF07, 0x05);
+
+    //Bit 3 is set, resetting the counter is a falling edge
+    timer.tick(8, &mut interrupts);
+    assert_eq!(timer.read_register(0xFF05), Some(0));
+    timer.write_register(0xFF04, 0x00);
+    assert_eq!(timer.read_register(0xFF05), Some(1));
+
+    //Bit 3 clear, no edge
+    timer.tick(4, &mut interrupts);
+    timer.write_register(0xFF04, 0x00);
+    assert_eq!(timer.read_register(0xFF05), Some(1));
+}
+
+#[test]
+fn test_tac_write_glitch() {
+    let mut interrupts = InterruptController::new();
+    let mut timer = Timer::new();
+    timer.write_register(0xFF07, 0x05);
+    timer.tick(8, &mut interrupts);
+
+    //Disabling the timer while the selected bit is high increments TIMA
+    timer.write_register(0xFF07, 0x01);
+    assert_eq!(timer.read_register(0xFF05), Some(1));
+
+    //So does switching to a bit that is low
+    timer.write_register(0xFF07, 0x05);
+    timer.write_register(0xFF07, 0x06);
+    assert_eq!(timer.read_register(0xFF05), Some(2));
+}
+
+#[test]
+fn test_frame_sequencer_clocks() {
+    let mut interrupts = InterruptController::new();
+    let mut timer = Timer::new();
+
+    timer.tick(8192 * 3, &mut interrupts);
+    assert_eq!(timer.take_frame_sequencer_clocks(), 3);
+    assert_eq!(timer.take_frame_sequencer_clocks(), 0);
+
+    //Resetting DIV with bit 12 set clocks the frame sequencer early
+    timer.tick(4096, &mut interrupts);
+    timer.write_register(0xFF04, 0x00);
+    assert_eq!(timer.take_frame_sequencer_clocks(), 1);
+}
+
+#[test]
+fn test_double_speed_frame_sequencer() {
+    let mut interrupts = InterruptController::new();
+    let mut timer = Timer::new();
+    timer.set_double_speed(true);
+
+    timer.tick(8192, &mut interrupts);
+    assert_eq!(timer.take_frame_sequencer_clocks(), 0);
+    timer.tick(8192, &mut interrupts);
+    assert_eq!(timer.take_frame_sequencer_clocks(), 1);
+}